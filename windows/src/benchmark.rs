@@ -0,0 +1,157 @@
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use glow::HasContext;
+use winit::dpi::PhysicalSize;
+
+use crate::config::Config;
+use crate::postprocess::Effects;
+use crate::renderer::FluxRenderer;
+use crate::still;
+
+// Frames drawn before the timing starts. The first few are slow while the driver compiles
+// shaders and allocates textures, which says nothing about how fast Flux runs afterwards.
+const WARM_UP_FRAMES: u32 = 10;
+
+// Run Flux offscreen as fast as it goes for `duration`, and time each frame. The desktop wallpaper
+// color mode uses the default colors, which take as long to draw.
+//
+// The GL context should be current. Nothing is presented, so there’s no vsync to wait on and the
+// window behind the context can stay hidden. Each frame is waited on before the next one starts,
+// so the frame times include the GPU work and not just the time to queue it.
+pub fn run(
+    gl: &Rc<glow::Context>,
+    config: &Config,
+    size: PhysicalSize<u32>,
+    duration: Duration,
+) -> Result<Results, String> {
+    let mut renderer = FluxRenderer::new(gl, size.width, size.height, config)?;
+
+    unsafe {
+        let texture = gl.create_texture()?;
+        let framebuffer = gl.create_framebuffer()?;
+        let result = still::attach_texture(gl, framebuffer, texture, size).map(|_| {
+            let start = Instant::now();
+            let timestamp = || start.elapsed().as_secs_f64() * 1000.0;
+            for _ in 0..WARM_UP_FRAMES {
+                renderer.render(Some(framebuffer), timestamp(), &Effects::default());
+                gl.finish();
+            }
+
+            let mut frame_times = Vec::new();
+            let measure_start = Instant::now();
+            while frame_times.is_empty() || measure_start.elapsed() < duration {
+                let frame_start = Instant::now();
+                renderer.render(Some(framebuffer), timestamp(), &Effects::default());
+                gl.finish();
+                frame_times.push(frame_start.elapsed());
+            }
+
+            Results::new(size, measure_start.elapsed(), frame_times)
+        });
+
+        gl.delete_framebuffer(framebuffer);
+        gl.delete_texture(texture);
+
+        result
+    }
+}
+
+pub struct Results {
+    size: PhysicalSize<u32>,
+    elapsed: Duration,
+    // Sorted from the fastest frame to the slowest
+    frame_times: Vec<Duration>,
+}
+
+impl Results {
+    fn new(size: PhysicalSize<u32>, elapsed: Duration, mut frame_times: Vec<Duration>) -> Self {
+        frame_times.sort();
+        Self {
+            size,
+            elapsed,
+            frame_times,
+        }
+    }
+
+    // The time that `percent` of the frames took at most.
+    fn percentile(&self, percent: f64) -> Duration {
+        let last = match self.frame_times.len().checked_sub(1) {
+            Some(last) => last,
+            None => return Duration::ZERO,
+        };
+        let rank = (percent / 100.0 * self.frame_times.len() as f64).ceil() as usize;
+        self.frame_times[rank.saturating_sub(1).min(last)]
+    }
+}
+
+impl fmt::Display for Results {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.elapsed.as_secs_f64();
+        let frames = self.frame_times.len();
+        let average_fps = if seconds > 0.0 {
+            frames as f64 / seconds
+        } else {
+            0.0
+        };
+        let line = |f: &mut fmt::Formatter<'_>, label: &str, time: Duration| {
+            let ms = time.as_secs_f64() * 1000.0;
+            let fps = if ms > 0.0 { 1000.0 / ms } else { 0.0 };
+            writeln!(f, "{}: {:.2} ms ({:.0} FPS)", label, ms, fps)
+        };
+
+        writeln!(
+            f,
+            "{} frames at {}x{} in {:.1} s",
+            frames, self.size.width, self.size.height, seconds
+        )?;
+        writeln!(f, "Average: {:.1} FPS", average_fps)?;
+        line(f, "Median frame", self.percentile(50.0))?;
+        line(f, "95th percentile", self.percentile(95.0))?;
+        line(f, "99th percentile", self.percentile(99.0))?;
+        write!(
+            f,
+            "Slowest frame: {:.2} ms",
+            self.percentile(100.0).as_secs_f64() * 1000.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn results(frame_times_ms: impl IntoIterator<Item = u64>) -> Results {
+        let frame_times = frame_times_ms
+            .into_iter()
+            .map(Duration::from_millis)
+            .collect::<Vec<_>>();
+        let elapsed = frame_times.iter().sum();
+        Results::new(PhysicalSize::new(1920, 1080), elapsed, frame_times)
+    }
+
+    #[test]
+    fn it_picks_the_slower_frames_for_the_percentiles() {
+        let results = results((1..=100).rev());
+
+        assert_eq!(results.percentile(50.0), Duration::from_millis(50));
+        assert_eq!(results.percentile(95.0), Duration::from_millis(95));
+        assert_eq!(results.percentile(100.0), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn it_reports_the_frame_rate_and_frame_times() {
+        let results = results([20, 10, 30, 20, 20]);
+
+        assert_eq!(
+            results.to_string(),
+            "5 frames at 1920x1080 in 0.1 s\n\
+             Average: 50.0 FPS\n\
+             Median frame: 20.00 ms (50 FPS)\n\
+             95th percentile: 30.00 ms (33 FPS)\n\
+             99th percentile: 30.00 ms (33 FPS)\n\
+             Slowest frame: 30.00 ms"
+        );
+    }
+}
@@ -1,5 +1,13 @@
 use raw_window_handle::RawWindowHandle;
-use std::ffi::c_void;
+use std::ffi::{c_void, OsString};
+use std::path::PathBuf;
+
+// The still frame size when `--size` isn’t given
+const DEFAULT_FRAME_SIZE: (u32, u32) = (1920, 1080);
+// How much simulated time to render before saving the still frame when `--seconds` isn’t given
+const DEFAULT_FRAME_SECONDS: f64 = 10.0;
+// Keep the offline render from running for ages by accident
+const MAX_FRAME_SECONDS: f64 = 10.0 * 60.0;
 
 #[cfg(windows)]
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
@@ -9,25 +17,178 @@ pub enum Mode {
     Preview(RawWindowHandle),
     Screensaver,
     Settings,
+    // Run in an ordinary window, optionally with an initial size
+    Windowed(Option<(u32, u32)>),
+    // Set up rendering without showing anything, report the environment, and exit
+    Diagnose,
+    // Draw behind the desktop icons, like an animated wallpaper
+    Wallpaper,
+    // Ask a running wallpaper to stop
+    StopWallpaper,
+    // Print the color and fill modes the settings file accepts, and exit
+    ListModes,
+    // Save a copy of the settings to share, or replace the settings with a shared copy
+    Export(PathBuf),
+    Import(PathBuf),
+    // Render a single frame offscreen after `seconds` of simulated time and save it as a PNG
+    RenderFrame {
+        path: PathBuf,
+        size: (u32, u32),
+        seconds: f64,
+    },
+    // Render offscreen as fast as possible for `seconds`, and report the frame rate
+    Benchmark {
+        size: (u32, u32),
+        seconds: f64,
+    },
+}
+
+pub struct Flags {
+    pub mode: Mode,
+    // The name of the config profile to use instead of the default
+    pub profile: Option<String>,
+    // Present with plain OpenGL instead of a DXGI swapchain
+    pub no_dxgi: bool,
+    // Keep the settings next to the executable
+    pub portable: bool,
+    // Run as a preview from the settings window, which never locks the session on exit
+    pub preview: bool,
+    // Load this settings file instead of the usual one, like for a preview of unsaved settings
+    pub settings_file: Option<PathBuf>,
+}
+
+impl Flags {
+    // The arguments that open the settings window on the same settings file.
+    //
+    // These are `OsString`s so that the settings file path is passed on as-is, rather than through
+    // a lossy conversion to UTF-8.
+    pub fn settings_window_args(&self) -> Vec<OsString> {
+        let mut args = vec![OsString::from("/c")];
+        if let Some(profile) = &self.profile {
+            args.push("--profile".into());
+            args.push(profile.into());
+        }
+        if self.portable {
+            args.push("--portable".into());
+        }
+        if let Some(settings_file) = &self.settings_file {
+            args.push("--settings-file".into());
+            args.push(settings_file.into());
+        }
+        args
+    }
+}
+
+pub fn read_flags() -> Result<Flags, String> {
+    let mut args = std::env::args_os().skip(1).collect::<Vec<OsString>>();
+    // Take the path before anything else, since it doesn’t have to be valid UTF-8.
+    let settings_file = take_path(&mut args, "--settings-file")?;
+    let mut args = args
+        .into_iter()
+        .map(|arg| {
+            arg.into_string()
+                .map_err(|arg| format!("Can't read the argument {}.", arg.to_string_lossy()))
+        })
+        .collect::<Result<Vec<String>, String>>()?;
+    let profile = take_profile(&mut args)?;
+    let no_dxgi = take_flag(&mut args, "--no-dxgi");
+    let portable = take_flag(&mut args, "--portable");
+    let preview = take_flag(&mut args, "--preview");
+    let mode = read_mode(&args)?;
+
+    Ok(Flags {
+        mode,
+        profile,
+        no_dxgi,
+        portable,
+        preview,
+        settings_file,
+    })
+}
+
+// Remove a flag without a value from the arguments, returning whether it was there.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    match args.iter().position(|arg| arg == flag) {
+        Some(index) => {
+            args.remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+// Remove a flag and the value after it from the arguments, so that it can be combined with any
+// mode.
+fn take_value(args: &mut Vec<String>, flag: &str) -> Result<Option<String>, String> {
+    let index = match args.iter().position(|arg| arg == flag) {
+        Some(index) => index,
+        None => return Ok(None),
+    };
+
+    args.remove(index);
+    if index >= args.len() {
+        return Err(format!("Missing a value after {}.", flag));
+    }
+
+    Ok(Some(args.remove(index)))
+}
+
+// Like `take_value`, but for a path that’s kept as it was passed in.
+fn take_path(args: &mut Vec<OsString>, flag: &str) -> Result<Option<PathBuf>, String> {
+    let index = match args.iter().position(|arg| arg == flag) {
+        Some(index) => index,
+        None => return Ok(None),
+    };
+
+    args.remove(index);
+    if index >= args.len() {
+        return Err(format!("Missing a value after {}.", flag));
+    }
+
+    Ok(Some(PathBuf::from(args.remove(index))))
 }
 
-pub fn read_flags() -> Result<Mode, String> {
-    match std::env::args().nth(1).as_mut().map(|s| {
-        s.make_ascii_lowercase();
-        s.as_str()
-    }) {
+// Remove `--profile <name>` from the arguments.
+fn take_profile(args: &mut Vec<String>) -> Result<Option<String>, String> {
+    let name = match take_value(args, "--profile")? {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+
+    // The name ends up in a file name, so keep it simple.
+    let is_valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !is_valid {
+        return Err(format!(
+            "Can't use {} as a profile name. Use letters, numbers, dashes, and underscores.",
+            name
+        ));
+    }
+
+    Ok(Some(name))
+}
+
+fn read_mode(args: &[String]) -> Result<Mode, String> {
+    let (flag, joined_value) = match args.first() {
+        Some(arg) => split_flag(arg),
+        // <right click + configure> sends no flags whatsoever.
+        None => return Ok(Mode::Settings),
+    };
+    // Window handles come either joined to the flag with a colon, or as the next argument.
+    let value = joined_value.or_else(|| args.get(1).cloned());
+
+    match flag.as_str() {
         // Settings panel
         //
         // /c -> you’re supposed to support this, but AFAIK the only way to get
         // this is to manually send it from the command line.
         //
-        // /c:HWND -> the screensaver configuration window gives a window
-        // handle. I’m not sure what it’s for. Maybe you’re supposed to use it
-        // to close your settings window if the parent windows closes?
-        //
-        // No flags -> <right click + configure> sends no flags whatsoever.
-        Some("/c") | None => Ok(Mode::Settings),
-        Some(s) if s.starts_with("/c:") => Ok(Mode::Settings),
+        // /c:HWND or /c HWND -> the screensaver configuration window gives a
+        // window handle. I’m not sure what it’s for. Maybe you’re supposed to
+        // use it to close your settings window if the parent windows closes?
+        "/c" => Ok(Mode::Settings),
 
         // Run screensaver
         //
@@ -35,22 +196,19 @@ pub fn read_flags() -> Result<Mode, String> {
         //
         // /S -> <right click + test> sends an uppercase /S, which doesn’t
         // seem to be documented anywhere.
-        Some("/s") => Ok(Mode::Screensaver),
+        "/s" => Ok(Mode::Screensaver),
 
         // Run preview or in Wallpaper Engine
         //
         // /p HWND -> draw the screensaver in the preview window.
         //
-        // /p:HWND -> TODO: apparently, this is also an option you need to
-        // support.
+        // /p:HWND -> some versions of Windows join the handle to the flag.
         //
         // -parenthwnd HWND -> Wallpaper Engine
-        Some("/p") | Some("-parenthwnd") => {
-            let handle_ptr = std::env::args()
-                .nth(2)
-                .ok_or("Can't find the window to show the screensaver preview.")?
-                .parse::<usize>()
-                .map_err(|e| format!("Can't parse the window handle: {}", e))?;
+        "/p" | "-parenthwnd" => {
+            let handle_ptr = value
+                .ok_or_else(|| "Can't find the window to show the screensaver preview.".to_string())
+                .and_then(|value| parse_window_handle(&value))?;
 
             let mut handle = raw_window_handle::Win32WindowHandle::empty();
             handle.hwnd = handle_ptr as *mut c_void;
@@ -60,8 +218,276 @@ pub fn read_flags() -> Result<Mode, String> {
             Ok(Mode::Preview(RawWindowHandle::Win32(handle)))
         }
 
-        Some(s) => {
-            return Err(format!("I don’t know what the argument {} is.", s));
+        // Run in a normal, resizable window
+        //
+        // --windowed [WxH] -> useful for development, or to run Flux as a desktop app.
+        "--windowed" => {
+            let size = args.get(1).map(|size| parse_size(size)).transpose()?;
+
+            Ok(Mode::Windowed(size))
         }
+
+        // Report on the rendering environment
+        //
+        // --diagnose -> for support requests. Prints what the screensaver would use to render.
+        "--diagnose" => Ok(Mode::Diagnose),
+
+        // Run as an animated wallpaper
+        //
+        // --wallpaper -> draw behind the desktop icons until stopped.
+        //
+        // --stop-wallpaper -> stop a running wallpaper.
+        "--wallpaper" => Ok(Mode::Wallpaper),
+        "--stop-wallpaper" => Ok(Mode::StopWallpaper),
+
+        // List the values for the settings file
+        //
+        // --list-modes -> for anyone editing or deploying the settings file by hand.
+        "--list-modes" => Ok(Mode::ListModes),
+
+        // Share settings
+        //
+        // --export <path> -> save the settings to a file that can be passed around.
+        //
+        // --import <path> -> replace the settings with those from an exported file.
+        "--export" | "--import" => {
+            let path = args
+                .get(1)
+                .filter(|path| !path.starts_with("--"))
+                .map(PathBuf::from)
+                .ok_or_else(|| format!("Missing the settings file after {}.", flag))?;
+
+            Ok(if flag == "--export" {
+                Mode::Export(path)
+            } else {
+                Mode::Import(path)
+            })
+        }
+
+        // Render a still image
+        //
+        // --render-frame <path> [--size WxH] [--seconds T] -> run the simulation for T seconds
+        // without showing anything, and save the last frame. Handy for making a wallpaper.
+        "--render-frame" => {
+            let path = args
+                .get(1)
+                .filter(|path| !path.starts_with("--"))
+                .ok_or_else(|| "Missing the file to save after --render-frame.".to_string())?;
+            let size = value_after(args, "--size")?
+                .map(|size| parse_size(size))
+                .transpose()?
+                .unwrap_or(DEFAULT_FRAME_SIZE);
+            let seconds = value_after(args, "--seconds")?
+                .map(|seconds| parse_seconds(seconds))
+                .transpose()?
+                .unwrap_or(DEFAULT_FRAME_SECONDS);
+
+            Ok(Mode::RenderFrame {
+                path: PathBuf::from(path),
+                size,
+                seconds,
+            })
+        }
+
+        // Measure performance
+        //
+        // --benchmark <seconds> [--size WxH] -> render without vsync for T seconds, and report the
+        // frame rate and frame times. For comparing settings like the render scale or MSAA.
+        "--benchmark" => {
+            let seconds = args
+                .get(1)
+                .filter(|seconds| !seconds.starts_with("--"))
+                .ok_or_else(|| "Missing the number of seconds after --benchmark.".to_string())
+                .and_then(|seconds| parse_seconds(seconds))?;
+            let size = value_after(args, "--size")?
+                .map(|size| parse_size(size))
+                .transpose()?
+                .unwrap_or(DEFAULT_FRAME_SIZE);
+
+            Ok(Mode::Benchmark { size, seconds })
+        }
+
+        _ => Err(format!("I don’t know what the argument {} is.", args[0])),
+    }
+}
+
+// The value after a mode-specific flag, like `--size` for `--render-frame`.
+fn value_after<'a>(args: &'a [String], flag: &str) -> Result<Option<&'a String>, String> {
+    match args.iter().position(|arg| arg == flag) {
+        Some(index) => args
+            .get(index + 1)
+            .map(Some)
+            .ok_or_else(|| format!("Missing a value after {}.", flag)),
+        None => Ok(None),
+    }
+}
+
+// Normalize a screensaver flag and split off a value joined with a colon.
+//
+// Flags are case-insensitive, and can start with either `/` or `-`: `/S`, `-s`, and `/s` all mean
+// the same thing.
+fn split_flag(arg: &str) -> (String, Option<String>) {
+    let arg = arg.to_ascii_lowercase();
+    let (flag, value) = match arg.split_once(':') {
+        Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+        None => (arg, None),
+    };
+
+    match flag.strip_prefix('-') {
+        Some(name) if name.len() == 1 => (format!("/{}", name), value),
+        _ => (flag, value),
+    }
+}
+
+// Parse a window handle, written either in decimal or in hex with a `0x` prefix.
+fn parse_window_handle(s: &str) -> Result<usize, String> {
+    let s = s.trim();
+    let result = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => s.parse::<usize>(),
+    };
+
+    result.map_err(|e| format!("Can't parse the window handle {}: {}", s, e))
+}
+
+// Parse a size in the form of `WIDTHxHEIGHT`.
+fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("Can't parse the window size {}. Use WIDTHxHEIGHT.", s);
+    let (width, height) = s
+        .to_ascii_lowercase()
+        .split_once('x')
+        .ok_or_else(invalid)
+        .and_then(|(width, height)| {
+            Ok((
+                width.parse::<u32>().map_err(|_| invalid())?,
+                height.parse::<u32>().map_err(|_| invalid())?,
+            ))
+        })?;
+
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+
+    Ok((width, height))
+}
+
+fn parse_seconds(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(seconds) if (0.0..=MAX_FRAME_SECONDS).contains(&seconds) => Ok(seconds),
+        _ => Err(format!(
+            "Can't use {} as the number of seconds. Use a number from 0 to {}.",
+            s, MAX_FRAME_SECONDS
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mode(args: &[&str]) -> Result<Mode, String> {
+        let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        read_mode(&args)
+    }
+
+    fn preview_handle(args: &[&str]) -> Option<usize> {
+        match mode(args) {
+            Ok(Mode::Preview(RawWindowHandle::Win32(handle))) => Some(handle.hwnd as usize),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn it_reads_the_settings_flag_with_or_without_a_handle() {
+        for args in [
+            &["/c"][..],
+            &["/c", "12345"],
+            &["/c:12345"],
+            &["/C:12345"],
+            &["-c"],
+            &[],
+        ] {
+            assert!(matches!(mode(args), Ok(Mode::Settings)), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn it_reads_the_preview_handle_joined_or_separate() {
+        assert_eq!(preview_handle(&["/p", "12345"]), Some(12345));
+        assert_eq!(preview_handle(&["/p:12345"]), Some(12345));
+        assert_eq!(preview_handle(&["/P:0x3039"]), Some(12345));
+        assert_eq!(preview_handle(&["-p", "0x3039"]), Some(12345));
+        assert_eq!(preview_handle(&["-parenthwnd", "12345"]), Some(12345));
+    }
+
+    #[test]
+    fn it_reads_the_screensaver_flag_in_any_form() {
+        for args in [["/s"], ["/S"], ["-s"], ["-S"]] {
+            assert!(matches!(mode(&args), Ok(Mode::Screensaver)), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn it_reads_the_list_modes_flag() {
+        assert!(matches!(mode(&["--list-modes"]), Ok(Mode::ListModes)));
+        assert!(matches!(mode(&["--LIST-MODES"]), Ok(Mode::ListModes)));
+    }
+
+    #[test]
+    fn it_reads_the_settings_file_to_share() {
+        assert!(matches!(
+            mode(&["--export", "shared.json"]),
+            Ok(Mode::Export(path)) if path == PathBuf::from("shared.json")
+        ));
+        assert!(matches!(
+            mode(&["--import", "shared.json"]),
+            Ok(Mode::Import(path)) if path == PathBuf::from("shared.json")
+        ));
+        assert!(mode(&["--import"]).is_err());
+    }
+
+    #[test]
+    fn it_reads_the_frame_to_render() {
+        assert!(matches!(
+            mode(&["--render-frame", "flux.png", "--size", "640x480", "--seconds", "2.5"]),
+            Ok(Mode::RenderFrame { path, size: (640, 480), seconds })
+                if path == PathBuf::from("flux.png") && seconds == 2.5
+        ));
+        assert!(matches!(
+            mode(&["--render-frame", "flux.png"]),
+            Ok(Mode::RenderFrame {
+                size: DEFAULT_FRAME_SIZE,
+                ..
+            })
+        ));
+        assert!(mode(&["--render-frame"]).is_err());
+        assert!(mode(&["--render-frame", "--size", "640x480"]).is_err());
+        assert!(mode(&["--render-frame", "flux.png", "--seconds", "-1"]).is_err());
+        assert!(mode(&["--render-frame", "flux.png", "--seconds"]).is_err());
+    }
+
+    #[test]
+    fn it_reads_the_benchmark_duration() {
+        assert!(matches!(
+            mode(&["--benchmark", "20", "--size", "3840x2160"]),
+            Ok(Mode::Benchmark { size: (3840, 2160), seconds }) if seconds == 20.0
+        ));
+        assert!(matches!(
+            mode(&["--benchmark", "5"]),
+            Ok(Mode::Benchmark {
+                size: DEFAULT_FRAME_SIZE,
+                ..
+            })
+        ));
+        assert!(mode(&["--benchmark"]).is_err());
+        assert!(mode(&["--benchmark", "--size", "640x480"]).is_err());
+    }
+
+    #[test]
+    fn it_rejects_a_missing_or_invalid_preview_handle() {
+        assert!(mode(&["/p"]).is_err());
+        assert!(mode(&["/p:"]).is_err());
+        assert!(mode(&["/p", "window"]).is_err());
+        assert!(mode(&["/p:0xnope"]).is_err());
     }
 }
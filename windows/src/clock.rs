@@ -0,0 +1,217 @@
+use std::time::{Duration, Instant};
+
+// The lowest the cap on how far a single frame moves the simulation can go, in milliseconds, however
+// short the recent frames were
+const MAX_FRAME_DELTA_MS: f64 = 1000.0 / 20.0;
+// Frames longer than this many times the recent average are stalls, like after the device was
+// lost or the window was covered, rather than a low frame rate.
+const MAX_FRAME_DELTA_FACTOR: f64 = 3.0;
+// How much each frame counts towards the average frame time
+const AVERAGE_WEIGHT: f64 = 0.2;
+
+// The time passed to Flux.
+//
+// Flux advances the simulation based on these timestamps, so running the clock slower slows down
+// the whole animation.
+#[derive(Clone, Copy, Debug)]
+pub struct Clock {
+    start: Instant,
+    speed: f64,
+    // How long the clock has been paused for, not counting a pause that’s still going
+    paused_for: Duration,
+    paused_at: Option<Instant>,
+}
+
+impl Clock {
+    pub fn new(start: Instant, speed: f64) -> Self {
+        Self {
+            start,
+            speed,
+            paused_for: Duration::ZERO,
+            paused_at: None,
+        }
+    }
+
+    pub fn start(&self) -> Instant {
+        self.start
+    }
+
+    // The current timestamp in milliseconds.
+    pub fn timestamp(&self) -> f64 {
+        self.timestamp_at(Instant::now())
+    }
+
+    pub fn timestamp_at(&self, now: Instant) -> f64 {
+        let now = self.paused_at.unwrap_or(now);
+        let running = now
+            .saturating_duration_since(self.start)
+            .saturating_sub(self.paused_for);
+        running.as_secs_f64() * 1000.0 * self.speed
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    // Start counting from zero again at `now`, like when the animation begins after a delay.
+    pub fn restart(&mut self, now: Instant) {
+        *self = Self::new(now, self.speed);
+    }
+
+    // Stop the clock, or start it again from where it stopped.
+    pub fn toggle_pause(&mut self, now: Instant) {
+        match self.paused_at.take() {
+            Some(paused_at) => self.paused_for += now.saturating_duration_since(paused_at),
+            None => self.paused_at = Some(now),
+        }
+    }
+}
+
+// The time the simulation has reached, in milliseconds, given the timestamp of each frame.
+//
+// This keeps up with the timestamps, except after a stall. Rather than jump ahead by the whole
+// stall, the simulation moves on by about as much as in a normal frame and carries on from there.
+// Low frame rates, like in power-saving mode or with `max_frame_rate`, soon raise the average and
+// run at their usual speed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SimulationTime {
+    time: f64,
+    last_timestamp: Option<f64>,
+    average_delta: Option<f64>,
+}
+
+impl SimulationTime {
+    // Where the simulation got to on the last frame, if there was one.
+    pub fn current(&self) -> Option<f64> {
+        self.last_timestamp.map(|_| self.time)
+    }
+
+    // Move on to the frame at `timestamp`. The simulation never goes backwards, even if the clock
+    // is restarted.
+    pub fn advance(&mut self, timestamp: f64) -> f64 {
+        match self.last_timestamp {
+            Some(last_timestamp) => {
+                let delta = (timestamp - last_timestamp).max(0.0);
+                let max_delta = self
+                    .average_delta
+                    .map_or(MAX_FRAME_DELTA_MS, |average| {
+                        average * MAX_FRAME_DELTA_FACTOR
+                    })
+                    .max(MAX_FRAME_DELTA_MS);
+                // Average what the simulation actually moved on by, so that a stall doesn’t raise
+                // the cap for the frames after it.
+                let delta = delta.min(max_delta);
+                self.time += delta;
+                self.average_delta = Some(match self.average_delta {
+                    Some(average) => average + (delta - average) * AVERAGE_WEIGHT,
+                    None => delta,
+                });
+            }
+            None => self.time = timestamp,
+        }
+        self.last_timestamp = Some(timestamp);
+        self.time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_counts_milliseconds_from_the_start() {
+        let start = Instant::now();
+        let clock = Clock::new(start, 1.0);
+
+        assert_eq!(clock.timestamp_at(start + Duration::from_secs(2)), 2000.0);
+    }
+
+    #[test]
+    fn it_slows_down_time() {
+        let start = Instant::now();
+        let clock = Clock::new(start, 0.25);
+
+        assert_eq!(clock.timestamp_at(start + Duration::from_secs(4)), 1000.0);
+    }
+
+    #[test]
+    fn it_stands_still_while_paused() {
+        let start = Instant::now();
+        let mut clock = Clock::new(start, 1.0);
+
+        clock.toggle_pause(start + Duration::from_secs(1));
+        assert_eq!(clock.timestamp_at(start + Duration::from_secs(5)), 1000.0);
+
+        clock.toggle_pause(start + Duration::from_secs(5));
+        assert_eq!(clock.timestamp_at(start + Duration::from_secs(6)), 2000.0);
+    }
+
+    fn run_frames(time: &mut SimulationTime, mut timestamp: f64, delta: f64, frames: u32) -> f64 {
+        for _ in 0..frames {
+            timestamp += delta;
+            time.advance(timestamp);
+        }
+        timestamp
+    }
+
+    #[test]
+    fn it_skips_over_a_stall() {
+        let mut time = SimulationTime::default();
+        assert_eq!(time.advance(1000.0), 1000.0);
+        let timestamp = run_frames(&mut time, 1000.0, 16.0, 60);
+        let before = time.current().unwrap();
+
+        assert_eq!(
+            time.advance(timestamp + 2000.0),
+            before + MAX_FRAME_DELTA_MS
+        );
+        assert_eq!(
+            time.advance(timestamp + 2016.0),
+            before + MAX_FRAME_DELTA_MS + 16.0
+        );
+    }
+
+    #[test]
+    fn it_skips_over_stalls_in_a_row() {
+        let mut time = SimulationTime::default();
+        time.advance(0.0);
+        let timestamp = run_frames(&mut time, 0.0, 16.0, 60);
+        let before = time.current().unwrap();
+
+        let after_first = time.advance(timestamp + 2000.0);
+        assert_eq!(after_first, before + MAX_FRAME_DELTA_MS);
+        // The first stall only nudges the cap up, rather than letting the second one through.
+        assert!(time.advance(timestamp + 4000.0) - after_first < 2.0 * MAX_FRAME_DELTA_MS);
+    }
+
+    #[test]
+    fn it_keeps_up_with_a_low_frame_rate() {
+        let mut time = SimulationTime::default();
+        time.advance(0.0);
+        let timestamp = run_frames(&mut time, 0.0, 1000.0, 10);
+        let before = time.current().unwrap();
+
+        assert_eq!(time.advance(timestamp + 1000.0), before + 1000.0);
+    }
+
+    #[test]
+    fn it_holds_still_when_the_clock_stops_or_goes_back() {
+        let mut time = SimulationTime::default();
+        time.advance(5000.0);
+
+        assert_eq!(time.advance(5000.0), 5000.0);
+        assert_eq!(time.advance(0.0), 5000.0);
+        assert_eq!(time.advance(16.0), 5016.0);
+    }
+
+    #[test]
+    fn it_counts_from_zero_after_a_restart() {
+        let start = Instant::now();
+        let mut clock = Clock::new(start, 0.5);
+        clock.toggle_pause(start + Duration::from_secs(1));
+
+        clock.restart(start + Duration::from_secs(3));
+        assert!(!clock.is_paused());
+        assert_eq!(clock.timestamp_at(start + Duration::from_secs(5)), 1000.0);
+    }
+}
@@ -1,13 +1,27 @@
+use crate::palette::{self, Color, Gradient, PaletteName};
+
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::{fmt, fs, io, path};
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+thread_local! {
+    // The values reset while `Config::decode` runs on this thread. Outside of it, resets are only
+    // logged.
+    static DECODE_RESETS: RefCell<Option<Vec<String>>> = RefCell::new(None);
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 #[serde(default)]
 pub struct Config {
     pub version: semver::Version,
+    #[serde(deserialize_with = "or_default")]
     pub log_level: log::Level,
     pub flux: FluxSettings,
+    pub screensaver: ScreensaverSettings,
     pub platform: PlatformConfig,
+    pub settings_window: SettingsWindowConfig,
 
     // An optional path to the location of this config
     #[serde(skip)]
@@ -21,39 +35,326 @@ impl Default for Config {
             version: semver::Version::parse("0.1.0").unwrap(),
             log_level: log::Level::Warn,
             flux: Default::default(),
+            screensaver: Default::default(),
             platform: Default::default(),
+            settings_window: Default::default(),
             location: None,
         }
     }
 }
 
 impl Config {
-    pub fn load(optional_config_dir: Option<&path::Path>) -> Self {
+    // Load the default settings, or those of a named profile.
+    pub fn load(optional_config_dir: Option<&path::Path>, profile: Option<&str>) -> Self {
         match optional_config_dir {
             None => Self::default(),
 
-            Some(config_dir) => {
-                let config_path = config_dir.join("settings.json");
-                let config = Self::load_existing_config(config_path.as_path());
-                if let Err(err) = &config {
-                    match err {
-                        Problem::ReadSettings { err, path }
-                            if err.kind() == io::ErrorKind::NotFound =>
-                        {
-                            log::info!(
-                                "No settings file found at {}. Using defaults.",
-                                path.display()
-                            )
-                        }
-                        _ => log::error!("{}", err),
+            Some(config_dir) => Self::load_file(&config_dir.join(file_name(profile))),
+        }
+    }
+
+    // Load a specific settings file, falling back to the defaults if it can’t be read.
+    pub fn load_file(config_path: &path::Path) -> Self {
+        let (config, fixes) = match Self::load_existing_config(config_path) {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                match err {
+                    Problem::ReadSettings { err, path }
+                        if err.kind() == io::ErrorKind::NotFound =>
+                    {
+                        log::info!(
+                            "No settings file found at {}. Using defaults.",
+                            path.display()
+                        )
                     }
+                    err => log::error!("{}", err),
                 }
+                (Self::default(), Vec::new())
+            }
+        };
+        for fix in fixes {
+            log::warn!("Adjusted settings: {}", fix);
+        }
+
+        config.attach_location(config_path)
+    }
+
+    // Portable installs keep their settings next to the executable, which helps when the user’s
+    // profile isn’t writable. Use that directory if it already has a settings file, or if `force`
+    // is set.
+    pub fn portable_dir(profile: Option<&str>, force: bool) -> Option<path::PathBuf> {
+        let exe_path = std::env::current_exe().ok()?;
+        let exe_dir = exe_path.parent()?;
+        (force || exe_dir.join(file_name(profile)).is_file()).then(|| exe_dir.to_owned())
+    }
+
+    // Let environment variables override the settings file. This makes it possible to debug an
+    // issue without editing someone’s settings.
+    //
+    // FLUX_LOG: the log level, like `debug` or `trace`.
+    // FLUX_ADAPTER: the name or vendor of the graphics adapter to use, like `NVIDIA`.
+    pub fn apply_env_overrides(&mut self) {
+        if let Ok(level) = std::env::var("FLUX_LOG") {
+            match level.parse::<log::Level>() {
+                Ok(level) => self.log_level = level,
+                Err(_) => log::warn!("Ignoring FLUX_LOG: {} isn’t a log level", level),
+            }
+        }
+
+        if let Ok(adapter) = std::env::var("FLUX_ADAPTER") {
+            if !adapter.is_empty() {
+                self.platform.windows.adapter = Some(adapter);
+            }
+        }
+    }
+
+    // Clamp values to their valid ranges.
+    //
+    // Returns a description of every adjustment made. Unknown enum values are already reset to
+    // their defaults while decoding.
+    pub fn sanitize(&mut self) -> Vec<String> {
+        let mut fixes = Vec::new();
 
-                config.unwrap_or_default().attach_location(&config_path)
+        let (min_width, min_height) = SettingsWindowConfig::MIN_SIZE;
+        let (max_width, max_height) = SettingsWindowConfig::MAX_SIZE;
+        clamp_field(
+            "settings window width",
+            &mut self.settings_window.size.0,
+            min_width..=max_width,
+            &mut fixes,
+        );
+        clamp_field(
+            "settings window height",
+            &mut self.settings_window.size.1,
+            min_height..=max_height,
+            &mut fixes,
+        );
+
+        clamp_field(
+            "fade duration",
+            &mut self.screensaver.fade_duration_ms,
+            0..=ScreensaverSettings::MAX_FADE_DURATION_MS,
+            &mut fixes,
+        );
+        clamp_field(
+            "input grace",
+            &mut self.screensaver.input_grace_ms,
+            0..=ScreensaverSettings::MAX_INPUT_GRACE_MS,
+            &mut fixes,
+        );
+        reset_if_not_finite(
+            "mouse exit distance",
+            &mut self.screensaver.mouse_exit_distance,
+            ScreensaverSettings::DEFAULT_MOUSE_EXIT_DISTANCE,
+            &mut fixes,
+        );
+        clamp_field(
+            "mouse exit distance",
+            &mut self.screensaver.mouse_exit_distance,
+            ScreensaverSettings::MOUSE_EXIT_DISTANCE_RANGE,
+            &mut fixes,
+        );
+        clamp_field(
+            "startup delay",
+            &mut self.screensaver.startup_delay_ms,
+            0..=ScreensaverSettings::MAX_STARTUP_DELAY_MS,
+            &mut fixes,
+        );
+        clamp_field(
+            "first frame timeout",
+            &mut self.screensaver.first_frame_timeout_secs,
+            0..=ScreensaverSettings::MAX_FIRST_FRAME_TIMEOUT_SECS,
+            &mut fixes,
+        );
+        clamp_field(
+            "max frame rate",
+            &mut self.screensaver.max_frame_rate,
+            0..=ScreensaverSettings::MAX_FRAME_RATE,
+            &mut fixes,
+        );
+        clamp_field(
+            "active duration",
+            &mut self.screensaver.active_duration_secs,
+            0..=ScreensaverSettings::MAX_CYCLE_DURATION_SECS,
+            &mut fixes,
+        );
+        clamp_field(
+            "idle duration",
+            &mut self.screensaver.idle_duration_secs,
+            0..=ScreensaverSettings::MAX_CYCLE_DURATION_SECS,
+            &mut fixes,
+        );
+        for channel in self.screensaver.background_color.iter_mut() {
+            clamp_field("background color channel", channel, 0.0..=1.0, &mut fixes);
+        }
+
+        reset_if_not_finite(
+            "render scale",
+            &mut self.screensaver.render_scale,
+            1.0,
+            &mut fixes,
+        );
+        clamp_field(
+            "render scale",
+            &mut self.screensaver.render_scale,
+            ScreensaverSettings::MIN_RENDER_SCALE..=1.0,
+            &mut fixes,
+        );
+        let night_mode = &mut self.screensaver.night_mode;
+        for (name, minute) in [
+            ("night start", &mut night_mode.start_minute),
+            ("night end", &mut night_mode.end_minute),
+        ] {
+            clamp_field(name, minute, 0..=MINUTES_PER_DAY - 1, &mut fixes);
+        }
+        reset_if_not_finite(
+            "night brightness",
+            &mut night_mode.brightness,
+            NightMode::default().brightness,
+            &mut fixes,
+        );
+        clamp_field(
+            "night brightness",
+            &mut night_mode.brightness,
+            NightMode::BRIGHTNESS_RANGE,
+            &mut fixes,
+        );
+        clamp_field(
+            "night ramp",
+            &mut night_mode.ramp_minutes,
+            0..=NightMode::MAX_RAMP_MINUTES,
+            &mut fixes,
+        );
+        reset_if_not_finite(
+            "watermark opacity",
+            &mut self.screensaver.watermark.opacity,
+            WatermarkSettings::default().opacity,
+            &mut fixes,
+        );
+        clamp_field(
+            "watermark opacity",
+            &mut self.screensaver.watermark.opacity,
+            0.0..=1.0,
+            &mut fixes,
+        );
+        reset_if_not_finite(
+            "trail persistence",
+            &mut self.screensaver.trail_persistence,
+            ScreensaverSettings::default().trail_persistence,
+            &mut fixes,
+        );
+        clamp_field(
+            "trail persistence",
+            &mut self.screensaver.trail_persistence,
+            ScreensaverSettings::TRAIL_PERSISTENCE_RANGE,
+            &mut fixes,
+        );
+        reset_if_not_finite(
+            "line width",
+            &mut self.flux.line_width_scale,
+            1.0,
+            &mut fixes,
+        );
+        clamp_field(
+            "line width",
+            &mut self.flux.line_width_scale,
+            FluxSettings::LINE_WIDTH_SCALE_RANGE,
+            &mut fixes,
+        );
+        clamp_field(
+            "substeps",
+            &mut self.flux.substeps,
+            FluxSettings::SUBSTEPS_RANGE,
+            &mut fixes,
+        );
+        reset_if_not_finite("noise scale", &mut self.flux.noise_scale, 1.0, &mut fixes);
+        clamp_field(
+            "noise scale",
+            &mut self.flux.noise_scale,
+            FluxSettings::NOISE_SCALE_RANGE,
+            &mut fixes,
+        );
+        if let Some(noise_octaves) = &mut self.flux.noise_octaves {
+            clamp_field(
+                "noise octaves",
+                noise_octaves,
+                FluxSettings::NOISE_OCTAVES_RANGE,
+                &mut fixes,
+            );
+        }
+        clamp_optional_field(
+            "viscosity",
+            &mut self.flux.viscosity,
+            FluxSettings::VISCOSITY_RANGE,
+            &mut fixes,
+        );
+        clamp_optional_field(
+            "velocity dissipation",
+            &mut self.flux.velocity_dissipation,
+            FluxSettings::VELOCITY_DISSIPATION_RANGE,
+            &mut fixes,
+        );
+        reset_if_not_finite(
+            "brightness",
+            &mut self.screensaver.brightness,
+            1.0,
+            &mut fixes,
+        );
+        clamp_field(
+            "brightness",
+            &mut self.screensaver.brightness,
+            ScreensaverSettings::BRIGHTNESS_RANGE,
+            &mut fixes,
+        );
+        reset_if_not_finite("contrast", &mut self.screensaver.contrast, 1.0, &mut fixes);
+        clamp_field(
+            "contrast",
+            &mut self.screensaver.contrast,
+            ScreensaverSettings::CONTRAST_RANGE,
+            &mut fixes,
+        );
+
+        fixes
+    }
+
+    // The settings file this config was loaded from, and will be saved to.
+    pub fn location(&self) -> Option<&path::Path> {
+        self.location.as_deref()
+    }
+
+    // The watermark image, with relative paths resolved against the settings folder.
+    pub fn watermark_path(&self) -> Option<path::PathBuf> {
+        let watermark_path = self.screensaver.watermark.path.as_ref()?;
+        match self.location().and_then(path::Path::parent) {
+            Some(settings_dir) if watermark_path.is_relative() => {
+                Some(settings_dir.join(watermark_path))
             }
+            _ => Some(watermark_path.clone()),
         }
     }
 
+    // The settings for the window on a monitor, with the monitor’s own color mode if it has one.
+    pub fn for_monitor(&self, monitor: Option<&str>) -> Self {
+        let mut config = self.clone();
+        if let Some(color_mode) = monitor.and_then(|name| self.flux.monitor_color_modes.get(name)) {
+            config.flux.color_mode = color_mode.clone();
+        }
+        config
+    }
+
+    // Load the settings file again, like after it was changed by the settings window.
+    pub fn reload(&self) -> Result<Self, Problem> {
+        let config_path = self.location.as_ref().ok_or(Problem::NoSaveLocation)?;
+        // The palettes may have changed too.
+        palette::load_custom_palettes(config_path.parent());
+        let (config, fixes) = Self::load_existing_config(config_path)?;
+        for fix in fixes {
+            log::warn!("Adjusted settings: {}", fix);
+        }
+
+        Ok(config.attach_location(config_path))
+    }
+
     // Attach the config's location
     fn attach_location(mut self, path: &path::Path) -> Self {
         self.location = Some(path.to_owned());
@@ -61,107 +362,1007 @@ impl Config {
         self
     }
 
-    fn load_existing_config(config_path: &path::Path) -> Result<Config, Problem> {
+    // Read a settings file and clamp its values. Returns the config and every value that was reset,
+    // dropped, or clamped along the way.
+    fn load_existing_config(config_path: &path::Path) -> Result<(Config, Vec<String>), Problem> {
         let config_string =
             fs::read_to_string(config_path).map_err(|err| Problem::ReadSettings {
                 path: config_path.to_owned(),
                 err,
             })?;
 
-        serde_json::from_str(&config_string).map_err(|err| Problem::DecodeSettings {
-            path: config_path.to_owned(),
-            err,
-        })
+        let (mut config, mut fixes) =
+            Self::decode(&config_string).map_err(|err| Problem::DecodeSettings {
+                path: config_path.to_owned(),
+                err,
+            })?;
+        fixes.extend(config.sanitize());
+
+        Ok((config, fixes))
+    }
+
+    // Decode the settings. Returns the config and the invalid or unknown values that were reset or
+    // dropped.
+    fn decode(source: &str) -> Result<(Config, Vec<String>), serde_json::Error> {
+        DECODE_RESETS.with(|resets| *resets.borrow_mut() = Some(Vec::new()));
+        let config = serde_json::from_str::<Config>(source);
+        let resets = DECODE_RESETS
+            .with(|resets| resets.take())
+            .unwrap_or_default();
+
+        config.map(|config| (config, resets))
     }
 
     pub fn save(&self) -> Result<(), Problem> {
         match &self.location {
             None => Err(Problem::NoSaveLocation),
-            Some(config_path) => {
-                if let Some(config_dir) = config_path.parent() {
-                    fs::create_dir_all(config_dir).map_err(Problem::IO)?
+            Some(config_path) => self.save_to(config_path),
+        }
+    }
+
+    // Save to another file, like a throwaway copy for a preview. The config keeps saving to its
+    // own location.
+    //
+    // Paths stay as `Path`s all the way down to the filesystem, so that folders with non-ASCII
+    // names work. The standard library also takes care of paths longer than MAX_PATH on Windows.
+    pub fn save_to(&self, config_path: &path::Path) -> Result<(), Problem> {
+        if let Some(config_dir) = config_path.parent() {
+            fs::create_dir_all(config_dir).map_err(|err| Problem::CreateProjectDir {
+                path: config_dir.to_owned(),
+                err,
+            })?
+        }
+        let config = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(config_path)
+            .map_err(|err| Problem::WriteSettings {
+                path: config_path.to_owned(),
+                err,
+            })?;
+
+        serde_json::to_writer_pretty(config, self).map_err(|err| Problem::Save {
+            path: config_path.to_owned(),
+            err,
+        })
+    }
+
+    // Use less video memory, for adapters that don’t have much. Returns a description of every
+    // adjustment made.
+    #[cfg(windows)]
+    pub fn fit_low_video_memory(&mut self) -> Vec<String> {
+        let mut fixes = Vec::new();
+        let render_scale = WindowsConfig::LOW_VIDEO_MEMORY_RENDER_SCALE;
+        if self.screensaver.render_scale > render_scale {
+            fixes.push(format!(
+                "render scale {} was lowered to {}",
+                self.screensaver.render_scale, render_scale
+            ));
+            self.screensaver.render_scale = render_scale;
+        }
+        if self.screensaver.anti_aliasing.samples() > 0 {
+            fixes.push(format!(
+                "anti-aliasing {} was replaced by {}",
+                self.screensaver.anti_aliasing,
+                AntiAliasing::Fxaa
+            ));
+            self.screensaver.anti_aliasing = AntiAliasing::Fxaa;
+        }
+        fixes
+    }
+
+    // Lay out a preview the size of a thumbnail in its own pixels. Squeezing the full-screen pattern
+    // into it leaves lines too thin and too many to see. Returns a description of every adjustment
+    // made.
+    pub fn fit_thumbnail(&mut self) -> Vec<String> {
+        // Thicker lines stay visible with so few pixels to draw them with.
+        const LINE_WIDTH_SCALE: f32 = 1.5;
+
+        let mut fixes = Vec::new();
+        if self.flux.resolution_independent {
+            fixes.push("the simulation was laid out in logical pixels".to_string());
+            self.flux.resolution_independent = false;
+        }
+        if self.flux.line_width_scale < LINE_WIDTH_SCALE {
+            fixes.push(format!(
+                "line width {} was raised to {}",
+                self.flux.line_width_scale, LINE_WIDTH_SCALE
+            ));
+            self.flux.line_width_scale = LINE_WIDTH_SCALE;
+        }
+        if self.screensaver.render_scale < 1.0 {
+            fixes.push(format!(
+                "render scale {} was raised to 1",
+                self.screensaver.render_scale
+            ));
+            self.screensaver.render_scale = 1.0;
+        }
+        if self.flux.substeps > 1 {
+            fixes.push(format!("{} substeps were lowered to 1", self.flux.substeps));
+            self.flux.substeps = 1;
+        }
+        fixes
+    }
+
+    // Save the settings to share with someone else, leaving out where the settings window was on
+    // this machine.
+    pub fn export(&self, path: &path::Path) -> Result<(), Problem> {
+        let mut shared = self.clone();
+        shared.settings_window.position = None;
+        shared.settings_window.monitor = None;
+        shared.save_to(path)
+    }
+
+    // Read settings shared by someone else to use instead of these ones. They’re checked like any
+    // settings file, then upgraded to the current version, and keep this config’s location and
+    // settings window. Returns the imported config and every adjustment made to it.
+    pub fn import(&self, path: &path::Path) -> Result<(Self, Vec<String>), Problem> {
+        let (mut imported, mut fixes) = Self::load_existing_config(path)?;
+
+        let version = Self::default().version;
+        if imported.version > version {
+            fixes.push(format!(
+                "the settings are from a newer version ({}), so any settings this version doesn’t know were reset",
+                imported.version
+            ));
+        }
+        imported.version = version;
+        imported.settings_window = self.settings_window.clone();
+        imported.location = self.location.clone();
+
+        Ok((imported, fixes))
+    }
+
+    // Pick a different look: the colors, how monitors are filled, and the shape of the lines and
+    // the motion. The ranges are narrower than the settings allow, so that every pick looks good.
+    pub fn randomize(&mut self, rng: &mut impl rand::Rng) {
+        use rand::seq::SliceRandom;
+
+        if let Some(color_mode) = ColorMode::all().choose(rng) {
+            self.flux.color_mode = color_mode.clone();
+        }
+        #[cfg(windows)]
+        if let Some(fill_mode) = FillMode::ALL.choose(rng) {
+            self.platform.windows.fill_mode = *fill_mode;
+        }
+        self.flux.line_width_scale = rng.gen_range(0.75..=1.5);
+        self.flux.noise_scale = rng.gen_range(0.5..=2.0);
+    }
+
+    pub fn to_settings(&self, wallpaper: Option<path::PathBuf>) -> flux::settings::Settings {
+        use flux::settings;
+
+        // Pick the colors for the time of day once, when Flux starts.
+        let selected_color_mode = match &self.flux.color_mode {
+            ColorMode::Auto => {
+                let color_mode = ColorMode::for_time_of_day(local_minute_of_day());
+                log::info!("Using {} colors for the time of day", color_mode);
+                color_mode
+            }
+            color_mode => color_mode.clone(),
+        };
+
+        // Flux samples colors from an image, so write one with a gradient through the colors.
+        let gradient_image = |file_name: &str, colors: &[Color]| {
+            let cache_dir = std::env::temp_dir().join("flux");
+            match palette::write_gradient_image(file_name, colors, &cache_dir) {
+                Ok(path) => settings::ColorMode::ImageFile(path),
+                Err(err) => {
+                    log::error!(
+                        "Failed to create the {} gradient: {}",
+                        selected_color_mode,
+                        err
+                    );
+                    settings::ColorMode::default()
                 }
-                let config = fs::OpenOptions::new()
-                    .write(true)
-                    .create(true)
-                    .truncate(true)
-                    .open(config_path)
-                    .map_err(Problem::IO)?;
-
-                serde_json::to_writer_pretty(config, self).map_err(|err| Problem::Save {
-                    path: config_path.clone(),
-                    err,
-                })
             }
-        }
-    }
-
-    pub fn to_settings(&self, wallpaper: Option<path::PathBuf>) -> flux::settings::Settings {
-        use flux::settings;
-
-        let color_mode = match &self.flux.color_mode {
-            ColorMode::Preset(preset) => settings::ColorMode::Preset(*preset),
-            ColorMode::DesktopImage => wallpaper.map_or(
-                settings::ColorMode::default(),
-                settings::ColorMode::ImageFile,
-            ),
-        };
-        flux::settings::Settings {
-            color_mode,
-            ..Default::default()
+        };
+
+        let color_mode = match &selected_color_mode {
+            ColorMode::Preset(preset) => settings::ColorMode::Preset(*preset),
+            // Name the file after the variant, since the display names aren’t file-friendly
+            ColorMode::Gradient(gradient) => {
+                gradient_image(&format!("{:?}", gradient), gradient.colors())
+            }
+            ColorMode::Custom(name) => match name.colors() {
+                Some(colors) => gradient_image(&format!("custom-{}", name.file_name()), &colors),
+                None => {
+                    log::warn!(
+                        "There’s no palette called {} in {}. Using the default colors.",
+                        name,
+                        palette::CUSTOM_PALETTES_FILE
+                    );
+                    settings::ColorMode::default()
+                }
+            },
+            ColorMode::DesktopImage => wallpaper.map_or(
+                settings::ColorMode::default(),
+                settings::ColorMode::ImageFile,
+            ),
+            ColorMode::Auto => unreachable!("Auto is resolved to a color mode above"),
+        };
+        let defaults = flux::settings::Settings::default();
+        flux::settings::Settings {
+            color_mode,
+            // Flux falls back to system entropy without a seed.
+            seed: self.flux.seed.map(|seed| seed.to_string()),
+            line_width: defaults.line_width * self.flux.line_width_scale,
+            viscosity: self.flux.viscosity(),
+            velocity_dissipation: self.flux.velocity_dissipation(),
+            noise_channels: self.flux.noise_channels(&defaults.noise_channels),
+            ..defaults
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct FluxSettings {
+    #[serde(deserialize_with = "or_default")]
+    pub color_mode: ColorMode,
+    // Color modes for particular monitors, keyed by their device name, like `\\.\DISPLAY2`.
+    // Monitors without an entry, or windows that cover several monitors, use `color_mode`.
+    #[serde(
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "without_invalid_entries"
+    )]
+    pub monitor_color_modes: BTreeMap<String, ColorMode>,
+    // Scales the width of the lines. The lines are sized relative to the simulation, so they look
+    // the same across display sizes and densities at the default of 1.
+    pub line_width_scale: f32,
+    // Lay the simulation out in the same coordinate space on every display, so that the pattern
+    // and the noise look the same at any resolution. Turn this off to lay it out in logical
+    // pixels, like older versions did, which fits more of the pattern onto larger displays.
+    pub resolution_independent: bool,
+    // Split each frame’s timestep into this many simulation updates. More substeps keep the
+    // simulation stable when it runs fast, at the cost of more GPU time.
+    pub substeps: u32,
+    // Advanced fluid parameters. Leave these out to use the values Flux ships with.
+    //
+    // How thick the fluid is. Thicker fluid smooths out small swirls.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub viscosity: Option<f32>,
+    // How quickly the fluid loses its momentum
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub velocity_dissipation: Option<f32>,
+    // Multiplies the frequency of the noise field that stirs the fluid. Higher values make tighter
+    // swirls, lower values broader sweeps.
+    pub noise_scale: f32,
+    // How many layers of noise to add up, each finer than the last
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub noise_octaves: Option<u32>,
+    // Seed the simulation for reproducible output. Only configurable through the settings file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl FluxSettings {
+    pub const LINE_WIDTH_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=2.0;
+    pub const SUBSTEPS_RANGE: std::ops::RangeInclusive<u32> = 1..=8;
+    pub const VISCOSITY_RANGE: std::ops::RangeInclusive<f32> = 0.0..=10.0;
+    pub const VELOCITY_DISSIPATION_RANGE: std::ops::RangeInclusive<f32> = 0.0..=1.0;
+    pub const NOISE_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.25..=4.0;
+    // Each octave is another noise pass every frame, so keep this small.
+    pub const NOISE_OCTAVES_RANGE: std::ops::RangeInclusive<u32> = 1..=6;
+
+    pub fn viscosity(&self) -> f32 {
+        self.viscosity
+            .unwrap_or_else(|| flux::settings::Settings::default().viscosity)
+    }
+
+    pub fn velocity_dissipation(&self) -> f32 {
+        self.velocity_dissipation
+            .unwrap_or_else(|| flux::settings::Settings::default().velocity_dissipation)
+    }
+
+    pub fn noise_octaves(&self) -> u32 {
+        self.noise_octaves
+            .unwrap_or_else(|| flux::settings::Settings::default().noise_channels.len() as u32)
+    }
+
+    // Go back to the values Flux ships with for the advanced settings.
+    pub fn reset_advanced(&mut self) {
+        self.substeps = 1;
+        self.viscosity = None;
+        self.velocity_dissipation = None;
+        self.noise_scale = 1.0;
+        self.noise_octaves = None;
+    }
+
+    // Flux’s noise channels, scaled and with octaves added or removed.
+    fn noise_channels(&self, defaults: &[flux::settings::Noise]) -> Vec<flux::settings::Noise> {
+        let octaves = self.noise_octaves() as usize;
+        let mut channels = defaults.iter().take(octaves).cloned().collect::<Vec<_>>();
+
+        // Add finer octaves past the ones Flux ships with, each with half the influence.
+        while channels.len() < octaves {
+            let last = match channels.last() {
+                Some(last) => last.clone(),
+                None => break,
+            };
+            channels.push(flux::settings::Noise {
+                scale: last.scale * 2.0,
+                multiplier: last.multiplier * 0.5,
+                ..last
+            });
+        }
+
+        for channel in channels.iter_mut() {
+            channel.scale *= self.noise_scale;
+        }
+        channels
+    }
+}
+
+impl Default for FluxSettings {
+    fn default() -> Self {
+        Self {
+            color_mode: ColorMode::Preset(ColorPreset::Original),
+            monitor_color_modes: BTreeMap::new(),
+            line_width_scale: 1.0,
+            resolution_independent: true,
+            substeps: 1,
+            viscosity: None,
+            velocity_dissipation: None,
+            noise_scale: 1.0,
+            noise_octaves: None,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+// How the screensaver behaves, independent of what Flux looks like
+pub struct ScreensaverSettings {
+    // How long to fade in on startup, in milliseconds. The fade-out on exit is shorter. Set to 0
+    // to disable fading.
+    pub fade_duration_ms: u64,
+
+    // Save power by cycling between animating at the full frame rate for `active_duration_secs`
+    // and drawing a frame every second for `idle_duration_secs`. Set the idle duration to 0 to
+    // always animate.
+    pub active_duration_secs: u64,
+    pub idle_duration_secs: u64,
+
+    // Exit on its own after running for this long, like on a kiosk that hands control back to
+    // another app. Set to 0 to run until there’s input.
+    pub max_runtime_secs: u64,
+
+    // Ignore input for this long after starting, in milliseconds, so that a key that’s still held
+    // down or a drifting mouse doesn’t close the screensaver straight away.
+    pub input_grace_ms: u64,
+    // How far the mouse can move before the screensaver closes, in pixels at 100% display
+    // scaling. It’s scaled with each display’s DPI, so it feels the same on every display.
+    pub mouse_exit_distance: f32,
+
+    // Keys that don’t close the screensaver, as Windows virtual-key codes in decimal. Defaults to
+    // the volume and media keys, 173 to 179. Mouse movement and clicks always close it.
+    pub ignored_keys: Vec<u32>,
+
+    // Keep the screen blank for this long after starting, in milliseconds, before the animation
+    // begins. Input during the delay closes the screensaver straight away, without a fade.
+    pub startup_delay_ms: u64,
+    // If nothing has been presented this long after the animation should have started, fall back
+    // to presenting with plain OpenGL. If even that doesn’t help, exit rather than leave a black
+    // screen. Set to 0 to wait forever.
+    pub first_frame_timeout_secs: u64,
+
+    // Draw at most this many frames per second. Set to 0 to draw as many as the display shows.
+    pub max_frame_rate: u32,
+    // How frames are handed to the display. See `PresentMode`.
+    #[serde(deserialize_with = "or_default")]
+    pub present_mode: PresentMode,
+    // Whether to run the animation at all. See `PowerMode`.
+    #[serde(deserialize_with = "or_default")]
+    pub power_mode: PowerMode,
+
+    // The color shown before the first frame is rendered, as RGB values from 0 to 1.
+    pub background_color: Color,
+
+    // Render at a fraction of the display resolution and upscale the result. Lower values help
+    // weaker GPUs keep up on high-resolution displays.
+    pub render_scale: f32,
+    #[serde(deserialize_with = "or_default")]
+    pub upscale_filter: UpscaleFilter,
+    // How to smooth out jagged edges. See `AntiAliasing`.
+    #[serde(deserialize_with = "or_default")]
+    pub anti_aliasing: AntiAliasing,
+    // How each frame starts out before Flux draws over it. See `FrameClear`.
+    #[serde(deserialize_with = "or_default")]
+    pub frame_clear: FrameClear,
+    // With `FrameClear::Trails`, how much of the previous frames to keep each frame. Higher values
+    // make longer trails.
+    pub trail_persistence: f32,
+    // Pick a random look every time the screensaver starts. The seed is logged, and setting it as
+    // `flux.seed` brings back the same look.
+    pub randomize_on_launch: bool,
+    // Log a one-line summary on exit, with the number of frames, the average frame rate, and how
+    // they were presented. It only goes to the local log file.
+    pub log_session_summary: bool,
+    // Slow the animation right down when “Show animations in Windows” is turned off. Disable this
+    // to always animate at full speed.
+    pub respect_reduced_motion: bool,
+    // Draw fewer, bolder lines in previews the size of a thumbnail, like the one in the Screen
+    // Saver Settings dialog. See `Config::fit_thumbnail`.
+    pub simplify_thumbnails: bool,
+
+    // Adjustments to the final picture, for a dimmer or more readable look
+    pub brightness: f32,
+    pub contrast: f32,
+    pub invert_colors: bool,
+    pub night_mode: NightMode,
+    // Drift the whole picture by a few pixels over several minutes, so nothing bright stays on
+    // the same pixels of an OLED display
+    pub pixel_shift: bool,
+    // An image drawn over the animation, like a logo on a kiosk
+    pub watermark: WatermarkSettings,
+}
+
+impl ScreensaverSettings {
+    pub const MAX_FADE_DURATION_MS: u64 = 5000;
+    pub const MAX_INPUT_GRACE_MS: u64 = 5000;
+    pub const DEFAULT_MOUSE_EXIT_DISTANCE: f32 = 10.0;
+    pub const MOUSE_EXIT_DISTANCE_RANGE: std::ops::RangeInclusive<f32> = 1.0..=500.0;
+    pub const MAX_STARTUP_DELAY_MS: u64 = 60 * 1000;
+    pub const MAX_FIRST_FRAME_TIMEOUT_SECS: u64 = 120;
+    pub const MAX_CYCLE_DURATION_SECS: u64 = 60 * 60;
+    pub const MAX_FRAME_RATE: u32 = 240;
+    // VK_VOLUME_MUTE through VK_MEDIA_PLAY_PAUSE
+    pub const DEFAULT_IGNORED_KEYS: [u32; 7] = [0xAD, 0xAE, 0xAF, 0xB0, 0xB1, 0xB2, 0xB3];
+    pub const MIN_RENDER_SCALE: f32 = 0.5;
+    pub const BRIGHTNESS_RANGE: std::ops::RangeInclusive<f32> = 0.25..=1.5;
+    pub const CONTRAST_RANGE: std::ops::RangeInclusive<f32> = 0.5..=2.0;
+    // Past this, the trails never quite fade out.
+    pub const TRAIL_PERSISTENCE_RANGE: std::ops::RangeInclusive<f32> = 0.0..=0.95;
+
+    pub fn fade_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.fade_duration_ms)
+    }
+
+    pub fn active_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.active_duration_secs)
+    }
+
+    pub fn idle_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.idle_duration_secs)
+    }
+
+    pub fn input_grace(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.input_grace_ms)
+    }
+
+    pub fn startup_delay(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.startup_delay_ms)
+    }
+
+    pub fn first_frame_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.first_frame_timeout_secs)
+    }
+
+    // The shortest time between frames, or zero for no limit.
+    pub fn min_frame_interval(&self) -> std::time::Duration {
+        let interval = match self.max_frame_rate {
+            0 => std::time::Duration::ZERO,
+            max_frame_rate => std::time::Duration::from_secs(1) / max_frame_rate,
+        };
+
+        match self.power_mode {
+            PowerMode::Animate => interval,
+            // The color barely changes from one second to the next.
+            PowerMode::SolidColor => interval.max(std::time::Duration::from_secs(1)),
+        }
+    }
+
+    pub fn max_runtime(&self) -> Option<std::time::Duration> {
+        (self.max_runtime_secs > 0).then(|| std::time::Duration::from_secs(self.max_runtime_secs))
+    }
+
+    pub fn clear_color(&self) -> [f32; 4] {
+        let [r, g, b] = self.background_color;
+        [r, g, b, 1.0]
+    }
+}
+
+impl Default for ScreensaverSettings {
+    fn default() -> Self {
+        Self {
+            fade_duration_ms: 500,
+            active_duration_secs: 30,
+            idle_duration_secs: 0,
+            max_runtime_secs: 0,
+            input_grace_ms: 750,
+            mouse_exit_distance: Self::DEFAULT_MOUSE_EXIT_DISTANCE,
+            ignored_keys: Self::DEFAULT_IGNORED_KEYS.to_vec(),
+            startup_delay_ms: 0,
+            first_frame_timeout_secs: 10,
+            max_frame_rate: 60,
+            present_mode: Default::default(),
+            power_mode: Default::default(),
+            background_color: [0.0, 0.0, 0.0],
+            render_scale: 1.0,
+            upscale_filter: Default::default(),
+            anti_aliasing: Default::default(),
+            frame_clear: Default::default(),
+            trail_persistence: 0.75,
+            randomize_on_launch: false,
+            log_session_summary: true,
+            respect_reduced_motion: true,
+            simplify_thumbnails: true,
+            brightness: 1.0,
+            contrast: 1.0,
+            invert_colors: false,
+            night_mode: Default::default(),
+            pixel_shift: false,
+            watermark: Default::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+// Dim the picture at night, like for a screen in a bedroom
+pub struct NightMode {
+    pub enabled: bool,
+    // When the night starts and ends, in minutes after midnight in local time. The night can run
+    // past midnight.
+    pub start_minute: u32,
+    pub end_minute: u32,
+    // The brightness at night, on top of the picture brightness
+    pub brightness: f32,
+    // How long to take to dim after the night starts, and to brighten again after it ends
+    pub ramp_minutes: u32,
+}
+
+impl NightMode {
+    pub const BRIGHTNESS_RANGE: std::ops::RangeInclusive<f32> = 0.05..=1.0;
+    pub const MAX_RAMP_MINUTES: u32 = 120;
+
+    // The brightness right now. The local time follows daylight saving changes, so the night
+    // always starts at the same time on the clock.
+    pub fn brightness(&self) -> f32 {
+        if !self.enabled {
+            return 1.0;
+        }
+
+        self.brightness_at(local_time_of_day())
+    }
+
+    // The brightness `minute` minutes after midnight.
+    pub fn brightness_at(&self, minute: f32) -> f32 {
+        let day = MINUTES_PER_DAY as f32;
+        let night_length = (self.end_minute as f32 - self.start_minute as f32).rem_euclid(day);
+        if !self.enabled || night_length == 0.0 {
+            return 1.0;
+        }
+
+        // Never ramp for longer than the night or the day lasts.
+        let ramp = (self.ramp_minutes as f32)
+            .min(night_length)
+            .min(day - night_length);
+        let progress = |minutes: f32| {
+            if ramp <= 0.0 {
+                1.0
+            } else {
+                (minutes / ramp).clamp(0.0, 1.0)
+            }
+        };
+
+        let into_night = (minute - self.start_minute as f32).rem_euclid(day);
+        let dimmed = if into_night < night_length {
+            progress(into_night)
+        } else {
+            1.0 - progress(into_night - night_length)
+        };
+
+        1.0 + (self.brightness - 1.0) * dimmed
+    }
+}
+
+impl Default for NightMode {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start_minute: 22 * 60,
+            end_minute: 7 * 60,
+            brightness: 0.4,
+            ramp_minutes: 30,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct WatermarkSettings {
+    // A PNG to draw. Relative paths start from the folder the settings file is in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<path::PathBuf>,
+    #[serde(deserialize_with = "or_default")]
+    pub corner: Corner,
+    pub opacity: f32,
+}
+
+impl Default for WatermarkSettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            corner: Default::default(),
+            opacity: 0.8,
+        }
+    }
+}
+
+#[derive(Default, Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+#[derive(Default, Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PowerMode {
+    // Run Flux
+    #[default]
+    Animate,
+    // Skip the simulation and show a single color that slowly shifts through the palette, once a
+    // second. This keeps the display on with next to no GPU use, for laptops on battery or
+    // machines that run hot.
+    SolidColor,
+}
+
+// How each frame starts out.
+//
+// The DXGI swapchain uses the DISCARD swap effect, which leaves the back buffer undefined after
+// every present. Without a clear, faint afterimages of older frames can show through wherever Flux
+// blends instead of overwriting.
+#[derive(Default, Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FrameClear {
+    // Clear to the background color, so that every frame starts from scratch
+    #[default]
+    Clear,
+    // Blend each frame over the previous ones for motion trails. The frames are kept in a texture
+    // of our own, so this works with any swap effect.
+    Trails,
+}
+
+// How to upscale frames rendered below the display resolution
+#[derive(Default, Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UpscaleFilter {
+    // Sharp, blocky pixels
+    Nearest,
+    // Smooth, slightly blurry pixels
+    #[default]
+    Linear,
+}
+
+impl UpscaleFilter {
+    pub const ALL: [UpscaleFilter; 2] = [UpscaleFilter::Nearest, UpscaleFilter::Linear];
+}
+
+impl fmt::Display for UpscaleFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                UpscaleFilter::Nearest => "Sharp",
+                UpscaleFilter::Linear => "Smooth",
+            }
+        )
+    }
+}
+
+// How to smooth out the edges of the lines. Both kinds work on our offscreen frame, so the window
+// itself is never multisampled.
+#[derive(Default, Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AntiAliasing {
+    #[default]
+    Off,
+    // A blur along the edges of the finished frame. Cheap, but slightly soft.
+    Fxaa,
+    // Render each frame with several samples per pixel and average them
+    Msaa2,
+    Msaa4,
+}
+
+impl AntiAliasing {
+    pub const ALL: [AntiAliasing; 4] = [
+        AntiAliasing::Off,
+        AntiAliasing::Fxaa,
+        AntiAliasing::Msaa2,
+        AntiAliasing::Msaa4,
+    ];
+
+    // The number of samples per pixel to render with, or 0 to render without multisampling
+    pub fn samples(&self) -> i32 {
+        match self {
+            AntiAliasing::Off | AntiAliasing::Fxaa => 0,
+            AntiAliasing::Msaa2 => 2,
+            AntiAliasing::Msaa4 => 4,
+        }
+    }
+}
+
+impl fmt::Display for AntiAliasing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                AntiAliasing::Off => "No anti-aliasing",
+                AntiAliasing::Fxaa => "FXAA",
+                AntiAliasing::Msaa2 => "2× MSAA",
+                AntiAliasing::Msaa4 => "4× MSAA",
+            }
+        )
+    }
+}
+
+// How to keep frames in step with the display.
+#[derive(Default, Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PresentMode {
+    // Wait for the display to refresh before showing each frame. Smooth, but frames queue up
+    // behind the one on screen.
+    #[default]
+    Vsync,
+    // Present straight away, without waiting, and instead draw no faster than the display
+    // refreshes. Frames reach the screen sooner, at the cost of some tearing.
+    MatchRefreshRate,
+}
+
+impl PresentMode {
+    pub const ALL: [PresentMode; 2] = [PresentMode::Vsync, PresentMode::MatchRefreshRate];
+
+    pub fn waits_for_vsync(&self) -> bool {
+        *self == PresentMode::Vsync
+    }
+}
+
+impl fmt::Display for PresentMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PresentMode::Vsync => "Vsync",
+                PresentMode::MatchRefreshRate => "Match the refresh rate, without vsync",
+            }
+        )
+    }
+}
+
+// Saved by variant name, not position, so that reordering `ALL` doesn’t change anyone’s colors.
+// Renaming a variant needs a serde alias for the old name.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ColorMode {
+    Preset(flux::settings::ColorPreset),
+    Gradient(Gradient),
+    // A palette from `palettes.toml`, by name
+    Custom(PaletteName),
+    DesktopImage,
+    // Change colors with the time of day
+    Auto,
+}
+
+impl Default for ColorMode {
+    fn default() -> Self {
+        Self::Preset(Default::default())
+    }
+}
+
+use flux::settings::ColorPreset;
+impl ColorMode {
+    pub const ALL: [ColorMode; 10] = [
+        ColorMode::Preset(ColorPreset::Original),
+        ColorMode::Preset(ColorPreset::Plasma),
+        ColorMode::Preset(ColorPreset::Poolside),
+        ColorMode::Gradient(Gradient::Sunset),
+        ColorMode::Gradient(Gradient::Mono),
+        ColorMode::Gradient(Gradient::Deuteranopia),
+        ColorMode::Gradient(Gradient::Protanopia),
+        ColorMode::Gradient(Gradient::Tritanopia),
+        ColorMode::DesktopImage,
+        ColorMode::Auto,
+    ];
+
+    // The built-in color modes, with the custom palettes after the built-in ones.
+    pub fn all() -> Vec<ColorMode> {
+        let custom = palette::custom_palettes()
+            .into_iter()
+            .map(|palette| ColorMode::Custom(PaletteName::new(palette.name)));
+        let all = ColorMode::ALL;
+        let (built_in, others) = all.split_at(all.len() - 2);
+
+        built_in
+            .iter()
+            .cloned()
+            .chain(custom)
+            .chain(others.iter().cloned())
+            .collect()
+    }
+
+    // Pick a color mode for a minute of the day, counting from midnight.
+    pub fn for_time_of_day(minute: u32) -> ColorMode {
+        let minute = minute % MINUTES_PER_DAY;
+        let (_, color_mode) = TIME_OF_DAY_SCHEDULE
+            .iter()
+            .rev()
+            .find(|(start, _)| minute >= *start)
+            // Before the first period starts, it’s still night from the day before.
+            .unwrap_or(&TIME_OF_DAY_SCHEDULE[TIME_OF_DAY_SCHEDULE.len() - 1]);
+        color_mode.clone()
+    }
+
+    // A short explanation for the settings window and `--list-modes`.
+    pub fn description(&self) -> &'static str {
+        match self {
+            ColorMode::Preset(ColorPreset::Original) => "The classic Flux colors",
+            ColorMode::Preset(ColorPreset::Plasma) => "Deep blue through magenta to yellow",
+            ColorMode::Preset(ColorPreset::Poolside) => "Soft teals and pinks",
+            ColorMode::Preset(ColorPreset::Freedom) => "Blue and yellow",
+            ColorMode::Gradient(Gradient::Sunset) => "Warm purples, reds, and oranges",
+            ColorMode::Gradient(Gradient::Mono) => "Shades of grey",
+            ColorMode::Gradient(Gradient::Deuteranopia) => "Easier to tell apart with deuteranopia",
+            ColorMode::Gradient(Gradient::Protanopia) => "Easier to tell apart with protanopia",
+            ColorMode::Gradient(Gradient::Tritanopia) => "Easier to tell apart with tritanopia",
+            ColorMode::Custom(_) => "Your own palette, from palettes.toml",
+            ColorMode::DesktopImage => "Colors sampled from your desktop wallpaper",
+            ColorMode::Auto => "Changes with the time of day",
+        }
+    }
+
+    // The colors used by this mode, if they’re known ahead of time. A palette missing from
+    // palettes.toml has the default colors, like when Flux runs.
+    pub fn palette(&self) -> Option<Cow<'static, [Color]>> {
+        match self {
+            ColorMode::Preset(preset) => Some(Cow::Borrowed(palette::preset_colors(*preset))),
+            ColorMode::Gradient(gradient) => Some(Cow::Borrowed(gradient.colors())),
+            ColorMode::Custom(name) => name
+                .colors()
+                .map(Cow::Owned)
+                .or_else(|| ColorMode::default().palette()),
+            ColorMode::DesktopImage => None,
+            ColorMode::Auto => ColorMode::for_time_of_day(local_minute_of_day()).palette(),
+        }
+    }
+}
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+// The color modes that `ColorMode::Auto` cycles through, and the minute of the day each one starts
+// at. Cool colors around midday, warm colors at night.
+static TIME_OF_DAY_SCHEDULE: [(u32, ColorMode); 4] = [
+    (5 * 60, ColorMode::Preset(ColorPreset::Poolside)),
+    (10 * 60, ColorMode::Preset(ColorPreset::Original)),
+    (17 * 60, ColorMode::Preset(ColorPreset::Plasma)),
+    (21 * 60, ColorMode::Gradient(Gradient::Sunset)),
+];
+
+#[cfg(windows)]
+fn local_minute_of_day() -> u32 {
+    crate::platform::windows::local_time::minute_of_day()
+}
+
+// Use UTC, since there’s no time zone to go on.
+#[cfg(not(windows))]
+fn local_minute_of_day() -> u32 {
+    let since_epoch = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    (since_epoch.as_secs() / 60 % MINUTES_PER_DAY as u64) as u32
+}
+
+#[cfg(windows)]
+fn local_time_of_day() -> f32 {
+    crate::platform::windows::local_time::time_of_day()
+}
+
+#[cfg(not(windows))]
+fn local_time_of_day() -> f32 {
+    let since_epoch = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    (since_epoch.as_secs_f64() / 60.0 % MINUTES_PER_DAY as f64) as f32
+}
+
+impl std::fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorMode::Preset(preset) => {
+                use flux::settings::ColorPreset::*;
+                write!(
+                    f,
+                    "{}",
+                    match preset {
+                        Original => "Original",
+                        Plasma => "Plasma",
+                        Poolside => "Poolside",
+                        Freedom => "Freedom",
+                    }
+                )
+            }
+            ColorMode::Gradient(gradient) => write!(f, "{}", gradient),
+            ColorMode::Custom(name) => write!(f, "{}", name),
+            ColorMode::DesktopImage => write!(f, "From wallpaper"),
+            ColorMode::Auto => write!(f, "Time of day"),
         }
     }
 }
 
-#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
-pub struct FluxSettings {
-    pub color_mode: ColorMode,
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+// Configuration for the settings window itself
+pub struct SettingsWindowConfig {
+    // The last size of the window in logical pixels
+    pub size: (u32, u32),
+    // Where the top-left corner of the window was last closed, in logical pixels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<(i32, i32)>,
+    // The name of the monitor the window was last on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<String>,
+    #[serde(deserialize_with = "or_default")]
+    pub theme: SettingsTheme,
+    // Save changes automatically, shortly after the last edit
+    pub auto_save: bool,
+    // Open the settings window once the screensaver closes, the first time it runs without a
+    // settings file. Deployments that don’t ship one can turn this off in the default profile.
+    pub open_on_first_run: bool,
 }
 
-#[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq, Eq)]
-pub enum ColorMode {
-    Preset(flux::settings::ColorPreset),
-    DesktopImage,
+impl SettingsWindowConfig {
+    pub const MIN_SIZE: (u32, u32) = (420, 460);
+    pub const MAX_SIZE: (u32, u32) = (3840, 2160);
 }
 
-impl Default for ColorMode {
+impl Default for SettingsWindowConfig {
     fn default() -> Self {
-        Self::Preset(Default::default())
+        Self {
+            size: (500, 500),
+            position: None,
+            monitor: None,
+            theme: Default::default(),
+            auto_save: false,
+            open_on_first_run: true,
+        }
     }
 }
 
-use flux::settings::ColorPreset;
-impl ColorMode {
-    pub const ALL: [ColorMode; 4] = [
-        ColorMode::Preset(ColorPreset::Original),
-        ColorMode::Preset(ColorPreset::Plasma),
-        ColorMode::Preset(ColorPreset::Poolside),
-        ColorMode::DesktopImage,
+#[derive(Default, Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SettingsTheme {
+    // Follow the Windows app mode
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+impl SettingsTheme {
+    pub const ALL: [SettingsTheme; 3] = [
+        SettingsTheme::System,
+        SettingsTheme::Dark,
+        SettingsTheme::Light,
     ];
 }
 
-impl std::fmt::Display for ColorMode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Display for SettingsTheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
             "{}",
             match self {
-                ColorMode::Preset(preset) => {
-                    use flux::settings::ColorPreset::*;
-                    match preset {
-                        Original => "Original",
-                        Plasma => "Plasma",
-                        Poolside => "Poolside",
-                        Freedom => "Freedom",
-                    }
-                }
-                ColorMode::DesktopImage => "From wallpaper",
+                SettingsTheme::System => "System",
+                SettingsTheme::Dark => "Dark",
+                SettingsTheme::Light => "Light",
             }
         )
     }
 }
 
-#[derive(Default, Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Default, Deserialize, Serialize, Clone, Debug, PartialEq)]
 #[serde(default)]
 // Platform-specific configuration
 pub struct PlatformConfig {
@@ -169,13 +1370,75 @@ pub struct PlatformConfig {
     pub windows: WindowsConfig,
 }
 
-#[derive(Default, Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 #[serde(default)]
 // Windows-specific configuration
 pub struct WindowsConfig {
+    #[serde(deserialize_with = "or_default")]
     pub fill_mode: FillMode,
+    // Which part of the frame to keep when a mirrored display has a different shape from the one
+    // it mirrors, and the frame is cropped to fill it
+    #[serde(deserialize_with = "or_default")]
+    pub crop_anchor: CropAnchor,
+    // Which monitors to cover, by their position rather than their name, so that it keeps working
+    // as monitors are plugged in and swapped
+    #[serde(deserialize_with = "or_default")]
+    pub monitor_selection: MonitorSelection,
+    // Prefer a graphics adapter whose name or vendor contains this text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adapter: Option<String>,
+    // Present through a DXGI swapchain, which is smoother on most GPUs. Turning this off falls
+    // back to plain OpenGL, for drivers that show a black screen with DXGI.
+    pub use_dxgi: bool,
+    // How to share the DXGI back buffer with OpenGL
+    #[serde(deserialize_with = "or_default")]
+    pub interop_attachment: InteropAttachment,
+    // As the wallpaper, stop rendering while a fullscreen app like a game has the foreground, to
+    // leave the GPU to it.
+    pub pause_for_fullscreen_apps: bool,
+    // As the wallpaper, what to do while a toast notification is showing, so that it doesn’t have
+    // to compete with the animation
+    #[serde(deserialize_with = "or_default")]
+    pub notification_response: NotificationResponse,
+    // The titles of the shell’s notification window to look for. Windows translates the title, so
+    // other display languages need their own added here.
+    pub notification_window_titles: Vec<String>,
+    // Over Remote Desktop or in session 0, present with plain OpenGL and cap the frame rate lower
+    // than `max_frame_rate`. Turn this off to render the same way as locally.
+    pub adapt_to_remote_sessions: bool,
+    // On graphics adapters with less dedicated memory than `low_video_memory_mb`, like most
+    // integrated GPUs, lower the render scale and skip MSAA, so that large displays don’t run out
+    // of memory.
+    pub adapt_to_low_video_memory: bool,
+    pub low_video_memory_mb: u64,
+}
+
+impl WindowsConfig {
+    // The render scale on adapters with little video memory
+    pub const LOW_VIDEO_MEMORY_RENDER_SCALE: f32 = 0.75;
+}
+
+impl Default for WindowsConfig {
+    fn default() -> Self {
+        Self {
+            // Spanning covers a row of matching 16:9 displays with a single, seamless instance
+            fill_mode: FillMode::Span,
+            crop_anchor: CropAnchor::Center,
+            monitor_selection: MonitorSelection::All,
+            adapter: None,
+            use_dxgi: true,
+            interop_attachment: InteropAttachment::Auto,
+            pause_for_fullscreen_apps: true,
+            notification_response: NotificationResponse::Ignore,
+            notification_window_titles: vec!["New notification".to_string()],
+            adapt_to_remote_sessions: true,
+            adapt_to_low_video_memory: true,
+            low_video_memory_mb: 512,
+        }
+    }
 }
 
+// Saved by variant name, like `ColorMode`.
 #[derive(Default, Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq)]
 pub enum FillMode {
     // Display a separate instance on each display
@@ -185,10 +1448,45 @@ pub enum FillMode {
     Span,
     // Fill all displays with a single surface
     Fill,
+    // Show the same frame on every display
+    Mirror,
 }
 
 impl FillMode {
-    pub const ALL: [FillMode; 3] = [FillMode::None, FillMode::Span, FillMode::Fill];
+    pub const ALL: [FillMode; 4] = [
+        FillMode::None,
+        FillMode::Span,
+        FillMode::Fill,
+        FillMode::Mirror,
+    ];
+
+    // A short explanation for the settings window and `--list-modes`.
+    pub fn description(&self) -> &'static str {
+        match self {
+            FillMode::None => "Each monitor is a separate surface.",
+            FillMode::Span => "Combines any matching adjacent monitors.",
+            FillMode::Fill => "Combines all monitors into a single seamless surface.",
+            FillMode::Mirror => "Shows the same animation on every monitor.",
+        }
+    }
+}
+
+// List the color and fill modes as they’re written in the settings file.
+pub fn list_modes() -> String {
+    fn entry<T: Serialize>(value: &T, name: impl fmt::Display, description: &str) -> String {
+        let value = serde_json::to_string(value).unwrap_or_default();
+        format!("  {:<32} {}: {}\n", value, name, description)
+    }
+
+    let mut list = String::from("Color modes (flux.color_mode):\n");
+    for color_mode in ColorMode::all() {
+        list.push_str(&entry(&color_mode, &color_mode, color_mode.description()));
+    }
+    list.push_str("\nFill modes (platform.windows.fill_mode):\n");
+    for fill_mode in FillMode::ALL {
+        list.push_str(&entry(&fill_mode, fill_mode, fill_mode.description()));
+    }
+    list
 }
 
 impl fmt::Display for FillMode {
@@ -200,11 +1498,207 @@ impl fmt::Display for FillMode {
                 FillMode::None => "None",
                 FillMode::Span => "Span",
                 FillMode::Fill => "Fill",
+                FillMode::Mirror => "Mirror",
+            }
+        )
+    }
+}
+
+// Which part of a frame to keep when cropping off the top and bottom to fill a wider display. Crops
+// off the sides always keep the middle. Saved by variant name, like `FillMode`.
+#[derive(Default, Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CropAnchor {
+    Top,
+    #[default]
+    Center,
+    Bottom,
+}
+
+impl CropAnchor {
+    pub const ALL: [CropAnchor; 3] = [CropAnchor::Top, CropAnchor::Center, CropAnchor::Bottom];
+}
+
+impl fmt::Display for CropAnchor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                CropAnchor::Top => "Keep the top",
+                CropAnchor::Center => "Keep the middle",
+                CropAnchor::Bottom => "Keep the bottom",
+            }
+        )
+    }
+}
+
+// Which monitors the screensaver covers. The primary monitor is looked up each time the screensaver
+// starts. Saved by variant name, like `FillMode`.
+#[derive(Default, Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MonitorSelection {
+    #[default]
+    All,
+    PrimaryOnly,
+    AllExceptPrimary,
+}
+
+impl MonitorSelection {
+    pub const ALL: [MonitorSelection; 3] = [
+        MonitorSelection::All,
+        MonitorSelection::PrimaryOnly,
+        MonitorSelection::AllExceptPrimary,
+    ];
+
+    pub fn includes(&self, is_primary: bool) -> bool {
+        match self {
+            MonitorSelection::All => true,
+            MonitorSelection::PrimaryOnly => is_primary,
+            MonitorSelection::AllExceptPrimary => !is_primary,
+        }
+    }
+}
+
+impl fmt::Display for MonitorSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                MonitorSelection::All => "All monitors",
+                MonitorSelection::PrimaryOnly => "Primary monitor only",
+                MonitorSelection::AllExceptPrimary => "All except the primary monitor",
             }
         )
     }
 }
 
+// The GL object the DXGI back buffer is shared as. Whichever is tried first, the other is tried if
+// it fails.
+#[derive(Default, Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum InteropAttachment {
+    // Pick based on the GPU vendor: AMD can’t share renderbuffers, so start with a texture there
+    #[default]
+    Auto,
+    Renderbuffer,
+    Texture,
+}
+
+// What the wallpaper does while a toast notification is showing
+#[derive(Default, Deserialize, Serialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum NotificationResponse {
+    #[default]
+    Ignore,
+    // Turn the brightness down until the notification goes away
+    Dim,
+    // Hold the current frame, like for fullscreen apps
+    Pause,
+}
+
+// The name of the settings file for a profile.
+fn file_name(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("settings.{}.json", profile),
+        None => "settings.json".to_string(),
+    }
+}
+
+// Reset NaN and infinite values, which can’t be clamped, recording the fix.
+fn reset_if_not_finite(name: &str, value: &mut f32, default: f32, fixes: &mut Vec<String>) {
+    if !value.is_finite() {
+        fixes.push(format!("{} {} was reset to {}", name, value, default));
+        *value = default;
+    }
+}
+
+// Clamp an optional override, dropping it if it’s not a number so that the default applies.
+fn clamp_optional_field(
+    name: &str,
+    value: &mut Option<f32>,
+    range: std::ops::RangeInclusive<f32>,
+    fixes: &mut Vec<String>,
+) {
+    match value {
+        Some(v) if !v.is_finite() => {
+            fixes.push(format!("{} {} was reset to the default", name, v));
+            *value = None;
+        }
+        Some(v) => clamp_field(name, v, range, fixes),
+        None => (),
+    }
+}
+
+// Clamp a value to a range, recording the fix if the value was out of bounds.
+fn clamp_field<T>(
+    name: &str,
+    value: &mut T,
+    range: std::ops::RangeInclusive<T>,
+    fixes: &mut Vec<String>,
+) where
+    T: PartialOrd + Copy + fmt::Display,
+{
+    let clamped = if *value < *range.start() {
+        *range.start()
+    } else if *value > *range.end() {
+        *range.end()
+    } else {
+        return;
+    };
+
+    fixes.push(format!("{} {} was clamped to {}", name, value, clamped));
+    *value = clamped;
+}
+
+// Decode a value, falling back to its default if it’s invalid or unknown.
+//
+// This keeps a single bad field, like an enum variant from a newer version, from resetting the
+// whole config.
+fn or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::de::DeserializeOwned + Default,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value.clone()).unwrap_or_else(|err| {
+        record_reset(format!("{} was reset to the default: {}", value, err));
+        T::default()
+    }))
+}
+
+// Note a value that was reset or dropped, for `Config::decode` to return.
+fn record_reset(fix: String) {
+    DECODE_RESETS.with(|resets| match resets.borrow_mut().as_mut() {
+        Some(resets) => resets.push(fix),
+        None => log::warn!("Adjusted settings: {}", fix),
+    });
+}
+
+// Decode a map, dropping the entries that are invalid or unknown, like `or_default` does for a
+// single value.
+fn without_invalid_entries<'de, D, T>(deserializer: D) -> Result<BTreeMap<String, T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    let entries = match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Object(entries) => entries,
+        value => {
+            record_reset(format!("{} was reset to the default", value));
+            return Ok(BTreeMap::new());
+        }
+    };
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|(key, value)| match serde_json::from_value(value.clone()) {
+            Ok(decoded) => Some((key, decoded)),
+            Err(err) => {
+                record_reset(format!("{} for {} was dropped: {}", value, key, err));
+                None
+            }
+        })
+        .collect())
+}
+
 #[derive(Debug)]
 pub enum Problem {
     GetProjectDir,
@@ -221,6 +1715,11 @@ pub enum Problem {
         err: serde_json::Error,
     },
     NoSaveLocation,
+    // The settings file can’t be written to, like when it’s on a read-only drive
+    WriteSettings {
+        path: path::PathBuf,
+        err: io::Error,
+    },
     Save {
         path: path::PathBuf,
         err: serde_json::Error,
@@ -258,6 +1757,14 @@ impl fmt::Display for Problem {
                 )
             }
             Problem::NoSaveLocation => write!(f, "No location available to save the settings"),
+            Problem::WriteSettings { path, err } => {
+                write!(
+                    f,
+                    "Failed to open the settings file at {} for writing: {}",
+                    path.display(),
+                    err
+                )
+            }
             Problem::Save { path, err } => {
                 write!(
                     f,
@@ -272,3 +1779,453 @@ impl fmt::Display for Problem {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_clamps_out_of_range_values() {
+        let mut config = Config::default();
+        config.settings_window.size = (10, 100_000);
+
+        let fixes = config.sanitize();
+
+        assert_eq!(fixes.len(), 2);
+        assert_eq!(
+            config.settings_window.size,
+            (
+                SettingsWindowConfig::MIN_SIZE.0,
+                SettingsWindowConfig::MAX_SIZE.1
+            )
+        );
+    }
+
+    #[test]
+    fn it_keeps_picture_adjustments_in_range() {
+        let mut config = Config::default();
+        config.screensaver.brightness = f32::NAN;
+        config.screensaver.contrast = 10.0;
+
+        let fixes = config.sanitize();
+
+        assert_eq!(fixes.len(), 2);
+        assert_eq!(config.screensaver.brightness, 1.0);
+        assert_eq!(
+            config.screensaver.contrast,
+            *ScreensaverSettings::CONTRAST_RANGE.end()
+        );
+    }
+
+    #[test]
+    fn it_keeps_substeps_in_range() {
+        let mut config = Config::default();
+        config.flux.substeps = 0;
+        assert_eq!(config.sanitize().len(), 1);
+        assert_eq!(config.flux.substeps, 1);
+
+        config.flux.substeps = 100;
+        assert_eq!(config.sanitize().len(), 1);
+        assert_eq!(config.flux.substeps, 8);
+    }
+
+    #[test]
+    fn it_keeps_noise_octaves_in_range() {
+        let mut config = Config::default();
+        config.flux.noise_octaves = Some(0);
+        assert_eq!(config.sanitize().len(), 1);
+        assert_eq!(config.flux.noise_octaves, Some(1));
+
+        config.flux.noise_octaves = Some(64);
+        assert_eq!(config.sanitize().len(), 1);
+        assert_eq!(config.flux.noise_octaves, Some(6));
+    }
+
+    #[test]
+    fn it_drops_invalid_fluid_overrides() {
+        let mut config = Config::default();
+        config.flux.viscosity = Some(f32::NAN);
+        config.flux.velocity_dissipation = Some(5.0);
+
+        assert_eq!(config.sanitize().len(), 2);
+        assert_eq!(config.flux.viscosity, None);
+        assert_eq!(config.flux.velocity_dissipation, Some(1.0));
+    }
+
+    #[test]
+    fn it_runs_indefinitely_without_a_max_runtime() {
+        let mut settings = ScreensaverSettings::default();
+        assert_eq!(settings.max_runtime(), None);
+
+        settings.max_runtime_secs = 90;
+        assert_eq!(
+            settings.max_runtime(),
+            Some(std::time::Duration::from_secs(90))
+        );
+    }
+
+    #[test]
+    fn it_resets_unknown_enum_values() {
+        let config: Config = serde_json::from_str(
+            r#"{ "flux": { "color_mode": "Rainbow" }, "platform": { "windows": { "fill_mode": "Stretch" } } }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.flux.color_mode, ColorMode::default());
+        #[cfg(windows)]
+        assert_eq!(config.platform.windows.fill_mode, FillMode::default());
+
+        let config: Config =
+            serde_json::from_str(r#"{ "screensaver": { "anti_aliasing": "Msaa16" } }"#).unwrap();
+        assert_eq!(config.screensaver.anti_aliasing, AntiAliasing::Off);
+    }
+
+    #[test]
+    fn it_keeps_palettes_missing_from_palettes_toml() {
+        let config: Config =
+            serde_json::from_str(r#"{ "flux": { "color_mode": { "Custom": "Ocean" } } }"#).unwrap();
+        let color_mode = ColorMode::Custom(PaletteName::new("Ocean"));
+        assert_eq!(config.flux.color_mode, color_mode);
+        assert_eq!(color_mode.palette(), ColorMode::default().palette());
+
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["flux"]["color_mode"]["Custom"], "Ocean");
+    }
+
+    #[test]
+    fn it_reports_unknown_values_when_decoding() {
+        // Decoding some other way doesn’t leave anything behind.
+        let _: Config =
+            serde_json::from_str(r#"{ "flux": { "color_mode": "LeftOver" } }"#).unwrap();
+
+        let (config, resets) =
+            Config::decode(r#"{ "flux": { "color_mode": "NotAColorMode" } }"#).unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(resets.len(), 1);
+        assert!(resets[0].contains("NotAColorMode"), "{:?}", resets);
+    }
+
+    #[test]
+    fn it_reports_unknown_values_when_loading() {
+        let path =
+            std::env::temp_dir().join(format!("flux-test-unknown-{}.json", std::process::id()));
+        fs::write(&path, r#"{ "flux": { "color_mode": "NotAColorMode" } }"#).unwrap();
+        let result = Config::load_existing_config(&path);
+        let _ = fs::remove_file(&path);
+
+        let (_, fixes) = result.unwrap();
+        assert!(
+            fixes.iter().any(|fix| fix.contains("NotAColorMode")),
+            "{:?}",
+            fixes
+        );
+    }
+
+    #[test]
+    fn it_selects_monitors_by_position() {
+        assert!(MonitorSelection::All.includes(false));
+        assert!(MonitorSelection::PrimaryOnly.includes(true));
+        assert!(!MonitorSelection::PrimaryOnly.includes(false));
+        assert!(!MonitorSelection::AllExceptPrimary.includes(true));
+        assert!(MonitorSelection::AllExceptPrimary.includes(false));
+    }
+
+    #[test]
+    fn it_uses_the_color_mode_of_each_monitor() {
+        let config: Config = serde_json::from_str(
+            r#"{ "flux": {
+                "color_mode": { "Preset": "Poolside" },
+                "monitor_color_modes": {
+                    "\\\\.\\DISPLAY2": { "Gradient": "Tritanopia" },
+                    "\\\\.\\DISPLAY3": "Rainbow"
+                }
+            } }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.for_monitor(Some(r"\\.\DISPLAY2")).flux.color_mode,
+            ColorMode::Gradient(Gradient::Tritanopia)
+        );
+        // Unknown color modes are dropped, and the monitor falls back to the global one.
+        assert_eq!(config.flux.monitor_color_modes.len(), 1);
+        assert_eq!(
+            config.for_monitor(Some(r"\\.\DISPLAY3")).flux.color_mode,
+            ColorMode::Preset(ColorPreset::Poolside)
+        );
+        assert_eq!(
+            config.for_monitor(None).flux.color_mode,
+            ColorMode::Preset(ColorPreset::Poolside)
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn it_uses_less_video_memory_when_asked() {
+        let mut config = Config::default();
+        config.screensaver.anti_aliasing = AntiAliasing::Msaa4;
+
+        assert_eq!(config.fit_low_video_memory().len(), 2);
+        assert_eq!(
+            config.screensaver.render_scale,
+            WindowsConfig::LOW_VIDEO_MEMORY_RENDER_SCALE
+        );
+        assert_eq!(config.screensaver.anti_aliasing, AntiAliasing::Fxaa);
+
+        // Settings that are already light enough are left alone.
+        assert!(config.fit_low_video_memory().is_empty());
+    }
+
+    #[test]
+    fn it_simplifies_thumbnails() {
+        let mut config = Config::default();
+        config.screensaver.render_scale = 0.5;
+
+        assert_eq!(config.fit_thumbnail().len(), 3);
+        assert!(!config.flux.resolution_independent);
+        assert_eq!(config.screensaver.render_scale, 1.0);
+
+        // Settings that already suit a thumbnail are left alone.
+        assert!(config.fit_thumbnail().is_empty());
+    }
+
+    #[test]
+    fn it_keeps_trails_from_lasting_forever() {
+        let mut config = Config::default();
+        config.screensaver.frame_clear = FrameClear::Trails;
+        config.screensaver.trail_persistence = 1.0;
+
+        assert_eq!(config.sanitize().len(), 1);
+        assert_eq!(config.screensaver.trail_persistence, 0.95);
+    }
+
+    #[test]
+    fn it_randomizes_the_same_way_for_the_same_seed() {
+        use rand::SeedableRng;
+
+        let mut first = Config::default();
+        first.randomize(&mut rand::rngs::StdRng::seed_from_u64(42));
+        let mut second = Config::default();
+        second.randomize(&mut rand::rngs::StdRng::seed_from_u64(42));
+
+        assert_eq!(first, second);
+        assert!(first.sanitize().is_empty());
+    }
+
+    #[test]
+    fn it_finds_the_watermark_next_to_the_settings_file() {
+        let mut config = Config::default();
+        config.location = Some(path::PathBuf::from("deploy").join("settings.json"));
+
+        config.screensaver.watermark.path = Some(path::PathBuf::from("logo.png"));
+        assert_eq!(
+            config.watermark_path(),
+            Some(path::Path::new("deploy").join("logo.png"))
+        );
+
+        let absolute = std::env::temp_dir().join("logo.png");
+        config.screensaver.watermark.path = Some(absolute.clone());
+        assert_eq!(config.watermark_path(), Some(absolute));
+    }
+
+    #[test]
+    fn it_lists_modes_as_they_are_saved() {
+        let list = list_modes();
+
+        assert!(list.contains(r#"{"Gradient":"Tritanopia"}"#));
+        assert!(list.contains(r#""DesktopImage""#));
+        assert!(list.contains(r#""Mirror""#));
+    }
+
+    #[test]
+    fn it_saves_enums_by_name() {
+        let mut config = Config::default();
+        config.flux.color_mode = ColorMode::Gradient(Gradient::Tritanopia);
+        #[cfg(windows)]
+        {
+            config.platform.windows.fill_mode = FillMode::Mirror;
+        }
+
+        let json = serde_json::to_value(&config).unwrap();
+
+        assert_eq!(
+            json["flux"]["color_mode"],
+            serde_json::json!({ "Gradient": "Tritanopia" })
+        );
+        #[cfg(windows)]
+        assert_eq!(json["platform"]["windows"]["fill_mode"], "Mirror");
+    }
+
+    #[test]
+    fn it_reads_enums_saved_by_earlier_versions() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "flux": { "color_mode": { "Preset": "Poolside" } },
+                "platform": { "windows": { "fill_mode": "None" } }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.flux.color_mode,
+            ColorMode::Preset(ColorPreset::Poolside)
+        );
+        #[cfg(windows)]
+        assert_eq!(config.platform.windows.fill_mode, FillMode::None);
+
+        let config: Config =
+            serde_json::from_str(r#"{ "flux": { "color_mode": "DesktopImage" } }"#).unwrap();
+        assert_eq!(config.flux.color_mode, ColorMode::DesktopImage);
+    }
+
+    #[test]
+    fn it_saves_and_loads_from_a_unicode_path() {
+        let config_dir = std::env::temp_dir()
+            .join(format!("flux-test-{}", std::process::id()))
+            .join("Ünïcødé 設定 ✨");
+        let config_path = config_dir.join(file_name(None));
+
+        let mut config = Config::default().attach_location(&config_path);
+        config.screensaver.brightness = 0.5;
+        config.save().unwrap();
+
+        let loaded = Config::load(Some(&config_dir), None);
+        let _ = fs::remove_dir_all(config_dir.parent().unwrap());
+
+        assert_eq!(loaded.screensaver.brightness, 0.5);
+        assert_eq!(loaded.location(), Some(config_path.as_path()));
+    }
+
+    #[test]
+    fn it_imports_exported_settings_without_the_window_placement() {
+        let dir = std::env::temp_dir().join(format!("flux-test-share-{}", std::process::id()));
+        let shared_path = dir.join("shared.json");
+
+        let mut theirs = Config::default();
+        theirs.screensaver.brightness = 0.75;
+        theirs.settings_window.position = Some((100, 200));
+        theirs.export(&shared_path).unwrap();
+
+        let ours = Config::default().attach_location(&dir.join(file_name(None)));
+        let (imported, fixes) = ours.import(&shared_path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(fixes.is_empty());
+        assert_eq!(imported.screensaver.brightness, 0.75);
+        assert_eq!(imported.settings_window.position, None);
+        assert_eq!(imported.location(), ours.location());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn it_saves_to_a_path_longer_than_max_path() {
+        let root = std::env::temp_dir().join(format!("flux-test-long-{}", std::process::id()));
+        let config_dir = (0..12).fold(root.clone(), |dir, _| dir.join("ÄÖÜ-long-folder-name"));
+        assert!(config_dir.as_os_str().len() > 260);
+
+        let config = Config::default().attach_location(&config_dir.join(file_name(None)));
+        let result = config.save().and_then(|_| config.reload());
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(result.unwrap(), config);
+    }
+
+    #[test]
+    fn it_uses_a_separate_file_for_each_profile() {
+        assert_eq!(file_name(None), "settings.json");
+        assert_eq!(file_name(Some("work")), "settings.work.json");
+    }
+
+    #[test]
+    fn it_matches_the_installer_defaults() {
+        let config = Config::default();
+        assert_eq!(config.log_level, log::Level::Warn);
+        assert_eq!(
+            config.flux.color_mode,
+            ColorMode::Preset(ColorPreset::Original)
+        );
+        assert_eq!(config.flux.seed, None);
+        assert_eq!(config.platform.windows.fill_mode, FillMode::Span);
+        assert_eq!(config.platform.windows.adapter, None);
+        assert!(config.platform.windows.use_dxgi);
+        assert_eq!(config.screensaver.render_scale, 1.0);
+        assert_eq!(config.screensaver.max_frame_rate, 60);
+        assert_eq!(
+            config.screensaver.min_frame_interval(),
+            std::time::Duration::from_secs(1) / 60
+        );
+    }
+
+    #[test]
+    fn it_uses_the_defaults_for_an_empty_settings_file() {
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn it_picks_colors_for_the_time_of_day() {
+        let night = ColorMode::Gradient(Gradient::Sunset);
+        assert_eq!(ColorMode::for_time_of_day(0), night);
+        assert_eq!(ColorMode::for_time_of_day(5 * 60 - 1), night);
+        assert_eq!(
+            ColorMode::for_time_of_day(5 * 60),
+            ColorMode::Preset(ColorPreset::Poolside)
+        );
+        assert_eq!(
+            ColorMode::for_time_of_day(12 * 60),
+            ColorMode::Preset(ColorPreset::Original)
+        );
+        assert_eq!(ColorMode::for_time_of_day(23 * 60 + 59), night);
+    }
+
+    #[test]
+    fn it_wraps_the_time_of_day_around_midnight() {
+        assert_eq!(
+            ColorMode::for_time_of_day(MINUTES_PER_DAY + 12 * 60),
+            ColorMode::for_time_of_day(12 * 60)
+        );
+    }
+
+    fn night_mode() -> NightMode {
+        NightMode {
+            enabled: true,
+            start_minute: 22 * 60,
+            end_minute: 7 * 60,
+            brightness: 0.5,
+            ramp_minutes: 60,
+        }
+    }
+
+    #[test]
+    fn it_dims_through_midnight() {
+        let night_mode = night_mode();
+        assert_eq!(night_mode.brightness_at(12.0 * 60.0), 1.0);
+        assert_eq!(night_mode.brightness_at(23.0 * 60.0), 0.5);
+        assert_eq!(night_mode.brightness_at(3.0 * 60.0), 0.5);
+        assert_eq!(night_mode.brightness_at(8.0 * 60.0), 1.0);
+    }
+
+    #[test]
+    fn it_ramps_across_the_night_boundaries() {
+        let night_mode = night_mode();
+        assert_eq!(night_mode.brightness_at(22.0 * 60.0 + 30.0), 0.75);
+        assert_eq!(night_mode.brightness_at(7.0 * 60.0 + 30.0), 0.75);
+    }
+
+    #[test]
+    fn it_stays_bright_without_a_night() {
+        let mut night_mode = night_mode();
+        night_mode.end_minute = night_mode.start_minute;
+        assert_eq!(night_mode.brightness_at(23.0 * 60.0), 1.0);
+
+        let night_mode = NightMode::default();
+        assert_eq!(night_mode.brightness_at(23.0 * 60.0), 1.0);
+    }
+
+    #[test]
+    fn it_does_not_change_valid_configs() {
+        let mut config = Config::default();
+        assert!(config.sanitize().is_empty());
+        assert_eq!(config, Config::default());
+    }
+}
@@ -0,0 +1,87 @@
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::panic::{self, PanicInfo};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, path};
+
+// Write a crash report when Flux panics.
+//
+// There’s no console to print to when running as a screensaver, so without this a panic just
+// makes the screensaver disappear. If `show_dialog` is set, also tell the user where to find the
+// report.
+//
+// This wraps the existing panic hook, so it should be installed after logging is set up.
+pub fn install_panic_hook(report_dir: Option<&path::Path>, show_dialog: bool) {
+    let report_dir = report_dir.map(path::Path::to_owned);
+    let previous_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        if let Some(report_dir) = &report_dir {
+            match write_report(report_dir, info) {
+                Ok(report_path) if show_dialog => show_report_dialog(&report_path),
+                Ok(_) => (),
+                Err(err) => log::error!("Failed to write a crash report: {}", err),
+            }
+        }
+
+        previous_hook(info);
+    }));
+}
+
+fn write_report(report_dir: &path::Path, info: &PanicInfo) -> std::io::Result<path::PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    let mut report = String::new();
+    let _ = writeln!(report, "Flux {} crashed", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report);
+    let _ = writeln!(report, "{}", message(info));
+    if let Some(location) = info.location() {
+        let _ = writeln!(report, "at {}", location);
+    }
+    let _ = writeln!(report);
+    let _ = writeln!(report, "{}", Backtrace::force_capture());
+
+    fs::create_dir_all(report_dir)?;
+    let report_path = report_dir.join(format!("flux_crash_{}.txt", timestamp));
+    fs::write(&report_path, report)?;
+
+    Ok(report_path)
+}
+
+fn message(info: &PanicInfo) -> String {
+    let payload = info.payload();
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic".to_string()
+    }
+}
+
+#[cfg(windows)]
+fn show_report_dialog(report_path: &path::Path) {
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        MessageBoxW, MB_ICONERROR, MB_OK, MB_SYSTEMMODAL, MB_TOPMOST,
+    };
+
+    let text = HSTRING::from(format!(
+        "Flux ran into a problem and had to close.\n\nA crash report was saved to:\n{}",
+        report_path.display()
+    ));
+    unsafe {
+        MessageBoxW(
+            HWND::default(),
+            &text,
+            &HSTRING::from("Flux"),
+            MB_OK | MB_ICONERROR | MB_SYSTEMMODAL | MB_TOPMOST,
+        );
+    }
+}
+
+#[cfg(not(windows))]
+fn show_report_dialog(_report_path: &path::Path) {}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs, path};
+
+const DIAGNOSTICS_FILE: &str = "diagnostics.json";
+
+// A record of the rendering environment from the last time the screensaver ran.
+//
+// The screensaver writes this after setting up its windows, so that the settings window can show
+// it without having to create a GL context of its own.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct Diagnostics {
+    pub adapter: Option<Adapter>,
+    // How frames are presented, e.g. through the DXGI interop or plain OpenGL.
+    pub presentation: String,
+    pub swap_effect: Option<String>,
+    // How many buffers the swapchain has, and their format
+    pub buffers: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct Adapter {
+    pub name: String,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    pub dedicated_video_memory: u64,
+}
+
+impl Adapter {
+    pub fn vendor(&self) -> &'static str {
+        match self.vendor_id {
+            0x10DE => "NVIDIA",
+            0x1002 | 0x1022 => "AMD",
+            0x8086 => "Intel",
+            0x1414 => "Microsoft",
+            _ => "Unknown vendor",
+        }
+    }
+
+    // Whether the adapter’s name or vendor contains `preference`, ignoring case.
+    pub fn matches(&self, preference: &str) -> bool {
+        let preference = preference.to_lowercase();
+        self.name.to_lowercase().contains(&preference)
+            || self.vendor().to_lowercase().contains(&preference)
+    }
+}
+
+impl fmt::Display for Adapter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}, {:#06x}:{:#06x}, {} MB dedicated memory)",
+            self.name,
+            self.vendor(),
+            self.vendor_id,
+            self.device_id,
+            self.dedicated_video_memory / (1024 * 1024)
+        )
+    }
+}
+
+impl Diagnostics {
+    pub fn load(optional_dir: Option<&path::Path>) -> Option<Self> {
+        let path = optional_dir?.join(DIAGNOSTICS_FILE);
+        let contents = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    pub fn save(&self, optional_dir: Option<&path::Path>) {
+        let Some(dir) = optional_dir else {
+            return;
+        };
+
+        let result = serde_json::to_string_pretty(self)
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                fs::write(dir.join(DIAGNOSTICS_FILE), contents).map_err(|err| err.to_string())
+            });
+
+        if let Err(err) = result {
+            log::warn!("Failed to save diagnostics: {}", err);
+        }
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.adapter {
+            Some(adapter) => writeln!(f, "Adapter: {}", adapter)?,
+            None => writeln!(f, "Adapter: unknown")?,
+        }
+        write!(f, "Presentation: {}", self.presentation)?;
+        if let Some(swap_effect) = &self.swap_effect {
+            write!(f, "\nSwap effect: {}", swap_effect)?;
+        }
+        if let Some(buffers) = &self.buffers {
+            write!(f, "\nBuffers: {}", buffers)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use std::time::{Duration, Instant};
+
+// Cap the fade-out, so that exiting the screensaver still feels responsive.
+const MAX_FADE_OUT: Duration = Duration::from_millis(300);
+
+// Fade in on startup and back out on exit.
+pub struct Fade {
+    fade_in: Duration,
+    fade_out: Duration,
+    start: Instant,
+    exit: Option<Instant>,
+}
+
+impl Fade {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            fade_in: duration,
+            fade_out: duration.min(MAX_FADE_OUT),
+            start: Instant::now(),
+            exit: None,
+        }
+    }
+
+    // Start fading out. Does nothing if we’re already fading out.
+    pub fn exit(&mut self) {
+        self.exit.get_or_insert_with(Instant::now);
+    }
+
+    pub fn is_exiting(&self) -> bool {
+        self.exit.is_some()
+    }
+
+    // Whether the fade-out has finished and it’s safe to close the windows.
+    pub fn is_finished(&self) -> bool {
+        self.exit
+            .is_some_and(|exit| exit.elapsed() >= self.fade_out)
+    }
+
+    pub fn brightness(&self) -> f32 {
+        let fade_in = progress(self.start.elapsed(), self.fade_in);
+        let fade_out = self
+            .exit
+            .map_or(1.0, |exit| 1.0 - progress(exit.elapsed(), self.fade_out));
+
+        fade_in.min(fade_out)
+    }
+}
+
+fn progress(elapsed: Duration, duration: Duration) -> f32 {
+    if duration.is_zero() {
+        return 1.0;
+    }
+
+    (elapsed.as_secs_f32() / duration.as_secs_f32()).clamp(0.0, 1.0)
+}
@@ -38,6 +38,8 @@ pub(crate) fn new_gl_context(
     // A hack to create the gl_display using the invisible event window
     // we create for the preview.
     attr_window: Option<RawWindowHandle>,
+    // Share textures and buffers with an existing context
+    shared_context: Option<&PossiblyCurrentContext>,
 ) -> GLContext {
     let template = ConfigTemplateBuilder::new()
         .with_buffer_type(glutin::config::ColorBufferType::Rgb {
@@ -88,13 +90,21 @@ pub(crate) fn new_gl_context(
         HumanConfig::new(&gl_config)
     );
 
+    let new_context_attributes = || {
+        let builder = ContextAttributesBuilder::new();
+        match shared_context {
+            Some(context) => builder.with_sharing(context),
+            None => builder,
+        }
+    };
+
     // Request the minimum required OpenGL version for Flux
-    let context_attributes = ContextAttributesBuilder::new()
+    let context_attributes = new_context_attributes()
         .with_context_api(ContextApi::OpenGl(Some(Version::new(3, 3))))
         .build(Some(raw_window_handle));
 
     // Fallback to GLES 3.0 (aka WebGL 2.0)
-    let fallback_context_attributes = ContextAttributesBuilder::new()
+    let fallback_context_attributes = new_context_attributes()
         .with_context_api(ContextApi::Gles(Some(Version::new(3, 0))))
         .build(Some(raw_window_handle));
 
@@ -0,0 +1,184 @@
+use std::time::{Duration, Instant};
+
+// How often to draw a frame while idle.
+const IDLE_FRAME_INTERVAL: Duration = Duration::from_secs(1);
+
+// Alternate between animating at the full frame rate and drawing an occasional frame to save
+// power.
+//
+// An idle duration of zero disables the cycle.
+pub struct IdleCycle {
+    active: Duration,
+    idle: Duration,
+    start: Instant,
+    last_frame: Option<Instant>,
+    // The shortest time between frames, in either phase. Zero for no limit.
+    min_frame_interval: Duration,
+}
+
+impl IdleCycle {
+    pub fn new(active: Duration, idle: Duration, start: Instant) -> Self {
+        Self {
+            active,
+            idle,
+            start,
+            last_frame: None,
+            min_frame_interval: Duration::ZERO,
+        }
+    }
+
+    // Never draw frames closer together than `min_frame_interval`, like to cap the frame rate.
+    pub fn with_min_frame_interval(mut self, min_frame_interval: Duration) -> Self {
+        self.min_frame_interval = min_frame_interval;
+        self
+    }
+
+    // How long to wait before drawing the next frame. Returns `None` if a frame should be drawn
+    // now.
+    pub fn time_until_next_frame(&self, now: Instant) -> Option<Duration> {
+        let until_allowed = self
+            .last_frame
+            .map(|last_frame| {
+                self.min_frame_interval
+                    .saturating_sub(now.saturating_duration_since(last_frame))
+            })
+            .unwrap_or_default();
+
+        let wait = self
+            .time_until_cycle_frame(now)
+            .unwrap_or_default()
+            .max(until_allowed);
+        (!wait.is_zero()).then_some(wait)
+    }
+
+    // Like `time_until_next_frame`, but only for the active and idle phases.
+    fn time_until_cycle_frame(&self, now: Instant) -> Option<Duration> {
+        if self.idle.is_zero() {
+            return None;
+        }
+
+        let period = self.active + self.idle;
+        let elapsed = now.saturating_duration_since(self.start);
+        let position = Duration::from_nanos((elapsed.as_nanos() % period.as_nanos()) as u64);
+
+        if position < self.active {
+            return None;
+        }
+
+        // Draw a frame as soon as we enter the idle phase, then once every interval. Always wake
+        // up for the start of the next active phase.
+        let until_active = period - position;
+        let since_last_frame = self
+            .last_frame
+            .map(|last_frame| now.saturating_duration_since(last_frame));
+        let until_frame = match since_last_frame {
+            // We’ve already drawn a frame during this idle phase
+            Some(since) if since <= position - self.active => {
+                IDLE_FRAME_INTERVAL.saturating_sub(since)
+            }
+            _ => Duration::ZERO,
+        };
+
+        let wait = until_frame.min(until_active);
+        (!wait.is_zero()).then_some(wait)
+    }
+
+    pub fn frame_drawn(&mut self, now: Instant) {
+        self.last_frame = Some(now);
+    }
+}
+
+// A wait in the whole milliseconds that SDL takes. Round up, so that we don’t wake up just before
+// the next frame is due and then spin until it is.
+pub fn wait_millis(wait: Duration) -> u32 {
+    let millis = (wait.as_nanos() + 999_999) / 1_000_000;
+    millis.try_into().unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACTIVE: Duration = Duration::from_secs(10);
+    const IDLE: Duration = Duration::from_secs(20);
+
+    #[test]
+    fn it_is_disabled_without_an_idle_duration() {
+        let start = Instant::now();
+        let cycle = IdleCycle::new(ACTIVE, Duration::ZERO, start);
+
+        assert_eq!(cycle.time_until_next_frame(start + ACTIVE * 2), None);
+    }
+
+    #[test]
+    fn it_draws_every_frame_while_active() {
+        let start = Instant::now();
+        let mut cycle = IdleCycle::new(ACTIVE, IDLE, start);
+        cycle.frame_drawn(start + Duration::from_secs(5));
+
+        assert_eq!(
+            cycle.time_until_next_frame(start + Duration::from_secs(5)),
+            None
+        );
+        assert_eq!(cycle.time_until_next_frame(start + ACTIVE + IDLE), None);
+    }
+
+    #[test]
+    fn it_draws_occasional_frames_while_idle() {
+        let start = Instant::now();
+        let mut cycle = IdleCycle::new(ACTIVE, IDLE, start);
+        cycle.frame_drawn(start + ACTIVE - Duration::from_millis(16));
+
+        // The first idle frame is drawn straight away
+        let idle_start = start + ACTIVE;
+        assert_eq!(cycle.time_until_next_frame(idle_start), None);
+
+        cycle.frame_drawn(idle_start);
+        assert_eq!(
+            cycle.time_until_next_frame(idle_start + Duration::from_millis(250)),
+            Some(Duration::from_millis(750))
+        );
+        assert_eq!(
+            cycle.time_until_next_frame(idle_start + IDLE_FRAME_INTERVAL),
+            None
+        );
+    }
+
+    #[test]
+    fn it_spaces_out_frames_with_a_minimum_interval() {
+        let start = Instant::now();
+        let interval = Duration::from_millis(100);
+        let mut cycle =
+            IdleCycle::new(ACTIVE, Duration::ZERO, start).with_min_frame_interval(interval);
+
+        assert_eq!(cycle.time_until_next_frame(start), None);
+
+        cycle.frame_drawn(start);
+        assert_eq!(
+            cycle.time_until_next_frame(start + Duration::from_millis(40)),
+            Some(Duration::from_millis(60))
+        );
+        assert_eq!(cycle.time_until_next_frame(start + interval), None);
+    }
+
+    #[test]
+    fn it_rounds_waits_up_to_whole_milliseconds() {
+        assert_eq!(wait_millis(Duration::from_micros(16_667)), 17);
+        assert_eq!(wait_millis(Duration::from_micros(300)), 1);
+        assert_eq!(wait_millis(Duration::from_millis(5)), 5);
+        assert_eq!(wait_millis(Duration::MAX), u32::MAX);
+    }
+
+    #[test]
+    fn it_wakes_up_for_the_next_active_phase() {
+        let start = Instant::now();
+        let mut cycle = IdleCycle::new(ACTIVE, IDLE, start);
+
+        let now = start + ACTIVE + IDLE - Duration::from_millis(100);
+        cycle.frame_drawn(now);
+        assert_eq!(
+            cycle.time_until_next_frame(now),
+            Some(Duration::from_millis(100))
+        );
+    }
+}
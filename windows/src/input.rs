@@ -0,0 +1,207 @@
+use std::time::{Duration, Instant};
+
+use sdl2::keyboard::Keycode;
+
+use crate::config::ScreensaverSettings;
+
+// Decides when input should close the screensaver.
+//
+// Input during the grace period after starting is ignored, since it’s often left over from
+// whatever launched the screensaver: a key that’s still held down, or a mouse that’s still
+// settling. Mouse movement is measured from where the cursor was when the grace period ended.
+pub struct ExitOnInput {
+    grace_ends: Instant,
+    // Input ignored during the grace period, for the logs
+    ignored_events: u32,
+    // How far the mouse has moved since the grace period ended, in pixels at 100% scaling
+    motion: (f32, f32),
+    // How far the mouse can move before closing the screensaver, in pixels at 100% scaling
+    mouse_exit_distance: f32,
+    // Virtual-key codes that never close the screensaver
+    ignored_keys: Vec<u32>,
+}
+
+impl ExitOnInput {
+    pub fn new(start: Instant, grace: Duration) -> Self {
+        Self {
+            grace_ends: start + grace,
+            ignored_events: 0,
+            motion: (0.0, 0.0),
+            mouse_exit_distance: ScreensaverSettings::DEFAULT_MOUSE_EXIT_DISTANCE,
+            ignored_keys: Vec::new(),
+        }
+    }
+
+    // Let these keys through without closing the screensaver, like the volume keys.
+    pub fn with_ignored_keys(mut self, ignored_keys: Vec<u32>) -> Self {
+        self.ignored_keys = ignored_keys;
+        self
+    }
+
+    // Tolerate more or less mouse movement before closing the screensaver.
+    pub fn with_mouse_exit_distance(mut self, mouse_exit_distance: f32) -> Self {
+        self.mouse_exit_distance = mouse_exit_distance;
+        self
+    }
+
+    // Whether a key or button press should close the screensaver.
+    pub fn press(&mut self, now: Instant) -> bool {
+        !self.ignore(now)
+    }
+
+    // Whether pressing `keycode` should close the screensaver.
+    pub fn key_press(&mut self, now: Instant, keycode: Option<Keycode>) -> bool {
+        let is_ignored = keycode
+            .and_then(virtual_key)
+            .is_some_and(|key| self.ignored_keys.contains(&key));
+        !is_ignored && self.press(now)
+    }
+
+    // Whether moving the mouse by `xrel` and `yrel` physical pixels should close the screensaver.
+    // The motion is scaled down by the `scale_factor` of the display it happened on, so that the
+    // same hand movement counts the same everywhere.
+    pub fn motion(&mut self, now: Instant, xrel: i32, yrel: i32, scale_factor: f64) -> bool {
+        if self.ignore(now) {
+            return false;
+        }
+
+        self.motion.0 += to_unscaled(xrel, scale_factor);
+        self.motion.1 += to_unscaled(yrel, scale_factor);
+        self.motion.0.abs().max(self.motion.1.abs()) > self.mouse_exit_distance
+    }
+
+    fn ignore(&mut self, now: Instant) -> bool {
+        if now < self.grace_ends {
+            self.ignored_events += 1;
+            return true;
+        }
+
+        if self.ignored_events > 0 {
+            log::debug!(
+                "Ignored {} input events while starting up",
+                self.ignored_events
+            );
+            self.ignored_events = 0;
+        }
+        false
+    }
+}
+
+// Convert a distance in physical pixels to pixels at 100% scaling.
+fn to_unscaled(distance: i32, scale_factor: f64) -> f32 {
+    if scale_factor.is_finite() && scale_factor > 0.0 {
+        (distance as f64 / scale_factor) as f32
+    } else {
+        distance as f32
+    }
+}
+
+// The Windows virtual-key code for an SDL keycode, for the keys someone is likely to want to
+// press while the screensaver is running.
+pub fn virtual_key(keycode: Keycode) -> Option<u32> {
+    let key = match keycode {
+        Keycode::AudioMute | Keycode::Mute => 0xAD,
+        Keycode::VolumeDown => 0xAE,
+        Keycode::VolumeUp => 0xAF,
+        Keycode::AudioNext => 0xB0,
+        Keycode::AudioPrev => 0xB1,
+        Keycode::AudioStop => 0xB2,
+        Keycode::AudioPlay => 0xB3,
+        Keycode::Backspace => 0x08,
+        Keycode::Tab => 0x09,
+        Keycode::Return => 0x0D,
+        Keycode::Escape => 0x1B,
+        Keycode::Space => 0x20,
+        Keycode::Left => 0x25,
+        Keycode::Up => 0x26,
+        Keycode::Right => 0x27,
+        Keycode::Down => 0x28,
+        _ => {
+            // SDL uses ASCII for letters and digits, with lowercase letters.
+            let code = keycode as i32;
+            match code {
+                0x30..=0x39 => code as u32,
+                0x61..=0x7A => (code - 0x20) as u32,
+                _ => return None,
+            }
+        }
+    };
+
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRACE: Duration = Duration::from_millis(750);
+
+    #[test]
+    fn it_ignores_input_during_the_grace_period() {
+        let start = Instant::now();
+        let mut exit_on_input = ExitOnInput::new(start, GRACE);
+
+        assert!(!exit_on_input.press(start));
+        assert!(!exit_on_input.motion(start + GRACE / 2, 500, 500, 1.0));
+        assert!(exit_on_input.press(start + GRACE));
+    }
+
+    #[test]
+    fn it_measures_mouse_motion_from_the_end_of_the_grace_period() {
+        let start = Instant::now();
+        let mut exit_on_input = ExitOnInput::new(start, GRACE);
+        assert!(!exit_on_input.motion(start, 8, 0, 1.0));
+
+        let after_grace = start + GRACE;
+        assert!(!exit_on_input.motion(after_grace, 6, 0, 1.0));
+        assert!(!exit_on_input.motion(after_grace, 4, 0, 1.0));
+        assert!(exit_on_input.motion(after_grace, 1, 0, 1.0));
+    }
+
+    #[test]
+    fn it_scales_the_mouse_exit_distance_with_the_dpi() {
+        // Windows treats 96 DPI as 100% scaling.
+        let scale_factor_for_dpi = |dpi: u32| dpi as f64 / 96.0;
+        assert_eq!(to_unscaled(10, scale_factor_for_dpi(96)), 10.0);
+        assert_eq!(to_unscaled(15, scale_factor_for_dpi(144)), 10.0);
+        assert_eq!(to_unscaled(20, scale_factor_for_dpi(192)), 10.0);
+        assert_eq!(to_unscaled(25, scale_factor_for_dpi(240)), 10.0);
+        assert_eq!(to_unscaled(10, 0.0), 10.0);
+
+        // At 200%, the mouse has to cover twice as many physical pixels.
+        let start = Instant::now();
+        let mut exit_on_input =
+            ExitOnInput::new(start, Duration::ZERO).with_mouse_exit_distance(10.0);
+        assert!(!exit_on_input.motion(start, 20, 0, scale_factor_for_dpi(192)));
+        assert!(exit_on_input.motion(start, 2, 0, scale_factor_for_dpi(192)));
+    }
+
+    #[test]
+    fn it_lets_ignored_keys_through() {
+        let start = Instant::now();
+        let mut exit_on_input =
+            ExitOnInput::new(start, Duration::ZERO).with_ignored_keys(vec![0xAF, 0x41]);
+
+        assert!(!exit_on_input.key_press(start, Some(Keycode::VolumeUp)));
+        assert!(!exit_on_input.key_press(start, Some(Keycode::A)));
+        assert!(exit_on_input.key_press(start, Some(Keycode::B)));
+        assert!(exit_on_input.key_press(start, None));
+        assert!(exit_on_input.press(start));
+    }
+
+    #[test]
+    fn it_maps_keys_to_virtual_key_codes() {
+        assert_eq!(virtual_key(Keycode::CapsLock), None);
+        assert_eq!(virtual_key(Keycode::AudioPlay), Some(0xB3));
+        assert_eq!(virtual_key(Keycode::Z), Some(0x5A));
+        assert_eq!(virtual_key(Keycode::Num7), Some(0x37));
+    }
+
+    #[test]
+    fn it_exits_straight_away_without_a_grace_period() {
+        let start = Instant::now();
+        let mut exit_on_input = ExitOnInput::new(start, Duration::ZERO);
+
+        assert!(exit_on_input.press(start));
+    }
+}
@@ -1,22 +1,52 @@
 // Disable the console window that pops up when you launch the .exe
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod benchmark;
 mod cli;
+mod clock;
 mod config;
+mod crash;
+mod diagnostics;
+mod fade;
 mod gl_context;
+mod idle;
+mod input;
+mod overlay;
+mod palette;
+mod pixel_shift;
 mod platform;
+mod postprocess;
+mod renderer;
+mod settings_watcher;
 mod settings_window;
+mod solid_color;
+mod stats;
+mod still;
 mod surface;
 mod wallpaper;
+mod watchdog;
+mod watermark;
 mod winit_compat;
 
 use cli::Mode;
-use config::Config;
-use flux::Flux;
-use winit_compat::{HasMonitors, HasWinitWindow, MonitorHandle};
+use clock::Clock;
+use config::{AntiAliasing, Config, FillMode, MonitorSelection, PowerMode, ScreensaverSettings};
+use diagnostics::Diagnostics;
+use fade::Fade;
+use idle::{wait_millis, IdleCycle};
+use input::ExitOnInput;
+use overlay::Overlay;
+use postprocess::{Effects, Frame, PostProcess};
+use renderer::FluxRenderer;
+use settings_watcher::SettingsWatcher;
+use solid_color::SolidColor;
+use stats::{DeviceLosses, SessionStats};
+use watchdog::FirstFrameWatchdog;
+use winit_compat::{HasMonitors, HasWinitWindow, MonitorHandle, NonZeroU32PhysicalSize};
 
 use std::collections::HashMap;
-use std::{fs, path, process, rc::Rc};
+use std::ffi::OsString;
+use std::{fs, path, process};
 
 use glow as GL;
 use glow::HasContext;
@@ -43,17 +73,112 @@ pub static mut NvOptimusEnablement: i32 = 1;
 #[no_mangle]
 pub static mut AmdPowerXpressRequestHighPerformance: i32 = 1;
 
-// Higher values will make the screensaver tolerate more mouse movement before exiting.
-const MINIMUM_MOUSE_MOTION_TO_EXIT_SCREENSAVER: f64 = 10.0;
+// The default size of the window in windowed mode
+const DEFAULT_WINDOW_SIZE: (u32, u32) = (1280, 720);
+
+// How fast Flux runs when the system asks for reduced motion
+const REDUCED_MOTION_SPEED: f64 = 0.1;
+
+// The frame rate over Remote Desktop, where every frame goes over the network
+#[cfg(windows)]
+const REMOTE_SESSION_FRAME_RATE: u32 = 15;
+
+// The refresh rate to pace frames to without vsync, if no display reports its own
+const DEFAULT_REFRESH_RATE: u32 = 60;
+
+// How often the wallpaper checks whether it was asked to stop
+const STOP_FILE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+// How much of the brightness the wallpaper keeps while dimmed for a notification
+#[cfg(windows)]
+const NOTIFICATION_BRIGHTNESS: f32 = 0.4;
+
+// How often to check whether a covered window is visible again
+const OCCLUDED_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+// Previews with a shorter side than this, in pixels, count as thumbnails and get fewer, bolder
+// lines. The preview in the Screen Saver Settings dialog is around 150 pixels tall.
+#[cfg(windows)]
+const THUMBNAIL_SIZE: u32 = 320;
+
+// The smallest preview to render, in pixels, whatever size the dialog reports
+#[cfg(windows)]
+const MIN_PREVIEW_SIZE: u32 = 16;
 
 type WindowId = u32;
 
+// Fields are dropped in order, so the GL resources go first and the window last.
 #[allow(dead_code)]
 struct Instance {
-    flux: Flux,
-    window: Window,
-    gl_context: gl_context::GLContext,
+    content: Content,
+    // Frame rate and presentation details, toggled with F12 in windowed mode
+    overlay: Option<Overlay>,
     swapchain: Swapchain,
+    gl_context: gl_context::GLContext,
+    window: Window,
+
+    // Whether we’ve checked that Flux renders to the whole window
+    checked_viewport: bool,
+    // How many times the graphics device was removed or reset while presenting
+    device_losses: DeviceLosses,
+}
+
+enum Content {
+    Flux(FluxRenderer),
+
+    // Show the frame rendered by another instance
+    Mirror(Frame, PostProcess),
+
+    // Show a slowly shifting color instead of running Flux
+    SolidColor(SolidColor),
+}
+
+impl Content {
+    // Run Flux, or show a solid color in the lowest power mode.
+    fn new(
+        gl: &std::rc::Rc<glow::Context>,
+        physical_size: PhysicalSize<u32>,
+        scale_factor: f64,
+        config: &Config,
+        wallpaper: Option<path::PathBuf>,
+    ) -> Result<Self, String> {
+        match config.screensaver.power_mode {
+            PowerMode::Animate => {
+                FluxRenderer::for_window(gl, physical_size, scale_factor, config, wallpaper)
+                    .map(Content::Flux)
+            }
+            PowerMode::SolidColor => Ok(Content::SolidColor(SolidColor::new(
+                gl,
+                physical_size,
+                config,
+            ))),
+        }
+    }
+
+    fn draw(&mut self, target: Option<GL::NativeFramebuffer>, timestamp: f64, effects: &Effects) {
+        match self {
+            Content::Flux(renderer) => renderer.render(target, timestamp, effects),
+            Content::Mirror(frame, post_process) => post_process.present(target, *frame, effects),
+            Content::SolidColor(solid_color) => solid_color.render(target, timestamp, effects),
+        }
+    }
+
+    fn resize(&mut self, physical_size: PhysicalSize<u32>, scale_factor: f64) {
+        match self {
+            Content::Flux(renderer) => renderer.resize(physical_size, scale_factor),
+            Content::Mirror(_, post_process) => post_process.resize(physical_size),
+            Content::SolidColor(solid_color) => solid_color.resize(physical_size),
+        }
+    }
+
+    // The frame to show in mirrored windows. A solid color is cheaper to draw again than to share.
+    fn share_frame(&mut self) -> Option<Frame> {
+        match self {
+            Content::Flux(renderer) => Some(renderer.share_frame()),
+            Content::Mirror(frame, _) => Some(*frame),
+            Content::SolidColor(_) => None,
+        }
+    }
 }
 
 enum Swapchain {
@@ -64,45 +189,269 @@ enum Swapchain {
 }
 
 impl Instance {
-    pub fn draw(&mut self, timestamp: f64) -> glutin::error::Result<()> {
+    fn is_mirror(&self) -> bool {
+        matches!(self.content, Content::Mirror(..))
+    }
+
+    pub fn diagnostics(&self) -> Diagnostics {
+        match self.swapchain {
+            Swapchain::Gl => {
+                let renderer = unsafe { self.gl_context.gl.get_parameter_string(GL::RENDERER) };
+                Diagnostics {
+                    adapter: None,
+                    presentation: format!("OpenGL ({})", renderer),
+                    swap_effect: None,
+                    buffers: None,
+                }
+            }
+
+            #[cfg(windows)]
+            Swapchain::Dxgi(ref dxgi_interop) => dxgi_interop.diagnostics(),
+        }
+    }
+
+    // Stop presenting through DXGI and use the window’s own GL surface instead.
+    #[cfg(windows)]
+    fn fall_back_to_gl(&mut self, settings: &ScreensaverSettings) {
+        if let Swapchain::Dxgi(_) = self.swapchain {
+            // Tear down the interop before GL takes over the window.
+            self.swapchain = Swapchain::Gl;
+            self.swapchain = create_gl_swapchain(&self.gl_context, settings);
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn fall_back_to_gl(&mut self, _settings: &ScreensaverSettings) {}
+
+    pub fn toggle_overlay(&mut self) {
+        if self.overlay.take().is_some() {
+            return;
+        }
+
+        let diagnostics = self.diagnostics();
+        let mut details = vec![match diagnostics.swap_effect {
+            Some(swap_effect) => format!("{} ({})", diagnostics.presentation, swap_effect),
+            None => diagnostics.presentation,
+        }];
+        if let Some(adapter) = diagnostics.adapter {
+            details.push(adapter.name);
+        }
+
+        match Overlay::new(&self.gl_context.gl, details) {
+            Ok(overlay) => self.overlay = Some(overlay),
+            Err(err) => log::error!("Failed to create the overlay: {}", err),
+        }
+    }
+
+    // Restart Flux with new settings. Presentation settings, like the DXGI swapchain, only change
+    // with a new window.
+    pub fn apply_config(&mut self, config: &Config) {
+        if self.is_mirror() {
+            return;
+        }
+
+        if let Err(err) = self
+            .gl_context
+            .context
+            .make_current(&self.gl_context.surface)
+        {
+            log::error!("Failed to make the GL context current: {}", err);
+            return;
+        }
+
+        let content = Content::new(
+            &self.gl_context.gl,
+            self.window.inner_size(),
+            self.window.scale_factor(),
+            config,
+            window_wallpaper(&self.window),
+        );
+        match content {
+            Ok(content) => self.content = content,
+            Err(err) => log::error!("Failed to apply the new settings: {}", err),
+        }
+    }
+
+    pub fn resize(&mut self, physical_size: PhysicalSize<u32>) {
+        // Skip zero-sized windows, like when the window is minimized.
+        let (width, height) = match physical_size.non_zero() {
+            Some(size) => size,
+            None => return,
+        };
+
+        if let Err(err) = self
+            .gl_context
+            .context
+            .make_current(&self.gl_context.surface)
+        {
+            log::error!("Failed to make the GL context current: {}", err);
+            return;
+        }
+
+        self.gl_context
+            .surface
+            .resize(&self.gl_context.context, width, height);
+
+        #[cfg(windows)]
+        if let Swapchain::Dxgi(ref mut dxgi_interop) = self.swapchain {
+            let result =
+                unsafe { dxgi_interop.resize(&self.gl_context.gl, width.get(), height.get()) };
+            if let Err(err) = result {
+                log::error!("{}", err);
+            }
+        }
+
+        self.content
+            .resize(physical_size, self.window.scale_factor());
+    }
+
+    // Whether the window is covered, in which case frames are skipped.
+    pub fn is_occluded(&self) -> bool {
+        match &self.swapchain {
+            Swapchain::Gl => false,
+            #[cfg(windows)]
+            Swapchain::Dxgi(dxgi_interop) => dxgi_interop.is_occluded(),
+        }
+    }
+
+    // Draw and present a frame. If the graphics device is lost while presenting with DXGI, the
+    // swapchain never recovers, so later frames are presented with OpenGL instead.
+    #[cfg_attr(not(windows), allow(unused_variables))]
+    pub fn draw(
+        &mut self,
+        timestamp: f64,
+        effects: &Effects,
+        settings: &ScreensaverSettings,
+    ) -> Result<(), String> {
         match self.swapchain {
             Swapchain::Gl => {
                 self.gl_context
                     .context
-                    .make_current(&self.gl_context.surface)?;
-
-                self.flux.animate(timestamp);
+                    .make_current(&self.gl_context.surface)
+                    .map_err(|err| err.to_string())?;
+
+                self.content.draw(None, timestamp, effects);
+                check_viewport(
+                    &mut self.checked_viewport,
+                    &self.gl_context.gl,
+                    &self.window,
+                );
+                if let Some(overlay) = self.overlay.as_mut() {
+                    overlay.draw(None, self.window.inner_size());
+                }
 
                 self.gl_context
                     .surface
                     .swap_buffers(&self.gl_context.context)
+                    .map_err(|err| err.to_string())
             }
 
             #[cfg(windows)]
-            Swapchain::Dxgi(ref mut dxgi_interop) => unsafe {
-                platform::windows::dxgi_swapchain::with_dxgi_swapchain(dxgi_interop, |fbo| {
-                    self.gl_context
-                        .context
-                        .make_current(&self.gl_context.surface)?;
-
-                    self.flux.compute(timestamp);
-
-                    self.gl_context
-                        .gl
-                        .bind_framebuffer(GL::FRAMEBUFFER, Some(*fbo));
+            Swapchain::Dxgi(ref mut dxgi_interop) => {
+                use platform::windows::dxgi_swapchain::{with_dxgi_swapchain, Problem};
+
+                let result = unsafe {
+                    with_dxgi_swapchain(dxgi_interop, &self.gl_context.gl, |fbo| {
+                        self.gl_context
+                            .context
+                            .make_current(&self.gl_context.surface)
+                            .map_err(|err| err.to_string())?;
+
+                        self.content.draw(Some(*fbo), timestamp, effects);
+                        check_viewport(
+                            &mut self.checked_viewport,
+                            &self.gl_context.gl,
+                            &self.window,
+                        );
+                        if let Some(overlay) = self.overlay.as_mut() {
+                            overlay.draw(Some(*fbo), self.window.inner_size());
+                        }
+
+                        self.gl_context.gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+                        self.gl_context.gl.finish();
+
+                        Ok(())
+                    })
+                };
+
+                let device_lost = matches!(result, Err(Problem::DeviceLost(_)));
+                if self.device_losses.frame_drawn(device_lost) {
+                    log::error!(
+                        "The graphics device was lost. Falling back to presenting with OpenGL."
+                    );
+                    self.fall_back_to_gl(settings);
+                }
 
-                    self.flux.render();
+                match result {
+                    // Nothing to draw while the window is covered. The loops slow down instead.
+                    Err(Problem::Occluded) => Ok(()),
+                    result => result.map_err(|err| err.to_string()),
+                }
+            }
+        }
+    }
+}
 
-                    self.gl_context.gl.bind_framebuffer(GL::FRAMEBUFFER, None);
-                    self.gl_context.gl.finish();
+// Warn once if the last viewport doesn’t cover the window. This usually means the logical and
+// physical sizes got mixed up somewhere and Flux is rendering blurry or offset on high-DPI
+// displays.
+fn check_viewport(checked: &mut bool, gl: &glow::Context, window: &Window) {
+    if *checked {
+        return;
+    }
+    *checked = true;
+
+    let mut viewport = [0; 4];
+    unsafe { gl.get_parameter_i32_slice(GL::VIEWPORT, &mut viewport) };
+
+    let size = window.inner_size();
+    if viewport != [0, 0, size.width as i32, size.height as i32] {
+        log::warn!(
+            "The viewport {:?} doesn’t match the window size {}x{}",
+            viewport,
+            size.width,
+            size.height
+        );
+    }
+}
 
-                    Ok(())
-                })
-            },
+impl Drop for Instance {
+    // Make our context current, so that the GL and interop resources are released from the
+    // context that owns them.
+    fn drop(&mut self) {
+        if let Err(err) = self
+            .gl_context
+            .context
+            .make_current(&self.gl_context.surface)
+        {
+            log::warn!("Failed to make the GL context current for cleanup: {}", err);
         }
     }
 }
 
+// Whether Windows has asked us to close because the system is shutting down or the user is
+// logging off.
+#[cfg(windows)]
+fn session_is_ending() -> bool {
+    platform::windows::session::is_ending()
+}
+
+#[cfg(not(windows))]
+fn session_is_ending() -> bool {
+    false
+}
+
+// Whether the set of displays or their layout changed since the last call.
+#[cfg(windows)]
+fn displays_changed() -> bool {
+    platform::windows::session::take_display_change()
+}
+
+#[cfg(not(windows))]
+fn displays_changed() -> bool {
+    false
+}
+
 fn main() {
     let project_dirs = directories::ProjectDirs::from("me", "sandydoo", "Flux");
     let log_dir = project_dirs.as_ref().map(|dirs| dirs.data_local_dir());
@@ -110,17 +459,81 @@ fn main() {
 
     init_logging(log_dir);
 
-    let config = Config::load(config_dir);
+    match cli::read_flags().and_then(|flags| {
+        // Only interrupt the user when the screensaver itself crashes. The preview is too small to
+        // show a dialog over.
+        crash::install_panic_hook(config_dir, flags.mode == Mode::Screensaver);
 
-    match cli::read_flags().and_then(|mode| {
-        if mode == Mode::Settings {
-            settings_window::run(config)
-                .map_err(|err| log::error!("{}", err))
-                .unwrap();
+        let portable_dir = Config::portable_dir(flags.profile.as_deref(), flags.portable);
+        if let Some(dir) = &portable_dir {
+            log::info!("Using the portable settings in {}", dir.display());
+        }
+        let settings_dir = portable_dir.as_deref().or(config_dir);
+        // Color modes look up custom palettes by name, for the settings window and for Flux.
+        palette::load_custom_palettes(settings_dir);
+
+        let mut config = match &flags.settings_file {
+            Some(settings_file) => Config::load_file(settings_file),
+            None => Config::load(settings_dir, flags.profile.as_deref()),
+        };
+
+        // Saved before the environment overrides, which are only meant for this run.
+        let first_run = flags.mode == Mode::Screensaver
+            && flags.settings_file.is_none()
+            && save_first_run_settings(&config);
+
+        // Shared before the environment overrides, which are only meant for this machine.
+        match &flags.mode {
+            Mode::Export(path) => return export_settings(&config, path),
+            Mode::Import(path) => return import_settings(&config, path),
+            _ => (),
+        }
+
+        config.apply_env_overrides();
+        #[cfg(windows)]
+        if flags.no_dxgi {
+            config.platform.windows.use_dxgi = false;
+        }
+        log::set_max_level(config.log_level.to_level_filter());
+
+        if flags.mode == Mode::StopWallpaper {
+            return fs::write(wallpaper_stop_file(), "")
+                .map_err(|err| format!("Failed to stop the wallpaper: {}", err));
+        }
+
+        // Printed for scripts, which can redirect the output even without a console.
+        if flags.mode == Mode::ListModes {
+            let list = config::list_modes();
+            log::info!("Modes:\n{}", list);
+            print!("{}", list);
             return Ok(());
         }
 
-        run_flux(mode, config)
+        // Previews pass their own settings file, which should be shown as it is.
+        if flags.mode == Mode::Screensaver
+            && flags.settings_file.is_none()
+            && config.screensaver.randomize_on_launch
+        {
+            randomize_on_launch(&mut config);
+        }
+
+        // The process exits once the window is closed, with a non-zero code if it failed to run.
+        if flags.mode == Mode::Settings {
+            let log_file = log_dir.map(log_file_path);
+            return settings_window::run(config, Diagnostics::load(log_dir), log_file)
+                .map_err(|err| format!("Failed to run the settings window: {}", err));
+        }
+
+        adapt_to_system(&mut config);
+
+        let settings_window_args = flags.settings_window_args();
+        run_flux(flags, config, log_dir)?;
+
+        // Only after the screensaver, so that it never keeps it from starting.
+        if first_run {
+            open_settings_window(&settings_window_args);
+        }
+        Ok(())
     }) {
         Ok(_) => process::exit(0),
         Err(err) => {
@@ -130,11 +543,44 @@ fn main() {
     };
 }
 
+// The first time the screensaver runs, there are no settings yet. Save the defaults, so that this
+// only happens once, and return whether to open the settings window afterwards.
+fn save_first_run_settings(config: &Config) -> bool {
+    if config.location().map_or(true, path::Path::exists) {
+        return false;
+    }
+
+    log::info!("No settings were saved yet. Saving the defaults.");
+    if let Err(err) = config.save() {
+        // Don’t open the settings window on every run.
+        log::warn!("Failed to save the default settings: {}", err);
+        return false;
+    }
+    config.settings_window.open_on_first_run
+}
+
+// Randomize the settings for this run. Reuse the simulation seed if there is one, so that a seed
+// from the log reproduces both the settings and the animation.
+fn randomize_on_launch(config: &mut Config) {
+    use rand::SeedableRng;
+
+    let seed = config.flux.seed.unwrap_or_else(rand::random);
+    log::info!("Randomizing the settings with seed {}", seed);
+    config.randomize(&mut rand::rngs::StdRng::seed_from_u64(seed));
+    config.flux.seed = Some(seed);
+}
+
+fn log_file_path(log_dir: &path::Path) -> path::PathBuf {
+    log_dir.join("flux_screensaver.log")
+}
+
 fn init_logging(optional_log_dir: Option<&path::Path>) {
     use simplelog::*;
 
+    // Log everything, and let `log::set_max_level` decide what gets through once the settings are
+    // loaded.
     let mut loggers: Vec<Box<dyn SharedLogger>> = vec![TermLogger::new(
-        LevelFilter::Warn,
+        LevelFilter::Trace,
         Config::default(),
         TerminalMode::Mixed,
         ColorChoice::Auto,
@@ -143,149 +589,1100 @@ fn init_logging(optional_log_dir: Option<&path::Path>) {
     if let Some(log_dir) = optional_log_dir {
         let maybe_log_file = {
             fs::create_dir_all(log_dir).unwrap();
-            let log_path = log_dir.join("flux_screensaver.log");
             fs::OpenOptions::new()
                 .append(true)
                 .create(true)
-                .open(log_path)
+                .open(log_file_path(log_dir))
         };
 
         if let Ok(log_file) = maybe_log_file {
             loggers.push(WriteLogger::new(
-                LevelFilter::Warn,
+                LevelFilter::Trace,
                 Config::default(),
                 log_file,
             ));
         }
     }
 
-    let _ = CombinedLogger::init(loggers);
-    log_panics::init();
+    let _ = CombinedLogger::init(loggers);
+    log::set_max_level(LevelFilter::Warn);
+    log_panics::init();
+}
+
+fn run_flux(flags: cli::Flags, config: Config, log_dir: Option<&path::Path>) -> Result<(), String> {
+    #[cfg(windows)]
+    platform::windows::dpi_awareness::set_dpi_awareness()?;
+
+    // By default, SDL disables the screensaver and doesn’t allow the display to sleep. We want
+    // both of these things to happen in both screensaver and preview modes.
+    sdl2::hint::set("SDL_VIDEO_ALLOW_SCREENSAVER", "1");
+
+    #[cfg(windows)]
+    let _session_watcher = platform::windows::session::SessionWatcher::new()
+        .map_err(|err| log::warn!("Can’t watch for the session ending: {}", err))
+        .ok();
+
+    let sdl_context = sdl2::init()?;
+    let video_subsystem = sdl_context.video()?;
+
+    match flags.mode {
+        Mode::Preview(raw_window_handle) => {
+            #[cfg(not(windows))]
+            panic!("Preview window unsupported");
+
+            let mut instance = new_preview_window(&video_subsystem, raw_window_handle, &config)?;
+            let clock = Clock::new(std::time::Instant::now(), motion_speed(&config.screensaver));
+            let mut event_pump = sdl_context.event_pump()?;
+
+            run_preview_loop(
+                &mut event_pump,
+                &mut instance,
+                raw_window_handle,
+                clock,
+                &config.screensaver,
+            )
+        }
+
+        Mode::Wallpaper => {
+            #[cfg(not(windows))]
+            panic!("Wallpaper mode unsupported");
+
+            let worker_window = platform::windows::desktop::find_worker_window()
+                .ok_or("Can’t find the desktop to draw on")?;
+
+            // Clear out a request to stop that was left over from a previous run.
+            let _ = fs::remove_file(wallpaper_stop_file());
+
+            let mut instance = new_preview_window(&video_subsystem, worker_window, &config)?;
+            let clock = Clock::new(std::time::Instant::now(), motion_speed(&config.screensaver));
+            let mut event_pump = sdl_context.event_pump()?;
+
+            log::info!("Running as the wallpaper. Stop with --stop-wallpaper.");
+            let result = run_wallpaper_loop(
+                &mut event_pump,
+                &mut instance,
+                clock,
+                config,
+                &flags.settings_window_args(),
+            );
+
+            // Bring back the regular wallpaper.
+            drop(instance);
+            platform::windows::desktop::redraw(&worker_window);
+
+            result
+        }
+
+        Mode::Screensaver => {
+            #[cfg(windows)]
+            let _single_instance =
+                match platform::windows::single_instance::SingleInstance::acquire() {
+                    Some(single_instance) => single_instance,
+                    None => {
+                        log::info!("The screensaver is already running. Exiting.");
+                        return Ok(());
+                    }
+                };
+
+            // Read this before starting, so that a change to the setting while the screensaver is
+            // running can’t leave the session unlocked. A preview from the settings window isn’t
+            // guarding anything, so it never locks.
+            #[cfg(windows)]
+            let lock_on_exit = !flags.preview && platform::windows::lock::is_screensaver_secure();
+            #[cfg(not(windows))]
+            let lock_on_exit = false;
+
+            // Started before the windows, since creating the swapchains is what usually hangs.
+            let mut watchdog = FirstFrameWatchdog::start(
+                std::time::Instant::now() + config.screensaver.startup_delay(),
+                config.screensaver.first_frame_timeout(),
+                move || exit_after_hang(lock_on_exit),
+            );
+
+            let mut instances = new_screensaver_instances(&video_subsystem, &config)?;
+
+            if let Some(instance) = instances.values().next() {
+                let diagnostics = instance.diagnostics();
+                log::info!("Rendering environment:\n{}", diagnostics);
+                diagnostics.save(log_dir);
+            }
+
+            // Hide the cursor and report relative mouse movements.
+            sdl_context.mouse().set_relative_mouse_mode(true);
+
+            // Unhide windows after context setup
+            for instance in instances.values_mut() {
+                instance.window.show();
+            }
+
+            let mut event_pump = sdl_context.event_pump()?;
+            let clock = Clock::new(std::time::Instant::now(), motion_speed(&config.screensaver));
+
+            // Lock even if the loop failed, so that an error can’t leave the session unlocked.
+            let result = run_main_loop(
+                &mut event_pump,
+                &mut instances,
+                clock,
+                &config.screensaver,
+                &mut watchdog,
+                || new_screensaver_instances(&video_subsystem, &config),
+            );
+
+            // Close the windows before showing the lock screen.
+            drop(instances);
+
+            // There’s no point in locking a session that’s about to end.
+            if lock_on_exit && !session_is_ending() {
+                lock_workstation();
+            }
+
+            result
+        }
+
+        Mode::Windowed(size) => {
+            let size = size.unwrap_or(DEFAULT_WINDOW_SIZE);
+            let mut instance = new_windowed_instance(&video_subsystem, &config, size.into())?;
+            instance.window.show();
+
+            let mut event_pump = sdl_context.event_pump()?;
+            let clock = Clock::new(std::time::Instant::now(), motion_speed(&config.screensaver));
+
+            run_windowed_loop(
+                &mut event_pump,
+                &mut instance,
+                clock,
+                config,
+                &flags.settings_window_args(),
+            )
+        }
+
+        Mode::Diagnose => run_diagnose(&video_subsystem, &config, log_dir),
+
+        Mode::RenderFrame {
+            ref path,
+            size,
+            seconds,
+        } => run_render_frame(&video_subsystem, &config, path, size.into(), seconds),
+
+        Mode::Benchmark { size, seconds } => {
+            run_benchmark(&video_subsystem, &config, size.into(), seconds)
+        }
+
+        _ => unreachable!(),
+    }
+}
+
+// Render a still frame offscreen. WGL needs a window for the GL context, but it stays hidden.
+fn run_render_frame(
+    video_subsystem: &sdl2::VideoSubsystem,
+    config: &Config,
+    path: &path::Path,
+    size: PhysicalSize<u32>,
+    seconds: f64,
+) -> Result<(), String> {
+    let window = video_subsystem
+        .window("Flux", size.width, size.height)
+        .hidden()
+        .build()
+        .map_err(|err| err.to_string())?;
+
+    let gl_context = gl_context::new_gl_context(
+        window.raw_display_handle(),
+        window.inner_size(),
+        window.raw_window_handle(),
+        None,
+        None,
+    );
+
+    log::info!(
+        "Rendering {} seconds at {}x{} to {}",
+        seconds,
+        size.width,
+        size.height,
+        path.display()
+    );
+    still::render_to_file(
+        &gl_context.gl,
+        config,
+        size,
+        seconds,
+        window_wallpaper(&window),
+        path,
+    )
+}
+
+// Render offscreen as fast as possible and report the frame rate, in the log and in a dialog. Like
+// a still frame, this needs a hidden window for the GL context.
+fn run_benchmark(
+    video_subsystem: &sdl2::VideoSubsystem,
+    config: &Config,
+    size: PhysicalSize<u32>,
+    seconds: f64,
+) -> Result<(), String> {
+    let window = video_subsystem
+        .window("Flux", size.width, size.height)
+        .hidden()
+        .build()
+        .map_err(|err| err.to_string())?;
+
+    let gl_context = gl_context::new_gl_context(
+        window.raw_display_handle(),
+        window.inner_size(),
+        window.raw_window_handle(),
+        None,
+        None,
+    );
+
+    let settings = &config.screensaver;
+    log::info!(
+        "Benchmarking for {} seconds at {}x{}, with a render scale of {} and {} anti-aliasing",
+        seconds,
+        size.width,
+        size.height,
+        settings.render_scale,
+        settings.anti_aliasing
+    );
+    let results = benchmark::run(
+        &gl_context.gl,
+        config,
+        size,
+        std::time::Duration::from_secs_f64(seconds),
+    )?;
+
+    let report = results.to_string();
+    log::info!("Benchmark results:\n{}", report);
+    show_report("Flux benchmark", &report);
+
+    Ok(())
+}
+
+// Set up rendering in a hidden window, draw a single frame, and report what we ended up with.
+fn run_diagnose(
+    video_subsystem: &sdl2::VideoSubsystem,
+    config: &Config,
+    log_dir: Option<&path::Path>,
+) -> Result<(), String> {
+    // Log the whole setup, so that the log file is useful on its own.
+    if log::max_level() < log::LevelFilter::Debug {
+        log::set_max_level(log::LevelFilter::Debug);
+    }
+
+    let mut report = String::new();
+
+    #[cfg(windows)]
+    match platform::windows::adapter::enumerate() {
+        Ok(adapters) => {
+            report.push_str("Available adapters:\n");
+            for adapter in adapters {
+                report.push_str(&format!("- {}\n", adapter));
+            }
+            report.push('\n');
+        }
+        Err(err) => report.push_str(&format!("Failed to list the adapters: {}\n\n", err)),
+    }
+
+    match new_windowed_instance(video_subsystem, config, DEFAULT_WINDOW_SIZE.into()) {
+        Ok(mut instance) => {
+            // The first frame checks that the shared back buffer actually receives content.
+            if let Err(err) = instance.draw(0.0, &Effects::default(), &config.screensaver) {
+                report.push_str(&format!("Failed to render a frame: {}\n", err));
+            }
+
+            let diagnostics = instance.diagnostics();
+            report.push_str(&diagnostics.to_string());
+            diagnostics.save(log_dir);
+        }
+        Err(err) => report.push_str(&format!("Failed to set up rendering: {}", err)),
+    }
+
+    log::info!("Diagnostics:\n{}", report);
+    show_report("Flux diagnostics", &report);
+
+    Ok(())
+}
+
+// Save the settings to a file to share, and say where it went.
+fn export_settings(config: &Config, path: &path::Path) -> Result<(), String> {
+    if let Err(err) = config.export(path) {
+        let report = format!("Failed to export the settings: {}", err);
+        show_report("Flux settings", &report);
+        return Err(report);
+    }
+
+    let report = format!("Exported the settings to {}.", path.display());
+    log::info!("{}", report);
+    show_report("Flux settings", &report);
+    Ok(())
+}
+
+// Replace the settings with a shared copy, and sum up what was imported.
+fn import_settings(config: &Config, path: &path::Path) -> Result<(), String> {
+    let imported = config.import(path).and_then(|(imported, fixes)| {
+        imported.save()?;
+        Ok((imported, fixes))
+    });
+    let (imported, fixes) = match imported {
+        Ok(imported) => imported,
+        Err(err) => {
+            let report = format!("Failed to import the settings: {}", err);
+            show_report("Flux settings", &report);
+            return Err(report);
+        }
+    };
+
+    let mut report = format!(
+        "Imported the settings from {}, with {} colors.",
+        path.display(),
+        imported.flux.color_mode
+    );
+    if !fixes.is_empty() {
+        report.push_str("\n\nSome settings were adjusted to work here:\n");
+        for fix in fixes {
+            report.push_str(&format!("- {}\n", fix));
+        }
+    }
+    log::info!("{}", report);
+    show_report("Flux settings", &report);
+    Ok(())
+}
+
+#[cfg(windows)]
+fn show_report(title: &str, report: &str) {
+    use windows::core::HSTRING;
+    use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONINFORMATION, MB_OK};
+
+    unsafe {
+        MessageBoxW(
+            HWND::default(),
+            &HSTRING::from(report),
+            &HSTRING::from(title),
+            MB_OK | MB_ICONINFORMATION,
+        );
+    }
+}
+
+#[cfg(not(windows))]
+fn show_report(_title: &str, report: &str) {
+    println!("{}", report);
+}
+
+// Combine the picture adjustments from the settings with the current fade. `elapsed` is how long
+// the animation has been running, which sets the pixel shift.
+fn effects(settings: &ScreensaverSettings, fade: &Fade, elapsed: std::time::Duration) -> Effects {
+    Effects {
+        brightness: settings.brightness * settings.night_mode.brightness() * fade.brightness(),
+        contrast: settings.contrast,
+        invert: settings.invert_colors,
+        shift: if settings.pixel_shift {
+            pixel_shift::offset(elapsed)
+        } else {
+            (0, 0)
+        },
+    }
+}
+
+// Slow Flux right down if animations are turned off in Windows, unless the settings say otherwise.
+// Go easy on remote and service sessions. These often don’t have a GPU for the D3D device, and
+// full-rate frames can swamp a Remote Desktop connection.
+//
+// This runs after the settings window has had its chance to save, so none of it ends up in the
+// settings file.
+#[cfg(windows)]
+fn adapt_to_session(config: &mut Config) {
+    let session = platform::windows::session::kind();
+    log::info!("Running in a {} session", session);
+    if !session.is_constrained() || !config.platform.windows.adapt_to_remote_sessions {
+        return;
+    }
+
+    config.platform.windows.use_dxgi = false;
+    let max_frame_rate = &mut config.screensaver.max_frame_rate;
+    if *max_frame_rate == 0 || *max_frame_rate > REMOTE_SESSION_FRAME_RATE {
+        *max_frame_rate = REMOTE_SESSION_FRAME_RATE;
+    }
+    log::info!(
+        "Presenting with OpenGL at up to {} FPS to keep the session responsive",
+        config.screensaver.max_frame_rate
+    );
+}
+
+// Adjust the settings to this machine. Reloaded settings start over from the file, so this runs
+// again after every reload as well as at startup.
+#[cfg_attr(not(windows), allow(unused_variables))]
+fn adapt_to_system(config: &mut Config) {
+    #[cfg(windows)]
+    adapt_to_session(config);
+    #[cfg(windows)]
+    adapt_to_video_memory(config);
+}
+
+// Go easy on adapters with little dedicated memory, like integrated GPUs, which can fail to
+// allocate the frames for a large display. As with the session, none of this is saved.
+#[cfg(windows)]
+fn adapt_to_video_memory(config: &mut Config) {
+    let windows = &config.platform.windows;
+    if !windows.adapt_to_low_video_memory {
+        return;
+    }
+
+    // Find the adapter D3D will pick: the preferred one, or else the first.
+    let adapters = match platform::windows::adapter::enumerate() {
+        Ok(adapters) => adapters,
+        Err(err) => {
+            log::debug!("Failed to list the adapters to check their memory: {}", err);
+            return;
+        }
+    };
+    let adapter = windows
+        .adapter
+        .as_deref()
+        .and_then(|preference| adapters.iter().find(|adapter| adapter.matches(preference)))
+        .or(adapters.first());
+    let Some(adapter) = adapter else {
+        return;
+    };
+
+    let memory_mb = adapter.dedicated_video_memory / (1024 * 1024);
+    if memory_mb >= windows.low_video_memory_mb {
+        return;
+    }
+
+    log::info!(
+        "{} has only {} MB of dedicated memory. Using less of it.",
+        adapter.name,
+        memory_mb
+    );
+    for fix in config.fit_low_video_memory() {
+        log::info!("Adjusted settings: {}", fix);
+    }
+}
+
+fn motion_speed(settings: &ScreensaverSettings) -> f64 {
+    if settings.respect_reduced_motion && !system_animations_enabled() {
+        log::info!("Animations are turned off in Windows. Slowing down Flux.");
+        return REDUCED_MOTION_SPEED;
+    }
+
+    1.0
+}
+
+#[cfg(windows)]
+fn system_animations_enabled() -> bool {
+    platform::windows::accessibility::animations_enabled()
+}
+
+#[cfg(not(windows))]
+fn system_animations_enabled() -> bool {
+    true
+}
+
+// Draw into the preview in the Screen Saver Settings dialog, until the dialog closes or switches
+// to another screensaver.
+fn run_preview_loop(
+    event_pump: &mut sdl2::EventPump,
+    instance: &mut Instance,
+    host_window: RawWindowHandle,
+    clock: Clock,
+    settings: &ScreensaverSettings,
+) -> Result<(), String> {
+    use sdl2::event::Event;
+
+    let fade = Fade::new(settings.fade_duration());
+    let mut occlusion = Occlusion::default();
+    let mut frame_pacing = pace_frames(settings, instance);
+
+    'main: loop {
+        let first_event = frame_pacing
+            .time_until_next_frame(std::time::Instant::now())
+            .and_then(|wait| event_pump.wait_event_timeout(wait_millis(wait)));
+
+        for event in first_event.into_iter().chain(event_pump.poll_iter()) {
+            match event {
+                Event::Quit { .. }
+                | Event::Window {
+                    win_event: sdl2::event::WindowEvent::Close,
+                    ..
+                } => break 'main,
+
+                _ => (),
+            }
+        }
+
+        if session_is_ending() {
+            break 'main;
+        }
+
+        // Drawing into a destroyed window fails at best, so stop as soon as it’s gone. The
+        // instance is dropped on the way out, which releases the swapchain and the interop.
+        if !window_exists(&host_window) {
+            log::info!("The preview window was closed");
+            break 'main;
+        }
+
+        // Skip the frame if we were woken up early.
+        let now = std::time::Instant::now();
+        if frame_pacing.time_until_next_frame(now).is_some() {
+            continue;
+        }
+
+        let timestamp = clock.timestamp();
+        let effects = effects(
+            settings,
+            &fade,
+            now.saturating_duration_since(clock.start()),
+        );
+        frame_pacing.frame_drawn(now);
+        if let Err(err) = instance.draw(timestamp, &effects, settings) {
+            // The dialog may have closed while the frame was being drawn.
+            if !window_exists(&host_window) {
+                log::info!("The preview window was closed while drawing");
+                break 'main;
+            }
+            log::error!("Failed to render Flux: {}", err);
+        }
+        occlusion.update([&*instance]);
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+fn window_exists(handle: &RawWindowHandle) -> bool {
+    match handle {
+        RawWindowHandle::Win32(handle) => platform::windows::window::exists(HWND(handle.hwnd as _)),
+        _ => true,
+    }
+}
+
+#[cfg(not(windows))]
+fn window_exists(_handle: &RawWindowHandle) -> bool {
+    true
 }
 
-fn run_flux(mode: Mode, config: Config) -> Result<(), String> {
-    #[cfg(windows)]
-    platform::windows::dpi_awareness::set_dpi_awareness()?;
+// Draw behind the desktop icons until asked to stop, either from the tray icon or with
+// `--stop-wallpaper`.
+#[cfg(windows)]
+fn run_wallpaper_loop(
+    event_pump: &mut sdl2::EventPump,
+    instance: &mut Instance,
+    mut clock: Clock,
+    mut config: Config,
+    settings_window_args: &[OsString],
+) -> Result<(), String> {
+    use config::NotificationResponse;
+    use platform::windows::tray::{Tray, TrayCommand};
+    use sdl2::event::Event;
 
-    // By default, SDL disables the screensaver and doesn’t allow the display to sleep. We want
-    // both of these things to happen in both screensaver and preview modes.
-    sdl2::hint::set("SDL_VIDEO_ALLOW_SCREENSAVER", "1");
+    let stop_file = wallpaper_stop_file();
+    let fade = Fade::new(config.screensaver.fade_duration());
+    let mut occlusion = Occlusion::default();
+    let mut settings_watcher = config.location().map(SettingsWatcher::new);
+    let mut last_stop_check = std::time::Instant::now();
+    // Whether the clock was paused because a fullscreen app is running or a notification is
+    // showing, rather than from the tray
+    let mut paused_automatically = false;
+    let mut dimmed_for_notification = false;
 
-    let sdl_context = sdl2::init()?;
-    let video_subsystem = sdl_context.video()?;
+    // The wallpaper still works without the tray, it just can’t be controlled from there.
+    let tray = Tray::new()
+        .map_err(|err| log::warn!("Failed to add the tray icon: {}", err))
+        .ok();
 
-    match mode {
-        Mode::Preview(raw_window_handle) => {
-            #[cfg(not(windows))]
-            panic!("Preview window unsupported");
+    let mut frame_pacing = pace_frames(&config.screensaver, instance);
 
-            let mut instance = new_preview_window(&video_subsystem, raw_window_handle, &config)?;
-            let start = std::time::Instant::now();
-            let mut event_pump = sdl_context.event_pump()?;
+    'main: loop {
+        let first_event = frame_pacing
+            .time_until_next_frame(std::time::Instant::now())
+            .and_then(|wait| event_pump.wait_event_timeout(wait_millis(wait)));
 
-            run_preview_loop(&mut event_pump, &mut instance, start)
+        for event in first_event.into_iter().chain(event_pump.poll_iter()) {
+            if let Event::Quit { .. } = event {
+                break 'main;
+            }
         }
 
-        Mode::Screensaver => {
-            let wallpaper_api = wallpaper::DesktopWallpaper::new().ok();
-            let monitors = video_subsystem
-                .available_monitors()
-                .enumerate()
-                .map(|(index, monitor)| {
-                    (
-                        monitor.clone(),
-                        wallpaper_api
-                            .as_ref()
-                            .and_then(|wallpaper| wallpaper.get(index as u32).ok()),
-                    )
-                })
-                .collect::<Vec<(MonitorHandle, Option<std::path::PathBuf>)>>();
-            log::debug!("Available monitors: {:?}", monitors);
-
-            let fill_mode = config.platform.windows.fill_mode;
-            let surfaces = surface::build(&monitors, fill_mode);
-            log::debug!("Creating windows: {:?}", surfaces);
-
-            let mut instances = surfaces
-                .iter()
-                .map(|surface| {
-                    new_instance(&video_subsystem, &config, surface)
-                        .map(|instance| (instance.window.id(), instance))
-                })
-                .collect::<Result<HashMap<WindowId, Instance>, String>>()?;
+        while let Some(command) = tray.as_ref().and_then(Tray::try_recv) {
+            match command {
+                TrayCommand::OpenSettings => open_settings_window(settings_window_args),
+                TrayCommand::TogglePause => {
+                    // The user is in charge of pausing from here on.
+                    paused_automatically = false;
+                    clock.toggle_pause(std::time::Instant::now());
+                    log::info!(
+                        "{} the wallpaper",
+                        if clock.is_paused() {
+                            "Paused"
+                        } else {
+                            "Resumed"
+                        }
+                    );
+                }
+                TrayCommand::Quit => break 'main,
+            }
+        }
 
-            // Hide the cursor and report relative mouse movements.
-            sdl_context.mouse().set_relative_mouse_mode(true);
+        if session_is_ending() {
+            break 'main;
+        }
 
-            // Unhide windows after context setup
-            for instance in instances.values_mut() {
-                instance.window.show();
+        // Checking for the file, fullscreen apps, and notifications every frame would be wasteful.
+        let now = std::time::Instant::now();
+        if now.saturating_duration_since(last_stop_check) >= STOP_FILE_CHECK_INTERVAL {
+            last_stop_check = now;
+            if stop_file.exists() {
+                log::info!("Asked to stop");
+                let _ = fs::remove_file(&stop_file);
+                break 'main;
             }
 
-            let mut event_pump = sdl_context.event_pump()?;
-            let start = std::time::Instant::now();
+            let windows = &config.platform.windows;
+            let fullscreen = windows.pause_for_fullscreen_apps
+                && platform::windows::fullscreen::is_fullscreen_app_running();
+            let notification = windows.notification_response != NotificationResponse::Ignore
+                && platform::windows::notifications::is_notification_showing(
+                    &windows.notification_window_titles,
+                );
+            let responds_with =
+                |response| notification && windows.notification_response == response;
+
+            if responds_with(NotificationResponse::Dim) != dimmed_for_notification {
+                dimmed_for_notification = !dimmed_for_notification;
+                log::debug!(
+                    "{} the wallpaper for a notification",
+                    if dimmed_for_notification {
+                        "Dimming"
+                    } else {
+                        "Restoring"
+                    }
+                );
+            }
 
-            run_main_loop(&mut event_pump, &mut instances, start)
+            let pause = fullscreen || responds_with(NotificationResponse::Pause);
+            if pause && !clock.is_paused() {
+                log::info!(
+                    "Pausing the wallpaper while {}",
+                    if fullscreen {
+                        "a fullscreen app is running"
+                    } else {
+                        "a notification is showing"
+                    }
+                );
+                clock.toggle_pause(now);
+                paused_automatically = true;
+            } else if !pause && paused_automatically {
+                log::info!("Resuming the wallpaper");
+                clock.toggle_pause(now);
+                paused_automatically = false;
+            }
         }
 
-        _ => unreachable!(),
+        if let Some(watcher) = settings_watcher.as_mut() {
+            if watcher.has_changed(now) {
+                match config.reload() {
+                    Ok(mut new_config) => {
+                        new_config.apply_env_overrides();
+                        adapt_to_system(&mut new_config);
+                        instance.apply_config(&new_config);
+                        config = new_config;
+                        frame_pacing = pace_frames(&config.screensaver, instance);
+                    }
+                    Err(err) => log::warn!("Failed to reload the settings: {}", err),
+                }
+            }
+        }
+
+        // Keep the last frame on the desktop while paused.
+        if clock.is_paused() {
+            std::thread::sleep(OCCLUDED_POLL_INTERVAL);
+            continue;
+        }
+
+        let now = std::time::Instant::now();
+        if frame_pacing.time_until_next_frame(now).is_some() {
+            continue;
+        }
+
+        let timestamp = clock.timestamp();
+        let mut effects = effects(
+            &config.screensaver,
+            &fade,
+            now.saturating_duration_since(clock.start()),
+        );
+        if dimmed_for_notification {
+            effects.brightness *= NOTIFICATION_BRIGHTNESS;
+        }
+        if let Err(err) = instance.draw(timestamp, &effects, &config.screensaver) {
+            log::error!("Failed to render Flux: {}", err);
+        }
+        frame_pacing.frame_drawn(now);
+        occlusion.update([&*instance]);
     }
+
+    Ok(())
 }
 
-fn run_preview_loop(
+// Created by `--stop-wallpaper` to ask a running wallpaper to stop
+fn wallpaper_stop_file() -> path::PathBuf {
+    std::env::temp_dir().join("flux-wallpaper.stop")
+}
+
+fn run_windowed_loop(
     event_pump: &mut sdl2::EventPump,
     instance: &mut Instance,
-    start: std::time::Instant,
+    mut clock: Clock,
+    mut config: Config,
+    settings_window_args: &[OsString],
 ) -> Result<(), String> {
-    use sdl2::event::Event;
+    use sdl2::event::{Event, WindowEvent};
+    use sdl2::keyboard::Keycode;
+    use sdl2::mouse::MouseButton;
+
+    let fade = Fade::new(config.screensaver.fade_duration());
+    let mut occlusion = Occlusion::default();
+    let mut settings_watcher = config.location().map(SettingsWatcher::new);
+    let mut frame_pacing = pace_frames(&config.screensaver, instance);
 
     'main: loop {
-        for event in event_pump.poll_iter() {
+        // Sleep until the next frame is due, but wake up as soon as there’s input.
+        let first_event = frame_pacing
+            .time_until_next_frame(std::time::Instant::now())
+            .and_then(|wait| event_pump.wait_event_timeout(wait_millis(wait)));
+
+        for event in first_event.into_iter().chain(event_pump.poll_iter()) {
             match event {
                 Event::Quit { .. }
                 | Event::Window {
-                    win_event: sdl2::event::WindowEvent::Close,
+                    win_event: WindowEvent::Close,
                     ..
                 } => break 'main,
 
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(width, height),
+                    ..
+                } => instance.resize(PhysicalSize::new(width as u32, height as u32)),
+
+                // A hidden toggle for the diagnostics overlay
+                Event::KeyDown {
+                    keycode: Some(Keycode::F12),
+                    repeat: false,
+                    ..
+                } => instance.toggle_overlay(),
+
+                // Freeze the animation on the current frame. Only the windowed mode has this, since
+                // any key closes the screensaver.
+                Event::KeyDown {
+                    keycode: Some(Keycode::Pause),
+                    repeat: false,
+                    ..
+                } => {
+                    clock.toggle_pause(std::time::Instant::now());
+                    log::info!(
+                        "{} the animation",
+                        if clock.is_paused() {
+                            "Paused"
+                        } else {
+                            "Resumed"
+                        }
+                    );
+                }
+
+                // Open the settings next to the window. Changes show up once they’re saved.
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Right,
+                    ..
+                } => open_settings_window(settings_window_args),
+
                 _ => (),
             }
         }
 
-        let timestamp = start.elapsed().as_secs_f64() * 1000.0;
-        if let Err(err) = instance.draw(timestamp) {
+        if session_is_ending() {
+            break 'main;
+        }
+
+        let now = std::time::Instant::now();
+        if let Some(watcher) = settings_watcher.as_mut() {
+            if watcher.has_changed(now) {
+                match config.reload() {
+                    Ok(mut new_config) => {
+                        new_config.apply_env_overrides();
+                        adapt_to_system(&mut new_config);
+                        instance.apply_config(&new_config);
+                        config = new_config;
+                        frame_pacing = pace_frames(&config.screensaver, instance);
+                    }
+                    Err(err) => log::warn!("Failed to reload the settings: {}", err),
+                }
+            }
+        }
+
+        // While paused, the timestamp stands still, so Flux draws the same frame without stepping
+        // the simulation. Drawing still keeps the window up to date as it’s resized.
+        let now = std::time::Instant::now();
+        if frame_pacing.time_until_next_frame(now).is_some() {
+            continue;
+        }
+
+        let timestamp = clock.timestamp();
+        let effects = effects(
+            &config.screensaver,
+            &fade,
+            now.saturating_duration_since(clock.start()),
+        );
+        if let Err(err) = instance.draw(timestamp, &effects, &config.screensaver) {
             log::error!("Failed to render Flux: {}", err);
         }
+        frame_pacing.frame_drawn(now);
+        occlusion.update([&*instance]);
     }
 
     Ok(())
 }
 
+// Whether every window is covered, like by a UAC prompt or another topmost window.
+//
+// Presenting to a covered window returns immediately, so the loops sleep between frames instead
+// of spinning until one of the windows is visible again.
+#[derive(Default)]
+struct Occlusion {
+    occluded: bool,
+}
+
+impl Occlusion {
+    fn update<'a>(&mut self, instances: impl IntoIterator<Item = &'a Instance>) {
+        let occluded = instances.into_iter().all(Instance::is_occluded);
+        if occluded != self.occluded {
+            if occluded {
+                log::info!("The screensaver is covered. Pausing rendering.");
+            } else {
+                log::info!("The screensaver is visible again. Resuming rendering.");
+            }
+            self.occluded = occluded;
+        }
+
+        if self.occluded {
+            std::thread::sleep(OCCLUDED_POLL_INTERVAL);
+        }
+    }
+}
+
+// Launch the settings window as a separate process, since it runs its own event loop.
+fn open_settings_window(args: &[OsString]) {
+    let result =
+        std::env::current_exe().and_then(|exe| std::process::Command::new(exe).args(args).spawn());
+    if let Err(err) = result {
+        log::error!("Failed to open the settings window: {}", err);
+    }
+}
+
+// Create a window for each surface, laid out across the monitors that are connected right now.
+fn new_screensaver_instances(
+    video_subsystem: &sdl2::VideoSubsystem,
+    config: &Config,
+) -> Result<HashMap<WindowId, Instance>, String> {
+    let wallpaper_api = wallpaper::DesktopWallpaper::new().ok();
+    let monitors = video_subsystem
+        .available_monitors()
+        .enumerate()
+        .map(|(index, monitor)| {
+            (
+                monitor.clone(),
+                wallpaper_api
+                    .as_ref()
+                    .and_then(|wallpaper| wallpaper.get(index as u32).ok()),
+            )
+        })
+        .collect::<Vec<(MonitorHandle, Option<std::path::PathBuf>)>>();
+    log::debug!("Available monitors: {:?}", monitors);
+    let monitors = select_monitors(monitors, config.platform.windows.monitor_selection);
+
+    let fill_mode = config.platform.windows.fill_mode;
+    let surfaces = surface::build(&monitors, fill_mode);
+    log::debug!("Creating windows: {:?}", surfaces);
+
+    let instances = match fill_mode {
+        FillMode::Mirror => new_mirrored_instances(video_subsystem, config, &surfaces)?,
+        _ => surfaces
+            .iter()
+            .map(|surface| new_instance(video_subsystem, config, surface, None))
+            .collect::<Result<Vec<Instance>, String>>()?,
+    };
+
+    Ok(instances
+        .into_iter()
+        .map(|instance| (instance.window.id(), instance))
+        .collect())
+}
+
+// Keep the monitors picked by their position. If that leaves none, like with a single monitor and
+// everything but the primary one, cover them all rather than show nothing.
+fn select_monitors(
+    monitors: Vec<(MonitorHandle, Option<std::path::PathBuf>)>,
+    selection: MonitorSelection,
+) -> Vec<(MonitorHandle, Option<std::path::PathBuf>)> {
+    if selection == MonitorSelection::All {
+        return monitors;
+    }
+
+    let primary = primary_monitor_name();
+    let selected = monitors
+        .iter()
+        .filter(|(monitor, _)| {
+            let is_primary = primary.is_some() && monitor.name() == primary;
+            selection.includes(is_primary)
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    if selected.is_empty() {
+        log::warn!(
+            "No monitors match the selection ({}), so covering all of them",
+            selection
+        );
+        return monitors;
+    }
+    selected
+}
+
+#[cfg(windows)]
+fn primary_monitor_name() -> Option<String> {
+    platform::windows::monitors::primary_device_name()
+}
+
+#[cfg(not(windows))]
+fn primary_monitor_name() -> Option<String> {
+    None
+}
+
+// The shortest time between frames. Without vsync to hold them back, frames are also kept to the
+// refresh rate of the fastest display.
+fn min_frame_interval<'a>(
+    settings: &ScreensaverSettings,
+    instances: impl IntoIterator<Item = &'a Instance>,
+) -> std::time::Duration {
+    let interval = settings.min_frame_interval();
+    if settings.present_mode.waits_for_vsync() {
+        return interval;
+    }
+
+    let refresh_rate = instances
+        .into_iter()
+        .filter_map(|instance| refresh_rate(&instance.window))
+        .max()
+        .unwrap_or(DEFAULT_REFRESH_RATE);
+    log::debug!("Pacing frames to {} Hz without vsync", refresh_rate);
+    interval.max(std::time::Duration::from_secs(1) / refresh_rate)
+}
+
+#[cfg(windows)]
+fn refresh_rate(window: &Window) -> Option<u32> {
+    let name = window.current_monitor()?.name()?;
+    platform::windows::orientation::current_mode(&name)?.refresh_rate
+}
+
+#[cfg(not(windows))]
+fn refresh_rate(_window: &Window) -> Option<u32> {
+    None
+}
+
+// Space out the frames of a single window, for the loops without a power-saving cycle. Waiting
+// for the next frame lets the thread sleep instead of spinning when nothing holds it back, like
+// with vsync off.
+fn pace_frames(settings: &ScreensaverSettings, instance: &Instance) -> IdleCycle {
+    let now = std::time::Instant::now();
+    IdleCycle::new(std::time::Duration::ZERO, std::time::Duration::ZERO, now)
+        .with_min_frame_interval(min_frame_interval(settings, [instance]))
+}
+
+// Give up on a screensaver that never managed to show a frame, so that the user gets their
+// desktop back. This runs on the watchdog thread while the main thread is stuck.
+fn exit_after_hang(lock_on_exit: bool) {
+    log::error!("Nothing was drawn since starting. The graphics driver may be stuck. Exiting.");
+    if lock_on_exit {
+        lock_workstation();
+    }
+    std::process::exit(1);
+}
+
+#[cfg(windows)]
+fn lock_workstation() {
+    platform::windows::lock::lock_workstation();
+}
+
+#[cfg(not(windows))]
+fn lock_workstation() {}
+
+// Run the screensaver until there’s input. `rebuild_instances` lays out the windows again when
+// a display is connected or disconnected, like when docking a laptop.
 fn run_main_loop(
     event_pump: &mut sdl2::EventPump,
     instances: &mut HashMap<WindowId, Instance>,
-    start: std::time::Instant,
+    mut clock: Clock,
+    settings: &ScreensaverSettings,
+    watchdog: &mut FirstFrameWatchdog,
+    rebuild_instances: impl Fn() -> Result<HashMap<WindowId, Instance>, String>,
 ) -> Result<(), String> {
     use sdl2::event::Event;
 
+    // Leave the windows blank until the startup delay is over.
+    let mut delay_end =
+        (!settings.startup_delay().is_zero()).then(|| clock.start() + settings.startup_delay());
+    let mut fade = Fade::new(settings.fade_duration());
+    let mut occlusion = Occlusion::default();
+    let mut exit_on_input = ExitOnInput::new(clock.start(), settings.input_grace())
+        .with_mouse_exit_distance(settings.mouse_exit_distance)
+        .with_ignored_keys(settings.ignored_keys.clone());
+    let mut idle_cycle = IdleCycle::new(
+        settings.active_duration(),
+        settings.idle_duration(),
+        clock.start(),
+    )
+    .with_min_frame_interval(min_frame_interval(settings, instances.values()));
+    let mut stats = SessionStats::new(clock.start());
+
     'main: loop {
-        for event in event_pump.poll_iter() {
+        // While idle or waiting to start, sleep until the next frame is due, but wake up as soon
+        // as there’s input.
+        let wait = if fade.is_exiting() {
+            None
+        } else if let Some(delay_end) = delay_end {
+            Some(delay_end.saturating_duration_since(std::time::Instant::now()))
+        } else {
+            idle_cycle.time_until_next_frame(std::time::Instant::now())
+        };
+        let first_event = wait.and_then(|wait| event_pump.wait_event_timeout(wait_millis(wait)));
+
+        for event in first_event.into_iter().chain(event_pump.poll_iter()) {
             match event {
+                // Close immediately if asked to by the system.
                 Event::Quit { .. }
                 | Event::Window {
                     win_event: sdl2::event::WindowEvent::Close,
                     ..
+                } => break 'main,
+
+                Event::KeyDown { keycode, .. } => {
+                    if exit_on_input.key_press(std::time::Instant::now(), keycode) {
+                        fade.exit();
+                    }
                 }
-                | Event::KeyDown { .. }
-                | Event::MouseButtonDown { .. } => {
-                    break 'main;
+
+                Event::MouseButtonDown { .. } => {
+                    if exit_on_input.press(std::time::Instant::now()) {
+                        fade.exit();
+                    }
                 }
 
-                Event::MouseMotion { xrel, yrel, .. } => {
-                    if f64::max(xrel.abs() as f64, yrel.abs() as f64)
-                        > MINIMUM_MOUSE_MOTION_TO_EXIT_SCREENSAVER
-                    {
-                        break 'main;
+                Event::MouseMotion {
+                    window_id,
+                    xrel,
+                    yrel,
+                    ..
+                } => {
+                    let scale_factor = instances
+                        .get(&window_id)
+                        .map_or(1.0, |instance| instance.window.scale_factor());
+                    if exit_on_input.motion(std::time::Instant::now(), xrel, yrel, scale_factor) {
+                        fade.exit();
                     }
                 }
 
@@ -293,26 +1690,141 @@ fn run_main_loop(
             }
         }
 
-        for (_, instance) in instances.iter_mut() {
-            let timestamp = start.elapsed().as_secs_f64() * 1000.0;
-            if let Err(err) = instance.draw(timestamp) {
+        if displays_changed() {
+            log::info!("The displays changed. Recreating the windows.");
+
+            // Release the old windows and their swapchains before creating new ones on the same
+            // devices.
+            record_windows(&mut stats, instances);
+            instances.clear();
+            *instances = rebuild_instances()?;
+            for instance in instances.values_mut() {
+                instance.window.show();
+            }
+            // The displays may refresh at different rates now.
+            idle_cycle = idle_cycle
+                .with_min_frame_interval(min_frame_interval(settings, instances.values()));
+
+            // The new windows can send a burst of mouse movement, which isn’t the user.
+            exit_on_input = ExitOnInput::new(std::time::Instant::now(), settings.input_grace())
+                .with_mouse_exit_distance(settings.mouse_exit_distance)
+                .with_ignored_keys(settings.ignored_keys.clone());
+        }
+
+        // Exit the same way as on input once the screensaver has run for long enough.
+        if settings
+            .max_runtime()
+            .is_some_and(|max_runtime| clock.start().elapsed() >= max_runtime)
+        {
+            fade.exit();
+        }
+
+        // Skip the fade-out if the session is ending, since Windows won’t wait for long.
+        if fade.is_finished() || session_is_ending() {
+            break 'main;
+        }
+
+        let now = std::time::Instant::now();
+        if let Some(end) = delay_end {
+            // Nothing has been shown yet, so there’s nothing to fade out.
+            if fade.is_exiting() {
+                break 'main;
+            }
+            if now < end {
+                continue;
+            }
+
+            // Start the animation, the fade-in, and the power-saving cycle from here.
+            delay_end = None;
+            clock.restart(now);
+            fade = Fade::new(settings.fade_duration());
+            idle_cycle = IdleCycle::new(settings.active_duration(), settings.idle_duration(), now)
+                .with_min_frame_interval(min_frame_interval(settings, instances.values()));
+        }
+
+        // Skip the frame if we were woken up early, unless we’re fading out.
+        if !fade.is_exiting() && idle_cycle.time_until_next_frame(now).is_some() {
+            continue;
+        }
+
+        if watchdog.is_overdue(now) {
+            log::error!(
+                "Nothing was drawn since starting. Falling back to presenting with OpenGL."
+            );
+            for instance in instances.values_mut() {
+                instance.fall_back_to_gl(settings);
+            }
+        }
+
+        let effects = effects(
+            settings,
+            &fade,
+            now.saturating_duration_since(clock.start()),
+        );
+
+        // Draw the instances running Flux first, so that mirrors show the latest frame.
+        let (mirrors, sources): (Vec<&mut Instance>, Vec<&mut Instance>) = instances
+            .values_mut()
+            .partition(|instance| instance.is_mirror());
+        let mut failed = false;
+        for instance in sources.into_iter().chain(mirrors) {
+            let timestamp = clock.timestamp();
+            if let Err(err) = instance.draw(timestamp, &effects, settings) {
                 log::error!("Failed to render Flux: {}", err);
+                failed = true;
             }
         }
+        if !failed {
+            watchdog.frame_presented();
+        }
+        stats.frame_drawn(failed);
+        idle_cycle.frame_drawn(now);
+        occlusion.update(instances.values());
+    }
+
+    if settings.log_session_summary {
+        record_windows(&mut stats, instances);
+        log::info!(
+            "Session summary: {}",
+            stats.summary(std::time::Instant::now())
+        );
     }
 
     Ok(())
 }
 
+fn record_windows(stats: &mut SessionStats, instances: &HashMap<WindowId, Instance>) {
+    for instance in instances.values() {
+        stats.record_window(
+            instance.diagnostics().presentation,
+            instance.device_losses.count(),
+        );
+    }
+}
+
+// Use the reduced-detail preset for previews the size of a thumbnail, unless the settings turn it off.
+#[cfg(windows)]
+fn fit_preview(config: &Config, size: PhysicalSize<u32>) -> Config {
+    let mut config = config.clone();
+    if config.screensaver.simplify_thumbnails && size.width.min(size.height) < THUMBNAIL_SIZE {
+        for fix in config.fit_thumbnail() {
+            log::info!(
+                "Simplifying the {}x{} preview: {}",
+                size.width,
+                size.height,
+                fix
+            );
+        }
+    }
+    config
+}
+
 #[cfg(windows)]
 fn new_preview_window(
     video_subsystem: &sdl2::VideoSubsystem,
     raw_window_handle: RawWindowHandle,
     config: &Config,
 ) -> Result<Instance, String> {
-    use windows::Win32::Foundation::RECT;
-    use windows::Win32::UI::WindowsAndMessaging::GetClientRect;
-
     let win32_handle = match raw_window_handle {
         RawWindowHandle::Win32(handle) => handle,
         _ => return Err("This platform is not supported yet".to_string()),
@@ -320,12 +1832,17 @@ fn new_preview_window(
 
     let preview_hwnd = HWND(win32_handle.hwnd as _);
 
-    let mut rect = RECT::default();
-    unsafe {
-        let _ = GetClientRect(preview_hwnd, &mut rect);
-    }
-
-    let inner_size = PhysicalSize::new(rect.right as u32, rect.bottom as u32);
+    // The dialog can report an empty client rect while it lays itself out, and neither GL nor DXGI
+    // can make a surface with no pixels. Render at a minimum size, and present with plain OpenGL,
+    // rather than set up a swapchain for next to nothing.
+    let client_size = platform::windows::window::client_size(preview_hwnd);
+    let is_degenerate =
+        client_size.width < MIN_PREVIEW_SIZE || client_size.height < MIN_PREVIEW_SIZE;
+    let inner_size = PhysicalSize::new(
+        client_size.width.max(MIN_PREVIEW_SIZE),
+        client_size.height.max(MIN_PREVIEW_SIZE),
+    );
+    let config = &fit_preview(config, inner_size);
 
     // You need to create an actual window to listen to events. We’ll
     // then link this to the preview window as a child to cleanup when
@@ -358,9 +1875,19 @@ fn new_preview_window(
         inner_size,
         raw_window_handle,
         Some(window.raw_window_handle()),
+        None,
     );
 
-    let swapchain = create_swapchain(&raw_window_handle, &gl_context);
+    let swapchain = if is_degenerate {
+        log::info!(
+            "The preview is only {}x{}. Presenting with OpenGL",
+            client_size.width,
+            client_size.height
+        );
+        create_gl_swapchain(&gl_context, &config.screensaver)
+    } else {
+        create_swapchain(&raw_window_handle, &gl_context, config)
+    };
 
     let some_current_monitor = window.current_monitor();
     let current_monitor_index = some_current_monitor
@@ -375,33 +1902,37 @@ fn new_preview_window(
         .ok()
         .and_then(|wallpaper| wallpaper.get(current_monitor_index).ok());
 
-    let physical_size = window.inner_size();
-    let scale_factor = window.scale_factor();
-    let logical_size = physical_size.to_logical(scale_factor);
-    let settings = config.to_settings(wallpaper);
-    let flux = Flux::new(
+    let content = Content::new(
         &gl_context.gl,
-        logical_size.width,
-        logical_size.height,
-        physical_size.width,
-        physical_size.height,
-        &Rc::new(settings),
-    )
-    .map_err(|err| err.to_string())?;
+        window.inner_size(),
+        window.scale_factor(),
+        config,
+        wallpaper,
+    )?;
 
     Ok(Instance {
-        flux,
+        content,
+        overlay: None,
         gl_context,
         window,
         swapchain,
+        checked_viewport: false,
+        device_losses: DeviceLosses::default(),
     })
 }
 
+// Create a window for a surface.
+//
+// If `mirror_of` is set, the window shares its GL context with that instance and shows its frames
+// instead of running Flux, as long as it has frames to share.
 fn new_instance(
     video_subsystem: &sdl2::VideoSubsystem,
     config: &Config,
     surface: &surface::Surface,
+    mirror_of: Option<&mut Instance>,
 ) -> Result<Instance, String> {
+    let config = &config.for_monitor(surface.monitor.as_deref());
+
     // Create the SDL window
     let window = video_subsystem
         .window("Flux", surface.size.width, surface.size.height)
@@ -418,41 +1949,149 @@ fn new_instance(
         platform::windows::window::enable_transparency(&window.raw_window_handle())
     };
 
+    // Size everything to the window’s physical client area. This should match the surface, unless
+    // the DPI awareness settings were overridden.
+    let physical_size = window.inner_size();
+    if physical_size != surface.size {
+        log::warn!(
+            "Created a {}x{} window for a {}x{} surface",
+            physical_size.width,
+            physical_size.height,
+            surface.size.width,
+            surface.size.height
+        );
+    }
+
     let gl_context = gl_context::new_gl_context(
         window.raw_display_handle(),
-        window.size().into(),
+        physical_size,
         window.raw_window_handle(),
         None,
+        mirror_of.as_ref().map(|source| &source.gl_context.context),
     );
 
-    let swapchain = create_swapchain(&window.raw_window_handle(), &gl_context);
-
-    let physical_size = surface.size;
-    let logical_size = physical_size.to_logical(surface.scale_factor);
-    let settings = config.to_settings(surface.wallpaper.clone());
-    let flux = Flux::new(
-        &Rc::clone(&gl_context.gl),
-        logical_size.width,
-        logical_size.height,
-        physical_size.width,
-        physical_size.height,
-        &Rc::new(settings),
-    )
-    .map_err(|err| err.to_string())?;
+    let swapchain = create_swapchain(&window.raw_window_handle(), &gl_context, config);
+
+    let content = match mirror_of.and_then(|source| source.content.share_frame()) {
+        Some(frame) => {
+            let mut post_process = PostProcess::new(
+                &gl_context.gl,
+                physical_size,
+                config.screensaver.render_scale,
+                config.screensaver.upscale_filter,
+            )?;
+            // Mirrors only present a finished frame, so there’s nothing to multisample.
+            if config.screensaver.anti_aliasing == AntiAliasing::Fxaa {
+                post_process.set_anti_aliasing(AntiAliasing::Fxaa)?;
+            }
+            post_process.set_crop_anchor(config.platform.windows.crop_anchor);
+            post_process.set_watermark(config);
+            Content::Mirror(frame, post_process)
+        }
+        None => Content::new(
+            &gl_context.gl,
+            physical_size,
+            surface.scale_factor,
+            config,
+            surface.wallpaper.clone(),
+        )?,
+    };
+
+    Ok(Instance {
+        content,
+        overlay: None,
+        gl_context,
+        window,
+        swapchain,
+        checked_viewport: false,
+        device_losses: DeviceLosses::default(),
+    })
+}
+
+// Run Flux on the largest surface and mirror it to the rest.
+fn new_mirrored_instances(
+    video_subsystem: &sdl2::VideoSubsystem,
+    config: &Config,
+    surfaces: &[surface::Surface],
+) -> Result<Vec<Instance>, String> {
+    let primary_index = match surfaces
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, surface)| surface.size.width as u64 * surface.size.height as u64)
+    {
+        Some((index, _)) => index,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut primary = new_instance(video_subsystem, config, &surfaces[primary_index], None)?;
+    let mut mirrors = Vec::with_capacity(surfaces.len() - 1);
+    for (index, surface) in surfaces.iter().enumerate() {
+        if index != primary_index {
+            mirrors.push(new_instance(
+                video_subsystem,
+                config,
+                surface,
+                Some(&mut primary),
+            )?);
+        }
+    }
+
+    let mut instances = vec![primary];
+    instances.extend(mirrors);
+    Ok(instances)
+}
+
+fn new_windowed_instance(
+    video_subsystem: &sdl2::VideoSubsystem,
+    config: &Config,
+    size: PhysicalSize<u32>,
+) -> Result<Instance, String> {
+    let window = video_subsystem
+        .window("Flux", size.width, size.height)
+        .position_centered()
+        .resizable()
+        .hidden()
+        .allow_highdpi()
+        .build()
+        .map_err(|err| err.to_string())?;
+
+    let gl_context = gl_context::new_gl_context(
+        window.raw_display_handle(),
+        window.inner_size(),
+        window.raw_window_handle(),
+        None,
+        None,
+    );
+
+    let swapchain = create_swapchain(&window.raw_window_handle(), &gl_context, config);
+
+    let wallpaper = window_wallpaper(&window);
+    let content = Content::new(
+        &gl_context.gl,
+        window.inner_size(),
+        window.scale_factor(),
+        config,
+        wallpaper,
+    )?;
 
     Ok(Instance {
-        flux,
+        content,
+        overlay: None,
         gl_context,
         window,
         swapchain,
+        checked_viewport: false,
+        device_losses: DeviceLosses::default(),
     })
 }
 
 #[cfg(not(windows))]
 fn create_swapchain(
-    raw_window_handle: &RawWindowHandle,
+    _raw_window_handle: &RawWindowHandle,
     gl_context: &gl_context::GLContext,
+    config: &Config,
 ) -> Swapchain {
+    clear_gl_surface(gl_context, config.screensaver.clear_color());
     Swapchain::Gl
 }
 
@@ -460,30 +2099,84 @@ fn create_swapchain(
 fn create_swapchain(
     raw_window_handle: &RawWindowHandle,
     gl_context: &gl_context::GLContext,
+    config: &Config,
 ) -> Swapchain {
-    let dxgi_interop =
-        platform::windows::dxgi_swapchain::create_dxgi_swapchain(raw_window_handle, &gl_context.gl);
+    let clear_color = config.screensaver.clear_color();
+
+    // Skip the interop entirely, so that none of the NV_DX_interop functions are even loaded.
+    if !config.platform.windows.use_dxgi {
+        log::info!("DXGI is disabled. Presenting with OpenGL");
+        return create_gl_swapchain(gl_context, &config.screensaver);
+    }
+
+    let dxgi_interop = platform::windows::dxgi_swapchain::create_dxgi_swapchain(
+        raw_window_handle,
+        &gl_context.gl,
+        clear_color,
+        config.platform.windows.adapter.as_deref(),
+        config.platform.windows.interop_attachment,
+    );
 
     match dxgi_interop {
-        Ok(dxgi_interop) => Swapchain::Dxgi(dxgi_interop),
+        Ok(mut dxgi_interop) => {
+            dxgi_interop.set_vsync(config.screensaver.present_mode.waits_for_vsync());
+            Swapchain::Dxgi(dxgi_interop)
+        }
         Err(err) => {
-            use glutin::surface::SwapInterval;
-            use std::num::NonZeroU32;
-
             log::warn!(
                 "Failed to create DXGI swapchain: {}. Falling back to GL.",
                 err
             );
+            create_gl_swapchain(gl_context, &config.screensaver)
+        }
+    }
+}
 
-            // Try setting vsync.
-            if let Err(res) = gl_context.surface.set_swap_interval(
-                &gl_context.context,
-                SwapInterval::Wait(NonZeroU32::new(1).unwrap()),
-            ) {
-                log::error!("Failed to set vsync: {res:?}");
-            }
+// Present with SwapBuffers on the window’s own GL surface.
+#[cfg(windows)]
+fn create_gl_swapchain(
+    gl_context: &gl_context::GLContext,
+    settings: &ScreensaverSettings,
+) -> Swapchain {
+    use glutin::surface::SwapInterval;
+    use std::num::NonZeroU32;
+
+    // Try setting vsync, unless the main loop is pacing the frames itself.
+    let interval = if settings.present_mode.waits_for_vsync() {
+        SwapInterval::Wait(NonZeroU32::new(1).unwrap())
+    } else {
+        SwapInterval::DontWait
+    };
+    if let Err(res) = gl_context
+        .surface
+        .set_swap_interval(&gl_context.context, interval)
+    {
+        log::error!("Failed to set vsync: {res:?}");
+    }
 
-            Swapchain::Gl
-        }
+    clear_gl_surface(gl_context, settings.clear_color());
+    Swapchain::Gl
+}
+
+// Present a frame filled with the clear color, so that the window doesn’t flash black before Flux
+// renders its first frame.
+fn clear_gl_surface(gl_context: &gl_context::GLContext, clear_color: [f32; 4]) {
+    let [r, g, b, a] = clear_color;
+    unsafe {
+        gl_context.gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+        gl_context.gl.clear_color(r, g, b, a);
+        gl_context.gl.clear(GL::COLOR_BUFFER_BIT);
+    }
+
+    if let Err(err) = gl_context.surface.swap_buffers(&gl_context.context) {
+        log::warn!("Failed to present the initial frame: {}", err);
     }
 }
+
+// The wallpaper on the display the window is on, if any.
+fn window_wallpaper(window: &Window) -> Option<path::PathBuf> {
+    let window_index = window.display_index().unwrap_or(0) as u32;
+    wallpaper::DesktopWallpaper::new()
+        .ok()
+        .and_then(|wallpaper| wallpaper.get(window_index).ok())
+}
@@ -0,0 +1,306 @@
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use glow as GL;
+use glow::HasContext;
+use winit::dpi::PhysicalSize;
+
+use crate::postprocess::create_program;
+
+// How often to refresh the frame rate
+const UPDATE_INTERVAL: Duration = Duration::from_millis(500);
+
+// Each pixel of the font covers this many screen pixels
+const SCALE: u32 = 2;
+
+// The gap between the overlay and the corner of the window, in screen pixels
+const MARGIN: u32 = 8;
+
+// A small text overlay in the corner of the window with the frame rate and how frames are
+// presented. It’s meant for reading off numbers when tracking down choppy playback.
+pub struct Overlay {
+    gl: Rc<glow::Context>,
+    program: GL::NativeProgram,
+    vertex_array: GL::NativeVertexArray,
+    texture: GL::NativeTexture,
+    rect_location: Option<GL::NativeUniformLocation>,
+    // The size of the rasterized text, in font pixels
+    text_size: PhysicalSize<u32>,
+
+    // Lines shown below the frame rate, like the adapter name
+    details: Vec<String>,
+    frames: u32,
+    since: Instant,
+}
+
+const VERTEX_SHADER: &str = r#"
+out vec2 v_uv;
+
+// The bottom-left corner and size of the overlay in clip space
+uniform vec4 u_rect;
+
+void main() {
+    // Draw a quad as a triangle strip.
+    vec2 position = vec2(float(gl_VertexID & 1), float((gl_VertexID >> 1) & 1));
+    v_uv = vec2(position.x, 1.0 - position.y);
+    gl_Position = vec4(u_rect.xy + position * u_rect.zw, 0.0, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+in vec2 v_uv;
+out vec4 out_color;
+
+uniform sampler2D u_text;
+
+void main() {
+    out_color = texture(u_text, v_uv);
+}
+"#;
+
+impl Overlay {
+    pub fn new(gl: &Rc<glow::Context>, details: Vec<String>) -> Result<Self, String> {
+        unsafe {
+            let program = create_program(gl, VERTEX_SHADER, FRAGMENT_SHADER)?;
+            let vertex_array = gl.create_vertex_array()?;
+            let texture = gl.create_texture()?;
+
+            gl.use_program(Some(program));
+            gl.uniform_1_i32(gl.get_uniform_location(program, "u_text").as_ref(), 0);
+            let rect_location = gl.get_uniform_location(program, "u_rect");
+            gl.use_program(None);
+
+            gl.bind_texture(GL::TEXTURE_2D, Some(texture));
+            gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
+            gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
+            gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+            gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+            gl.bind_texture(GL::TEXTURE_2D, None);
+
+            let mut overlay = Self {
+                gl: Rc::clone(gl),
+                program,
+                vertex_array,
+                texture,
+                rect_location,
+                text_size: PhysicalSize::new(0, 0),
+                details,
+                frames: 0,
+                since: Instant::now(),
+            };
+            overlay.set_frame_rate(None);
+
+            Ok(overlay)
+        }
+    }
+
+    // Count a frame and draw the overlay on top of it.
+    pub fn draw(&mut self, target: Option<GL::NativeFramebuffer>, size: PhysicalSize<u32>) {
+        self.frames += 1;
+        let elapsed = self.since.elapsed();
+        if elapsed >= UPDATE_INTERVAL {
+            self.set_frame_rate(Some(self.frames as f64 / elapsed.as_secs_f64()));
+            self.frames = 0;
+            self.since = Instant::now();
+        }
+
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+
+        let gl = &self.gl;
+        let width = 2.0 * (self.text_size.width * SCALE) as f32 / size.width as f32;
+        let height = 2.0 * (self.text_size.height * SCALE) as f32 / size.height as f32;
+        let left = -1.0 + 2.0 * MARGIN as f32 / size.width as f32;
+        let top = 1.0 - 2.0 * MARGIN as f32 / size.height as f32;
+
+        unsafe {
+            gl.bind_framebuffer(GL::FRAMEBUFFER, target);
+            gl.viewport(0, 0, size.width as i32, size.height as i32);
+
+            let blend_enabled = gl.is_enabled(GL::BLEND);
+            gl.enable(GL::BLEND);
+            // Leave the destination alpha alone, so the overlay doesn’t punch through
+            // transparent windows.
+            gl.blend_func_separate(GL::SRC_ALPHA, GL::ONE_MINUS_SRC_ALPHA, GL::ZERO, GL::ONE);
+
+            gl.use_program(Some(self.program));
+            gl.uniform_4_f32(
+                self.rect_location.as_ref(),
+                left,
+                top - height,
+                width,
+                height,
+            );
+
+            gl.active_texture(GL::TEXTURE0);
+            gl.bind_texture(GL::TEXTURE_2D, Some(self.texture));
+            gl.bind_vertex_array(Some(self.vertex_array));
+            gl.draw_arrays(GL::TRIANGLE_STRIP, 0, 4);
+
+            gl.bind_vertex_array(None);
+            gl.bind_texture(GL::TEXTURE_2D, None);
+            gl.use_program(None);
+
+            if !blend_enabled {
+                gl.disable(GL::BLEND);
+            }
+        }
+    }
+
+    fn set_frame_rate(&mut self, frame_rate: Option<f64>) {
+        let first_line = match frame_rate {
+            Some(fps) if fps > 0.0 => format!("{:.0} FPS  {:.1} MS", fps, 1000.0 / fps),
+            _ => "-- FPS".to_string(),
+        };
+
+        let mut lines = vec![first_line];
+        lines.extend(self.details.iter().cloned());
+
+        let (pixels, size) = rasterize(&lines);
+        self.text_size = size;
+
+        let gl = &self.gl;
+        unsafe {
+            gl.bind_texture(GL::TEXTURE_2D, Some(self.texture));
+            gl.tex_image_2d(
+                GL::TEXTURE_2D,
+                0,
+                GL::RGBA8 as i32,
+                size.width as i32,
+                size.height as i32,
+                0,
+                GL::RGBA,
+                GL::UNSIGNED_BYTE,
+                Some(&pixels),
+            );
+            gl.bind_texture(GL::TEXTURE_2D, None);
+        }
+    }
+}
+
+impl Drop for Overlay {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_program(self.program);
+            self.gl.delete_vertex_array(self.vertex_array);
+            self.gl.delete_texture(self.texture);
+        }
+    }
+}
+
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+// Glyphs plus the spacing between them
+const CELL_WIDTH: u32 = GLYPH_WIDTH + 1;
+const CELL_HEIGHT: u32 = GLYPH_HEIGHT + 2;
+const PADDING: u32 = 4;
+
+const BACKGROUND: [u8; 4] = [0, 0, 0, 160];
+const FOREGROUND: [u8; 4] = [255, 255, 255, 255];
+
+// Draw the lines of text into an RGBA image, top row first.
+fn rasterize(lines: &[String]) -> (Vec<u8>, PhysicalSize<u32>) {
+    let columns = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0) as u32;
+    let width = 2 * PADDING + columns * CELL_WIDTH;
+    let height = 2 * PADDING + lines.len() as u32 * CELL_HEIGHT;
+
+    let mut pixels = BACKGROUND.repeat((width * height) as usize);
+    for (row, line) in lines.iter().enumerate() {
+        for (column, c) in line.chars().enumerate() {
+            let x0 = PADDING + column as u32 * CELL_WIDTH;
+            let y0 = PADDING + row as u32 * CELL_HEIGHT;
+            for (y, bits) in glyph(c).iter().enumerate() {
+                for x in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - x)) != 0 {
+                        let index = (((y0 + y as u32) * width + x0 + x) * 4) as usize;
+                        pixels[index..index + 4].copy_from_slice(&FOREGROUND);
+                    }
+                }
+            }
+        }
+    }
+
+    (pixels, PhysicalSize::new(width, height))
+}
+
+// A 5x7 bitmap for each character, one row per byte with the leftmost pixel in the highest bit.
+// Letters are drawn in uppercase, and anything without a glyph shows up as a question mark.
+#[rustfmt::skip]
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0; 7],
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        'A' => [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+        'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+        'D' => [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100],
+        'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+        'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+        'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+        'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+        'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+        'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+        'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+        'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+        'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+        'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+        'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+        'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+        'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+        'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+        'Y' => [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100],
+        'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+        ',' => [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000],
+        ':' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000],
+        '-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+        '_' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111],
+        '+' => [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000],
+        '%' => [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011],
+        '/' => [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000],
+        '(' => [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010],
+        ')' => [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000],
+        _ => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_sizes_the_text_to_the_longest_line() {
+        let (pixels, size) = rasterize(&["60 FPS".to_string(), "OpenGL".to_string()]);
+        assert_eq!(
+            size,
+            PhysicalSize::new(2 * PADDING + 6 * CELL_WIDTH, 2 * PADDING + 2 * CELL_HEIGHT)
+        );
+        assert_eq!(pixels.len(), (size.width * size.height * 4) as usize);
+    }
+
+    #[test]
+    fn it_draws_spaces_as_background() {
+        let (pixels, _) = rasterize(&[" ".to_string()]);
+        assert!(pixels.chunks(4).all(|pixel| pixel == BACKGROUND));
+    }
+}
@@ -0,0 +1,349 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{PoisonError, RwLock};
+use std::{fmt, fs, io, path};
+
+use flux::settings::ColorPreset;
+
+pub type Color = [f32; 3];
+
+// Named palettes that Flux doesn’t ship with.
+//
+// Flux renders these by sampling colors from a generated gradient image, the same way it handles
+// the desktop wallpaper.
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
+pub enum Gradient {
+    Sunset,
+    Mono,
+
+    // Palettes for color vision deficiencies. These vary mostly in brightness, with hues picked
+    // along an axis that stays distinguishable for each deficiency.
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl Gradient {
+    pub fn colors(&self) -> &'static [Color] {
+        match self {
+            Gradient::Sunset => &SUNSET,
+            Gradient::Mono => &MONO,
+            Gradient::Deuteranopia => &DEUTERANOPIA,
+            Gradient::Protanopia => &PROTANOPIA,
+            Gradient::Tritanopia => &TRITANOPIA,
+        }
+    }
+}
+
+impl fmt::Display for Gradient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Gradient::Sunset => "Sunset",
+                Gradient::Mono => "Mono",
+                Gradient::Deuteranopia => "Deuteranopia (blue–orange)",
+                Gradient::Protanopia => "Protanopia (blue–yellow)",
+                Gradient::Tritanopia => "Tritanopia (red–cyan)",
+            }
+        )
+    }
+}
+
+// The file next to the settings file where people can define palettes of their own
+pub const CUSTOM_PALETTES_FILE: &str = "palettes.toml";
+
+// A palette from `palettes.toml`, like `Ocean = ["#003f5c", "#2f4b7c", "#a05195"]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomPalette {
+    pub name: String,
+    pub colors: Vec<Color>,
+}
+
+// The custom palettes from the settings folder. Color modes only keep a palette’s name and look up
+// its colors when they’re needed, so the palettes can be loaded again at any time.
+static CUSTOM_PALETTES: RwLock<Vec<CustomPalette>> = RwLock::new(Vec::new());
+
+// Load the custom palettes from the settings folder, replacing any loaded before. A missing file
+// just means there are none.
+pub fn load_custom_palettes(settings_dir: Option<&path::Path>) {
+    let palettes = match settings_dir.map(|dir| dir.join(CUSTOM_PALETTES_FILE)) {
+        Some(path) if path.exists() => match fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|source| parse_custom_palettes(&source))
+        {
+            Ok(palettes) => {
+                log::info!("Loaded {} palettes from {}", palettes.len(), path.display());
+                palettes
+            }
+            Err(err) => {
+                log::warn!("Can’t read the palettes in {}: {}", path.display(), err);
+                Vec::new()
+            }
+        },
+        _ => Vec::new(),
+    };
+
+    *CUSTOM_PALETTES
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = palettes;
+}
+
+pub fn custom_palettes() -> Vec<CustomPalette> {
+    CUSTOM_PALETTES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+// Read palettes as names mapped to lists of hex colors. Palettes with fewer than two colors, or
+// with colors that aren’t hex, are skipped.
+pub fn parse_custom_palettes(source: &str) -> Result<Vec<CustomPalette>, String> {
+    let table = source
+        .parse::<toml::Table>()
+        .map_err(|err| err.to_string())?;
+
+    Ok(table
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let colors = match value.as_array() {
+                Some(values) => values
+                    .iter()
+                    .map(|value| value.as_str().and_then(parse_hex))
+                    .collect::<Option<Vec<Color>>>(),
+                None => None,
+            };
+
+            match colors {
+                _ if name.trim().is_empty() => {
+                    log::warn!("Skipped a palette without a name");
+                    None
+                }
+                Some(colors) if colors.len() >= 2 => Some(CustomPalette { name, colors }),
+                Some(_) => {
+                    log::warn!("Skipped the palette {}: it needs at least two colors", name);
+                    None
+                }
+                None => {
+                    log::warn!(
+                        "Skipped the palette {}: colors should be a list like [\"#ff8800\"]",
+                        name
+                    );
+                    None
+                }
+            }
+        })
+        .collect())
+}
+
+// Parse a color like `#ff8800` or `ff8800`.
+fn parse_hex(hex: &str) -> Option<Color> {
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |index: usize| {
+        u8::from_str_radix(&hex[index..index + 2], 16)
+            .ok()
+            .map(|value| value as f32 / 255.0)
+    };
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// Refers to a custom palette by name. The name is kept even if palettes.toml doesn’t have the
+// palette, so that fixing the file brings the colors back instead of losing the choice.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct PaletteName(String);
+
+impl PaletteName {
+    pub fn new(name: impl Into<String>) -> Self {
+        PaletteName(name.into())
+    }
+
+    // The palette’s colors, if it was loaded from palettes.toml.
+    pub fn colors(&self) -> Option<Vec<Color>> {
+        CUSTOM_PALETTES
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .find(|palette| palette.name == self.0)
+            .map(|palette| palette.colors.clone())
+    }
+
+    // A name safe to use in a file name
+    pub fn file_name(&self) -> String {
+        self.0
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect()
+    }
+}
+
+impl fmt::Display for PaletteName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Swatches approximating the presets built into Flux. These are only used for previews.
+pub fn preset_colors(preset: ColorPreset) -> &'static [Color] {
+    match preset {
+        ColorPreset::Original => &ORIGINAL,
+        ColorPreset::Plasma => &PLASMA,
+        ColorPreset::Poolside => &POOLSIDE,
+        ColorPreset::Freedom => &FREEDOM,
+    }
+}
+
+const ORIGINAL: [Color; 4] = [
+    [0.235, 0.471, 0.824],
+    [0.353, 0.745, 0.863],
+    [0.941, 0.706, 0.353],
+    [0.863, 0.392, 0.314],
+];
+
+const PLASMA: [Color; 4] = [
+    [0.051, 0.031, 0.529],
+    [0.611, 0.090, 0.620],
+    [0.929, 0.475, 0.325],
+    [0.941, 0.976, 0.129],
+];
+
+const POOLSIDE: [Color; 4] = [
+    [0.180, 0.576, 0.682],
+    [0.545, 0.839, 0.855],
+    [0.980, 0.855, 0.765],
+    [0.949, 0.553, 0.627],
+];
+
+const FREEDOM: [Color; 2] = [[0.0, 0.357, 0.733], [1.0, 0.835, 0.0]];
+
+const SUNSET: [Color; 4] = [
+    [0.212, 0.114, 0.341],
+    [0.745, 0.227, 0.373],
+    [0.969, 0.525, 0.275],
+    [0.996, 0.835, 0.533],
+];
+
+const MONO: [Color; 3] = [[0.2, 0.2, 0.2], [0.6, 0.6, 0.6], [0.95, 0.95, 0.95]];
+
+const DEUTERANOPIA: [Color; 4] = [
+    [0.0, 0.165, 0.420],
+    [0.192, 0.498, 0.855],
+    [0.902, 0.584, 0.0],
+    [1.0, 0.953, 0.710],
+];
+
+const PROTANOPIA: [Color; 4] = [
+    [0.051, 0.098, 0.353],
+    [0.0, 0.447, 0.698],
+    [0.941, 0.894, 0.259],
+    [1.0, 1.0, 0.851],
+];
+
+const TRITANOPIA: [Color; 4] = [
+    [0.298, 0.0, 0.102],
+    [0.835, 0.196, 0.247],
+    [0.400, 0.847, 0.898],
+    [0.894, 1.0, 1.0],
+];
+
+const GRADIENT_IMAGE_SIZE: (u32, u32) = (256, 144);
+
+// Write a diagonal gradient through the colors to an image file.
+pub fn write_gradient_image(
+    name: &str,
+    colors: &[Color],
+    dir: &path::Path,
+) -> io::Result<path::PathBuf> {
+    let (width, height) = GRADIENT_IMAGE_SIZE;
+    let image = image::RgbImage::from_fn(width, height, |x, y| {
+        let t = (x + y) as f32 / (width + height - 2) as f32;
+        let [r, g, b] = sample(colors, t);
+        image::Rgb([to_u8(r), to_u8(g), to_u8(b)])
+    });
+
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("gradient-{}.png", name.to_lowercase()));
+    image
+        .save(&path)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+    Ok(path)
+}
+
+// Linearly interpolate between evenly spaced color stops.
+pub fn sample(colors: &[Color], t: f32) -> Color {
+    match colors {
+        [] => [0.0; 3],
+        [color] => *color,
+        _ => {
+            let position = t.clamp(0.0, 1.0) * (colors.len() - 1) as f32;
+            let index = (position.floor() as usize).min(colors.len() - 2);
+            let local_t = position - index as f32;
+            let (from, to) = (colors[index], colors[index + 1]);
+            [
+                from[0] + (to[0] - from[0]) * local_t,
+                from[1] + (to[1] - from[1]) * local_t,
+                from[2] + (to[2] - from[2]) * local_t,
+            ]
+        }
+    }
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_reads_palettes_of_hex_colors() {
+        let palettes = parse_custom_palettes(
+            r##"
+            Ocean = ["#000000", "ff8000"]
+            "Deep sea" = ["#000033", "#003366", "#0066ff"]
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            palettes,
+            vec![
+                CustomPalette {
+                    name: "Deep sea".to_string(),
+                    colors: vec![[0.0, 0.0, 0.2], [0.0, 0.2, 0.4], [0.0, 0.4, 1.0],],
+                },
+                CustomPalette {
+                    name: "Ocean".to_string(),
+                    colors: vec![[0.0, 0.0, 0.0], [1.0, 128.0 / 255.0, 0.0]],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_skips_invalid_palettes() {
+        let palettes = parse_custom_palettes(
+            r##"
+            One = ["#ffffff"]
+            Typo = ["#ffffff", "#gg0000"]
+            Short = ["#fff", "#000"]
+            NotAList = "#ffffff"
+            Good = ["#ffffff", "#000000"]
+            "##,
+        )
+        .unwrap();
+
+        let names: Vec<&str> = palettes
+            .iter()
+            .map(|palette| palette.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Good"]);
+        assert!(parse_custom_palettes("not toml = [").is_err());
+    }
+}
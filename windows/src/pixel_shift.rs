@@ -0,0 +1,42 @@
+use std::f64::consts::TAU;
+use std::time::Duration;
+
+// The furthest the picture drifts from its usual place, in pixels
+const MAX_OFFSET: f64 = 4.0;
+
+// How long the drift takes to go back and forth, across and up and down. The periods don’t share
+// a small multiple, so the path takes a long time to repeat and doesn’t settle on any one spot.
+const PERIOD_X: Duration = Duration::from_secs(5 * 60);
+const PERIOD_Y: Duration = Duration::from_secs(7 * 60);
+
+// How far to move the final picture `elapsed` into the run, in whole pixels. This is slow enough
+// that nobody sees it move, but nothing bright, like a watermark, stays on the same pixels.
+pub fn offset(elapsed: Duration) -> (i32, i32) {
+    let along = |period: Duration| {
+        let phase = TAU * elapsed.as_secs_f64() / period.as_secs_f64();
+        (phase.sin() * MAX_OFFSET).round() as i32
+    };
+    (along(PERIOD_X), along(PERIOD_Y))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_starts_in_place() {
+        assert_eq!(offset(Duration::ZERO), (0, 0));
+    }
+
+    #[test]
+    fn it_drifts_a_few_pixels_over_minutes() {
+        assert_eq!(offset(Duration::from_secs(1)), (0, 0));
+        assert_eq!(offset(PERIOD_X / 4).0, MAX_OFFSET as i32);
+        assert_eq!(offset(PERIOD_Y * 3 / 4).1, -(MAX_OFFSET as i32));
+
+        for minute in 0..120 {
+            let (x, y) = offset(Duration::from_secs(minute * 60));
+            assert!(x.abs() <= MAX_OFFSET as i32 && y.abs() <= MAX_OFFSET as i32);
+        }
+    }
+}
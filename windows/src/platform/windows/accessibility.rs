@@ -0,0 +1,28 @@
+use windows::Win32::Foundation::BOOL;
+use windows::Win32::UI::WindowsAndMessaging::{
+    SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+};
+
+// Read the "Show animations in Windows" setting. People turn this off when motion makes them
+// feel unwell.
+//
+// Assumes animations are enabled if the setting can’t be read.
+pub fn animations_enabled() -> bool {
+    let mut enabled = BOOL(1);
+
+    let result = unsafe {
+        SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            Some(&mut enabled as *mut BOOL as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    };
+
+    if let Err(err) = result {
+        log::warn!("Failed to read the animation setting: {}", err);
+        return true;
+    }
+
+    enabled.as_bool()
+}
@@ -0,0 +1,75 @@
+use windows::core::ComInterface;
+use windows::Win32::Graphics::Direct3D11::ID3D11Device;
+use windows::Win32::Graphics::Dxgi::{
+    CreateDXGIFactory1, IDXGIAdapter1, IDXGIDevice, IDXGIFactory1,
+};
+
+use crate::diagnostics::Adapter;
+
+// Look up the adapter a D3D11 device was created on.
+pub fn from_device(device: &ID3D11Device) -> windows::core::Result<Adapter> {
+    unsafe {
+        let dxgi_device: IDXGIDevice = device.cast()?;
+        let desc = dxgi_device.GetAdapter()?.GetDesc()?;
+
+        Ok(Adapter {
+            name: from_wide(&desc.Description),
+            vendor_id: desc.VendorId,
+            device_id: desc.DeviceId,
+            dedicated_video_memory: desc.DedicatedVideoMemory as u64,
+        })
+    }
+}
+
+// List the adapters available to DXGI. The first adapter is the one D3D picks by default.
+pub fn enumerate() -> windows::core::Result<Vec<Adapter>> {
+    let factory: IDXGIFactory1 = unsafe { CreateDXGIFactory1()? };
+
+    let mut adapters = Vec::new();
+    // EnumAdapters1 returns DXGI_ERROR_NOT_FOUND once we run out of adapters.
+    while let Ok(adapter) = unsafe { factory.EnumAdapters1(adapters.len() as u32) } {
+        let desc = unsafe { adapter.GetDesc1()? };
+        adapters.push(Adapter {
+            name: from_wide(&desc.Description),
+            vendor_id: desc.VendorId,
+            device_id: desc.DeviceId,
+            dedicated_video_memory: desc.DedicatedVideoMemory as u64,
+        });
+    }
+
+    Ok(adapters)
+}
+
+// Find the first adapter whose name or vendor matches `preference`.
+pub fn find(preference: &str) -> Option<IDXGIAdapter1> {
+    let factory: IDXGIFactory1 = unsafe { CreateDXGIFactory1().ok()? };
+
+    let mut index = 0;
+    while let Ok(adapter) = unsafe { factory.EnumAdapters1(index) } {
+        index += 1;
+        let Ok(desc) = (unsafe { adapter.GetDesc1() }) else {
+            continue;
+        };
+        let info = Adapter {
+            name: from_wide(&desc.Description),
+            vendor_id: desc.VendorId,
+            device_id: desc.DeviceId,
+            dedicated_video_memory: desc.DedicatedVideoMemory as u64,
+        };
+        if info.matches(preference) {
+            log::info!("Using the preferred adapter: {}", info);
+            return Some(adapter);
+        }
+    }
+
+    log::warn!(
+        "No adapter matches {}. Using the default adapter",
+        preference
+    );
+    None
+}
+
+fn from_wide(wide: &[u16]) -> String {
+    let len = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+    String::from_utf16_lossy(&wide[..len])
+}
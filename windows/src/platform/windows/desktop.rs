@@ -0,0 +1,94 @@
+use raw_window_handle::{RawWindowHandle, Win32WindowHandle};
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM, WPARAM};
+use windows::Win32::Graphics::Gdi::{
+    RedrawWindow, HRGN, RDW_ALLCHILDREN, RDW_ERASE, RDW_INVALIDATE, RDW_UPDATENOW,
+};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, FindWindowExW, FindWindowW, SendMessageTimeoutW, SMTO_NORMAL,
+};
+
+// An undocumented message that asks Program Manager to create a WorkerW window between the
+// wallpaper and the desktop icons.
+const SPAWN_WORKER_WINDOW: u32 = 0x052C;
+
+// Find the window behind the desktop icons, so that Flux can draw there instead of the wallpaper.
+pub fn find_worker_window() -> Option<RawWindowHandle> {
+    let worker = unsafe {
+        let progman = FindWindowW(w!("Progman"), PCWSTR::null());
+        if progman.0 == 0 {
+            log::error!("Can’t find the Program Manager window");
+            return None;
+        }
+
+        SendMessageTimeoutW(
+            progman,
+            SPAWN_WORKER_WINDOW,
+            WPARAM(0),
+            LPARAM(0),
+            SMTO_NORMAL,
+            1000,
+            None,
+        );
+
+        // The worker is the WorkerW that follows the window hosting the desktop icons.
+        let mut worker = HWND::default();
+        let _ = EnumWindows(
+            Some(find_worker_after_icons),
+            LPARAM(&mut worker as *mut HWND as isize),
+        );
+
+        // Newer versions of Windows put the worker inside Program Manager instead.
+        if worker.0 == 0 {
+            worker = FindWindowExW(progman, HWND::default(), w!("WorkerW"), PCWSTR::null());
+        }
+        worker
+    };
+
+    if worker.0 == 0 {
+        log::error!("Can’t find the window behind the desktop icons");
+        return None;
+    }
+
+    let mut handle = Win32WindowHandle::empty();
+    handle.hwnd = worker.0 as *mut _;
+    handle.hinstance = unsafe { GetModuleHandleW(None) }
+        .map(|module| module.0 as *mut _)
+        .unwrap_or(std::ptr::null_mut());
+
+    Some(RawWindowHandle::Win32(handle))
+}
+
+unsafe extern "system" fn find_worker_after_icons(hwnd: HWND, worker: LPARAM) -> BOOL {
+    let icons = FindWindowExW(
+        hwnd,
+        HWND::default(),
+        w!("SHELLDLL_DefView"),
+        PCWSTR::null(),
+    );
+    if icons.0 == 0 {
+        return true.into();
+    }
+
+    *(worker.0 as *mut HWND) = FindWindowExW(HWND::default(), hwnd, w!("WorkerW"), PCWSTR::null());
+    false.into()
+}
+
+// Ask the worker window to repaint, so that the wallpaper shows again once Flux stops drawing
+// over it.
+pub fn redraw(handle: &RawWindowHandle) {
+    let hwnd = match handle {
+        RawWindowHandle::Win32(handle) => HWND(handle.hwnd as _),
+        _ => return,
+    };
+
+    unsafe {
+        RedrawWindow(
+            hwnd,
+            None,
+            HRGN::default(),
+            RDW_INVALIDATE | RDW_ERASE | RDW_ALLCHILDREN | RDW_UPDATENOW,
+        );
+    }
+}
@@ -1,12 +1,21 @@
 // Specifying DPI awareness in the app manifest does not apply when running in a
 // preview window.
+//
+// Prefer per-monitor v2 awareness, which is available from Windows 10 1703. Without it, Windows
+// reports scaled window sizes on high-DPI displays and stretches the result.
 pub fn set_dpi_awareness() -> Result<(), String> {
     use windows::Win32::Foundation::E_INVALIDARG;
     use windows::Win32::UI::HiDpi::{
-        GetProcessDpiAwareness, SetProcessDpiAwareness, PROCESS_PER_MONITOR_DPI_AWARE,
+        GetProcessDpiAwareness, SetProcessDpiAwareness, SetProcessDpiAwarenessContext,
+        DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2, PROCESS_PER_MONITOR_DPI_AWARE,
         PROCESS_SYSTEM_DPI_AWARE,
     };
 
+    if unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) }.is_ok()
+    {
+        return Ok(());
+    }
+
     if let Err(err) = unsafe { SetProcessDpiAwareness(PROCESS_PER_MONITOR_DPI_AWARE) } {
         match err.code() {
             E_INVALIDARG => return Err("Can’t enable support for high-resolution screens.".to_string()),
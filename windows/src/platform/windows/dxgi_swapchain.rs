@@ -8,25 +8,39 @@ use glow as GL;
 use glow::HasContext;
 use raw_window_handle::RawWindowHandle;
 
-use windows::core::{Interface, PCSTR};
+use windows::core::{Interface, HRESULT, PCSTR};
 use windows::Win32::Foundation::{BOOL, HANDLE, HWND};
-use windows::Win32::Graphics::Direct3D::D3D_DRIVER_TYPE_HARDWARE;
+use windows::Win32::Graphics::Direct3D::{D3D_DRIVER_TYPE_HARDWARE, D3D_DRIVER_TYPE_UNKNOWN};
 use windows::Win32::Graphics::Direct3D11::{
     D3D11CreateDeviceAndSwapChain, ID3D11Device, ID3D11DeviceContext, ID3D11RenderTargetView,
-    ID3D11Texture2D, D3D11_CREATE_DEVICE_FLAG, D3D11_SDK_VERSION,
+    ID3D11Texture2D, D3D11_CREATE_DEVICE_FLAG, D3D11_SDK_VERSION, D3D11_TEXTURE2D_DESC,
 };
 use windows::Win32::Graphics::Dxgi::Common::{
-    DXGI_FORMAT_R8G8B8A8_UNORM, DXGI_MODE_DESC, DXGI_SAMPLE_DESC,
+    DXGI_FORMAT, DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_FORMAT_R10G10B10A2_UNORM,
+    DXGI_FORMAT_R16G16B16A16_FLOAT, DXGI_FORMAT_R8G8B8A8_UNORM, DXGI_FORMAT_UNKNOWN,
+    DXGI_MODE_DESC, DXGI_SAMPLE_DESC,
 };
 use windows::Win32::Graphics::Dxgi::{
-    IDXGISwapChain, DXGI_SWAP_CHAIN_DESC, DXGI_SWAP_EFFECT_DISCARD, DXGI_USAGE_RENDER_TARGET_OUTPUT,
+    IDXGISwapChain, DXGI_ERROR_DEVICE_REMOVED, DXGI_ERROR_DEVICE_RESET, DXGI_PRESENT_TEST,
+    DXGI_STATUS_OCCLUDED, DXGI_SWAP_CHAIN_DESC, DXGI_SWAP_EFFECT, DXGI_SWAP_EFFECT_DISCARD,
+    DXGI_SWAP_EFFECT_FLIP_DISCARD, DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL, DXGI_SWAP_EFFECT_SEQUENTIAL,
+    DXGI_USAGE_RENDER_TARGET_OUTPUT,
 };
 use windows::Win32::Graphics::Gdi::HDC;
 use windows::Win32::Graphics::OpenGL::{wglGetCurrentDC, wglGetProcAddress};
 
+use crate::config::InteropAttachment;
+use crate::diagnostics::{Adapter, Diagnostics};
+
 #[derive(Debug)]
 pub(crate) enum Problem {
     Unsupported,
+    // The window is covered, so the frame wasn’t rendered or presented
+    Occluded,
+    // The GPU was removed or reset, and the swapchain needs to be recreated
+    DeviceLost(String),
+    // The driver advertises the interop extension, but doesn’t provide these functions
+    MissingExtension(Vec<&'static str>),
     Failure(String),
 }
 
@@ -45,6 +59,11 @@ impl fmt::Display for Problem {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Problem::Unsupported => write!(f, "Unsupported"),
+            Problem::Occluded => write!(f, "The window is occluded"),
+            Problem::DeviceLost(s) => write!(f, "Lost the graphics device: {}", s),
+            Problem::MissingExtension(names) => {
+                write!(f, "The driver is missing {}", names.join(", "))
+            }
             Problem::Failure(s) => write!(f, "{}", s),
         }
     }
@@ -52,13 +71,319 @@ impl fmt::Display for Problem {
 
 #[allow(dead_code)]
 pub(crate) struct DXGIInterop {
-    device: ID3D11Device,
-    context: ID3D11DeviceContext,
+    // Fields are dropped in order, so the COM objects are listed in the reverse of the order
+    // they’re created in. The mode holds on to the back buffer, which belongs to the swapchain.
+    mode: InteropMode,
     swap_chain: IDXGISwapChain,
-    gl_handle_d3d: HANDLE,
+    context: ID3D11DeviceContext,
+    device: ID3D11Device,
+    fbo: GL::NativeFramebuffer,
+    adapter: Option<Adapter>,
+    // Whether the last present found the window covered
+    occluded: bool,
+    // How many vertical blanks to wait for before presenting, from 0 to 4
+    sync_interval: u32,
+}
+
+// How frames rendered by GL end up in the swapchain's back buffer.
+enum InteropMode {
+    // The back buffer is shared with GL through WGL_NV_DX_interop2.
+    Shared(SharedBackBuffer),
+    // GL renders to a texture, which is read back and copied into the back buffer on the CPU.
+    // This is slow, but works even if the interop extension is broken.
+    Copy(CopyBackBuffer),
+}
+
+struct SharedBackBuffer {
     dx_interop: WGLDXInteropExtensionFunctions,
+    gl_handle_d3d: HANDLE,
     color_handle_gl: HANDLE,
-    fbo: GL::NativeFramebuffer,
+    attachment: Attachment,
+    // Whether we know that frames rendered to the shared buffer end up on screen
+    verified: bool,
+}
+
+struct CopyBackBuffer {
+    texture: GL::NativeTexture,
+    back_buffer: Option<ID3D11Texture2D>,
+    upload_buffer: ID3D11Texture2D,
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+// The GL object the swapchain's back buffer is registered as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Attachment {
+    Renderbuffer(GL::NativeRenderbuffer),
+    Texture(GL::NativeTexture),
+}
+
+impl Attachment {
+    fn name(&self) -> GLuint {
+        match self {
+            Attachment::Renderbuffer(rbo) => rbo.0.into(),
+            Attachment::Texture(texture) => texture.0.into(),
+        }
+    }
+
+    fn target(&self) -> GLenum {
+        match self {
+            Attachment::Renderbuffer(_) => GL::RENDERBUFFER,
+            Attachment::Texture(_) => GL::TEXTURE_2D,
+        }
+    }
+}
+
+impl fmt::Display for Attachment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Attachment::Renderbuffer(_) => write!(f, "renderbuffer"),
+            Attachment::Texture(_) => write!(f, "texture"),
+        }
+    }
+}
+
+impl DXGIInterop {
+    // Whether the window was covered the last time we presented to it.
+    pub(crate) fn is_occluded(&self) -> bool {
+        self.occluded
+    }
+
+    // Wait for a vertical blank before presenting, or present straight away.
+    pub(crate) fn set_vsync(&mut self, vsync: bool) {
+        self.sync_interval = if vsync { 1 } else { 0 };
+    }
+
+    pub(crate) fn diagnostics(&self) -> Diagnostics {
+        let presentation = match &self.mode {
+            InteropMode::Shared(shared) => format!("DXGI interop ({})", shared.attachment),
+            InteropMode::Copy(_) => "DXGI with CPU copy fallback".to_string(),
+        };
+
+        // As the driver created the swapchain, which isn’t always what was asked for
+        let desc = unsafe { self.swap_chain.GetDesc() }
+            .map_err(|err| log::debug!("Failed to query the swapchain description: {}", err))
+            .ok();
+
+        Diagnostics {
+            adapter: self.adapter.clone(),
+            presentation,
+            swap_effect: desc.as_ref().map(|desc| swap_effect_name(desc.SwapEffect)),
+            buffers: desc.as_ref().map(|desc| {
+                format!(
+                    "{} × {}",
+                    desc.BufferCount,
+                    format_name(desc.BufferDesc.Format)
+                )
+            }),
+        }
+    }
+
+    // Resize the swapchain buffers to match the window.
+    //
+    // The back buffer can’t be resized while it’s registered with GL, so we unregister it,
+    // resize the buffers, and register the new back buffer with the same GL object.
+    pub(crate) unsafe fn resize(
+        &mut self,
+        gl: &glow::Context,
+        width: u32,
+        height: u32,
+    ) -> Result<(), Problem> {
+        // Release any references to the old back buffer
+        match &mut self.mode {
+            InteropMode::Shared(shared) => {
+                (shared.dx_interop.DXUnregisterObjectNV)(
+                    shared.gl_handle_d3d,
+                    shared.color_handle_gl,
+                );
+            }
+            InteropMode::Copy(copy) => copy.back_buffer = None,
+        }
+        self.context.OMSetRenderTargets(None, None);
+        self.context.Flush();
+
+        self.swap_chain
+            .ResizeBuffers(0, width, height, DXGI_FORMAT_UNKNOWN, 0)
+            .map_err(|err| format!("Failed to resize the DXGI swapchain: {}", err))?;
+
+        let color_buffer: ID3D11Texture2D = self
+            .swap_chain
+            .GetBuffer(0)
+            .map_err(|err| format!("Failed to fetch the resized back buffer: {}", err))?;
+
+        let registered = match &mut self.mode {
+            InteropMode::Shared(shared) => {
+                let color_handle_gl = (shared.dx_interop.DXRegisterObjectNV)(
+                    shared.gl_handle_d3d,
+                    color_buffer.as_raw(),
+                    shared.attachment.name(),
+                    shared.attachment.target(),
+                    WGL_ACCESS_READ_WRITE_DISCARD_NV,
+                );
+
+                match check_interop_handle(color_handle_gl, "DXRegisterObjectNV") {
+                    Ok(handle) => {
+                        shared.color_handle_gl = handle;
+                        Ok(())
+                    }
+                    Err(err) => Err(format!(
+                        "Failed to register the resized back buffer with GL: {}",
+                        err
+                    )),
+                }
+            }
+
+            InteropMode::Copy(copy) => {
+                gl.delete_texture(copy.texture);
+                *copy = CopyBackBuffer::new(gl, &self.device, color_buffer.clone(), self.fbo)?;
+                Ok(())
+            }
+        };
+
+        // The driver may refuse to share the new buffer even though it shared the old one. Switch
+        // to copying frames rather than failing outright.
+        if let Err(err) = registered {
+            log::warn!("{}. Falling back to copying frames on the CPU.", err);
+            let copy = CopyBackBuffer::new(gl, &self.device, color_buffer, self.fbo)?;
+            if let InteropMode::Shared(shared) = &self.mode {
+                (shared.dx_interop.DXCloseDeviceNV)(shared.gl_handle_d3d);
+                match shared.attachment {
+                    Attachment::Renderbuffer(rbo) => gl.delete_renderbuffer(rbo),
+                    Attachment::Texture(texture) => gl.delete_texture(texture),
+                }
+            }
+            self.mode = InteropMode::Copy(copy);
+        }
+
+        log::debug!("Resized DXGI swapchain to {}x{}", width, height);
+
+        Ok(())
+    }
+}
+
+// Release the interop handles and flush any pending work, so that the device can shut down
+// cleanly. The GL context should be current.
+impl Drop for DXGIInterop {
+    fn drop(&mut self) {
+        unsafe {
+            if let InteropMode::Shared(shared) = &self.mode {
+                (shared.dx_interop.DXUnregisterObjectNV)(
+                    shared.gl_handle_d3d,
+                    shared.color_handle_gl,
+                );
+                (shared.dx_interop.DXCloseDeviceNV)(shared.gl_handle_d3d);
+            }
+
+            // Releasing a swapchain in exclusive fullscreen can hang. It’s created windowed, but
+            // some drivers switch it to fullscreen on their own.
+            if let Err(err) = self.swap_chain.SetFullscreenState(false, None) {
+                log::debug!(
+                    "Failed to leave fullscreen before releasing the swapchain: {}",
+                    err
+                );
+            }
+
+            self.context.OMSetRenderTargets(None, None);
+            self.context.ClearState();
+            self.context.Flush();
+        }
+    }
+}
+
+impl CopyBackBuffer {
+    unsafe fn new(
+        gl: &glow::Context,
+        device: &ID3D11Device,
+        back_buffer: ID3D11Texture2D,
+        fbo: GL::NativeFramebuffer,
+    ) -> Result<Self, Problem> {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        back_buffer.GetDesc(&mut desc);
+
+        // A GPU-side copy of the back buffer that we can upload pixels to.
+        let mut upload_buffer: Option<ID3D11Texture2D> = None;
+        device
+            .CreateTexture2D(&desc, None, Some(&mut upload_buffer))
+            .map_err(|err| format!("Failed to create the upload texture: {}", err))?;
+        let upload_buffer = upload_buffer.ok_or("Failed to create the upload texture")?;
+
+        let texture = gl.create_texture()?;
+        gl.bind_texture(GL::TEXTURE_2D, Some(texture));
+        gl.tex_image_2d(
+            GL::TEXTURE_2D,
+            0,
+            GL::RGBA8 as i32,
+            desc.Width as i32,
+            desc.Height as i32,
+            0,
+            GL::RGBA,
+            GL::UNSIGNED_BYTE,
+            None,
+        );
+        gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
+        gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::LINEAR as i32);
+        gl.bind_texture(GL::TEXTURE_2D, None);
+
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(fbo));
+        gl.framebuffer_texture_2d(
+            GL::FRAMEBUFFER,
+            GL::COLOR_ATTACHMENT0,
+            GL::TEXTURE_2D,
+            Some(texture),
+            0,
+        );
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+        Ok(Self {
+            texture,
+            back_buffer: Some(back_buffer),
+            upload_buffer,
+            pixels: vec![0; desc.Width as usize * desc.Height as usize * 4],
+            width: desc.Width,
+            height: desc.Height,
+        })
+    }
+
+    // Read back the rendered frame and copy it into the back buffer.
+    unsafe fn copy(
+        &mut self,
+        gl: &glow::Context,
+        context: &ID3D11DeviceContext,
+        fbo: GL::NativeFramebuffer,
+    ) {
+        gl.bind_framebuffer(GL::READ_FRAMEBUFFER, Some(fbo));
+        gl.read_pixels(
+            0,
+            0,
+            self.width as i32,
+            self.height as i32,
+            GL::RGBA,
+            GL::UNSIGNED_BYTE,
+            GL::PixelPackData::Slice(&mut self.pixels),
+        );
+        gl.bind_framebuffer(GL::READ_FRAMEBUFFER, None);
+
+        // GL stores rows bottom to top, D3D top to bottom.
+        let row_size = self.width as usize * 4;
+        let rows = self.height as usize;
+        for y in 0..rows / 2 {
+            let (top, bottom) = self.pixels.split_at_mut((rows - 1 - y) * row_size);
+            top[y * row_size..(y + 1) * row_size].swap_with_slice(&mut bottom[..row_size]);
+        }
+
+        context.UpdateSubresource(
+            &self.upload_buffer,
+            0,
+            None,
+            self.pixels.as_ptr() as *const c_void,
+            row_size as u32,
+            0,
+        );
+        if let Some(back_buffer) = &self.back_buffer {
+            context.CopyResource(back_buffer, &self.upload_buffer);
+        }
+    }
 }
 
 type GLint = c_int;
@@ -88,27 +413,154 @@ pub(crate) struct WGLDXInteropExtensionFunctions {
     pub(crate) DXUnregisterObjectNV: unsafe extern "C" fn(hDevice: HANDLE, hObject: HANDLE) -> BOOL,
 }
 
-pub(crate) unsafe fn with_dxgi_swapchain<R>(
+// Look up the WGL_NV_DX_interop functions, failing with the names of any the driver doesn’t
+// provide rather than keeping null pointers around to call later.
+unsafe fn load_interop_functions() -> Result<WGLDXInteropExtensionFunctions, Problem> {
+    let mut missing = Vec::new();
+    let mut load = |name: &'static str| {
+        let function = wglGetProcAddress(PCSTR(name.as_ptr()));
+        if function.is_none() {
+            missing.push(name.trim_end_matches('\0'));
+        }
+        function
+    };
+
+    let close_device = load("wglDXCloseDeviceNV\0").map(|f| mem::transmute(f));
+    let lock_objects = load("wglDXLockObjectsNV\0").map(|f| mem::transmute(f));
+    let open_device = load("wglDXOpenDeviceNV\0").map(|f| mem::transmute(f));
+    let register_object = load("wglDXRegisterObjectNV\0").map(|f| mem::transmute(f));
+    let set_resource_share_handle =
+        load("wglDXSetResourceShareHandleNV\0").map(|f| mem::transmute(f));
+    let unlock_objects = load("wglDXUnlockObjectsNV\0").map(|f| mem::transmute(f));
+    let unregister_object = load("wglDXUnregisterObjectNV\0").map(|f| mem::transmute(f));
+
+    match (
+        close_device,
+        lock_objects,
+        open_device,
+        register_object,
+        set_resource_share_handle,
+        unlock_objects,
+        unregister_object,
+    ) {
+        (
+            Some(close_device),
+            Some(lock_objects),
+            Some(open_device),
+            Some(register_object),
+            Some(set_resource_share_handle),
+            Some(unlock_objects),
+            Some(unregister_object),
+        ) => Ok(WGLDXInteropExtensionFunctions {
+            DXCloseDeviceNV: close_device,
+            DXLockObjectsNV: lock_objects,
+            DXOpenDeviceNV: open_device,
+            DXRegisterObjectNV: register_object,
+            DXSetResourceShareHandleNV: set_resource_share_handle,
+            DXUnlockObjectsNV: unlock_objects,
+            DXUnregisterObjectNV: unregister_object,
+        }),
+        _ => Err(Problem::MissingExtension(missing)),
+    }
+}
+
+// Render a frame into the swapchain’s back buffer and present it.
+//
+// While the window is covered, like by another window or a locked session, frames are skipped and
+// this returns `Problem::Occluded` until a test present says the window is visible again.
+pub(crate) unsafe fn with_dxgi_swapchain(
     dxgi_interop: &mut DXGIInterop,
-    render: impl FnOnce(&GL::NativeFramebuffer) -> R,
-) -> R {
-    (dxgi_interop.dx_interop.DXLockObjectsNV)(
-        dxgi_interop.gl_handle_d3d,
-        1,
-        &mut dxgi_interop.color_handle_gl as *mut _,
-    );
+    gl: &glow::Context,
+    render: impl FnOnce(&GL::NativeFramebuffer) -> Result<(), Problem>,
+) -> Result<(), Problem> {
+    if dxgi_interop.occluded {
+        let hr = dxgi_interop.swap_chain.Present(0, DXGI_PRESENT_TEST);
+        if hr == DXGI_STATUS_OCCLUDED {
+            return Err(Problem::Occluded);
+        }
+        check_present(dxgi_interop, hr)?;
 
-    let result = render(&dxgi_interop.fbo);
+        dxgi_interop.occluded = false;
+    }
 
-    (dxgi_interop.dx_interop.DXUnlockObjectsNV)(
-        dxgi_interop.gl_handle_d3d,
-        1,
-        &mut dxgi_interop.color_handle_gl as *mut _,
-    );
+    if let InteropMode::Shared(shared) = &mut dxgi_interop.mode {
+        let locked = (shared.dx_interop.DXLockObjectsNV)(
+            shared.gl_handle_d3d,
+            1,
+            &mut shared.color_handle_gl as *mut _,
+        );
+        if !locked.as_bool() {
+            return Err(format!(
+                "Failed to lock the shared back buffer. OS Error: {:?}",
+                std::io::Error::last_os_error()
+            )
+            .into());
+        }
+    }
+
+    let rendered = render(&dxgi_interop.fbo);
 
-    let _ = dxgi_interop.swap_chain.Present(1, 0);
+    match &mut dxgi_interop.mode {
+        InteropMode::Shared(shared) => {
+            if !shared.verified && rendered.is_ok() {
+                let desc = dxgi_interop.swap_chain.GetDesc().unwrap_or_default();
+                verify_first_frame(
+                    gl,
+                    dxgi_interop.fbo,
+                    desc.BufferDesc.Width,
+                    desc.BufferDesc.Height,
+                );
+                shared.verified = true;
+            }
 
-    result
+            let unlocked = (shared.dx_interop.DXUnlockObjectsNV)(
+                shared.gl_handle_d3d,
+                1,
+                &mut shared.color_handle_gl as *mut _,
+            );
+            if !unlocked.as_bool() {
+                return Err(format!(
+                    "Failed to unlock the shared back buffer. OS Error: {:?}",
+                    std::io::Error::last_os_error()
+                )
+                .into());
+            }
+        }
+        InteropMode::Copy(copy) => {
+            if rendered.is_ok() {
+                copy.copy(gl, &dxgi_interop.context, dxgi_interop.fbo)
+            }
+        }
+    }
+    rendered?;
+
+    let hr = dxgi_interop
+        .swap_chain
+        .Present(dxgi_interop.sync_interval, 0);
+    if hr == DXGI_STATUS_OCCLUDED {
+        dxgi_interop.occluded = true;
+        return Err(Problem::Occluded);
+    }
+
+    check_present(dxgi_interop, hr)
+}
+
+// Turn a failed present into a problem, explaining why the device was lost if that’s what
+// happened.
+unsafe fn check_present(dxgi_interop: &DXGIInterop, hr: HRESULT) -> Result<(), Problem> {
+    if hr == DXGI_ERROR_DEVICE_REMOVED || hr == DXGI_ERROR_DEVICE_RESET {
+        // The device knows more about why it went away than the present does.
+        let reason = match dxgi_interop.device.GetDeviceRemovedReason() {
+            Ok(()) => hr,
+            Err(err) => err.code(),
+        };
+        return Err(Problem::DeviceLost(
+            windows::core::Error::from(reason).to_string(),
+        ));
+    }
+
+    hr.ok()
+        .map_err(|err| format!("Failed to present the frame: {}", err).into())
 }
 
 // Detect Intel GPUs.
@@ -125,6 +577,11 @@ pub(crate) fn is_intel_gpu(gl: &glow::Context) -> bool {
 pub(crate) fn create_dxgi_swapchain(
     raw_window_handle: &RawWindowHandle,
     gl: &glow::Context,
+    // The back buffer is cleared to this color until the first frame is rendered
+    clear_color: [f32; 4],
+    // The name or vendor of the adapter to create the device on
+    adapter: Option<&str>,
+    interop_attachment: InteropAttachment,
 ) -> Result<DXGIInterop, Problem> {
     if is_intel_gpu(gl) {
         log::debug!("Intel GPU detected. Disabling DXGI swapchain");
@@ -138,36 +595,55 @@ pub(crate) fn create_dxgi_swapchain(
 
     let hwnd = HWND(win32_handle.hwnd as _);
 
+    // The WGL extensions are looked up on the current context, so check that there is one before
+    // creating any D3D objects.
+    let dc = unsafe { wglGetCurrentDC() };
+    if dc.is_invalid() {
+        return Err("No current GL context to share the DXGI swapchain with".into());
+    }
+
+    // D3D requires the driver type to be unknown when an adapter is given.
+    let adapter = adapter.and_then(super::adapter::find);
+    let driver_type = if adapter.is_some() {
+        D3D_DRIVER_TYPE_UNKNOWN
+    } else {
+        D3D_DRIVER_TYPE_HARDWARE
+    };
+
     let mut p_device: Option<ID3D11Device> = None;
     let mut p_context: Option<ID3D11DeviceContext> = None;
     let mut p_swap_chain: Option<IDXGISwapChain> = None;
 
+    let requested_desc = DXGI_SWAP_CHAIN_DESC {
+        BufferDesc: DXGI_MODE_DESC {
+            Format: DXGI_FORMAT_R8G8B8A8_UNORM,
+            ..Default::default()
+        },
+        BufferUsage: DXGI_USAGE_RENDER_TARGET_OUTPUT,
+        BufferCount: 2,
+        OutputWindow: hwnd,
+        // Windowed swapchains can’t be rotated. On a rotated display, the compositor turns the
+        // window upright instead, so the window just has to match the desktop layout.
+        Windowed: true.into(),
+        // FLIP modes don't work on NVIDIA cards.
+        SwapEffect: DXGI_SWAP_EFFECT_DISCARD,
+        SampleDesc: DXGI_SAMPLE_DESC {
+            // Disable MSAA (also unsupported with the 'flip' model)
+            Count: 1,
+            Quality: 0,
+        },
+        ..Default::default()
+    };
+
     unsafe {
         D3D11CreateDeviceAndSwapChain(
-            None,                        // Adapter
-            D3D_DRIVER_TYPE_HARDWARE,    // Driver type
+            adapter.as_ref(),            // Adapter
+            driver_type,                 // Driver type
             None,                        // Software
             D3D11_CREATE_DEVICE_FLAG(0), // Flags (do not set D3D11_CREATE_DEVICE_SINGLETHREADED)
             None,                        // Feature levels
             D3D11_SDK_VERSION,           // SDK version
-            Some(&DXGI_SWAP_CHAIN_DESC {
-                BufferDesc: DXGI_MODE_DESC {
-                    Format: DXGI_FORMAT_R8G8B8A8_UNORM,
-                    ..Default::default()
-                },
-                BufferUsage: DXGI_USAGE_RENDER_TARGET_OUTPUT,
-                BufferCount: 2,
-                OutputWindow: hwnd,
-                Windowed: true.into(),
-                // FLIP modes don't work on NVIDIA cards.
-                SwapEffect: DXGI_SWAP_EFFECT_DISCARD,
-                SampleDesc: DXGI_SAMPLE_DESC {
-                    // Disable MSAA (also unsupported with the 'flip' model)
-                    Count: 1,
-                    Quality: 0,
-                },
-                ..Default::default()
-            }),
+            Some(&requested_desc),
             Some(&mut p_swap_chain),
             Some(&mut p_device),
             None,
@@ -176,26 +652,34 @@ pub(crate) fn create_dxgi_swapchain(
         .map_err(|_| "Failed to create DXGI device and swapchain")?;
     }
 
-    let swap_chain = p_swap_chain.expect("failed to created swapchain");
-    let context = p_context.expect("failed to create immediate context");
-    let device = p_device.expect("failed to create device");
+    let (swap_chain, context, device) = match (p_swap_chain, p_context, p_device) {
+        (Some(swap_chain), Some(context), Some(device)) => (swap_chain, context, device),
+        _ => return Err("D3D created the device without a swapchain or context".into()),
+    };
 
     log::debug!("Created device, context, and swapchain");
+    log_swap_chain_desc(&swap_chain, &requested_desc);
+
+    let adapter = super::adapter::from_device(&device)
+        .map_err(|err| log::warn!("Failed to query the DXGI adapter: {}", err))
+        .ok();
+    if let Some(adapter) = &adapter {
+        log::debug!("DXGI adapter: {}", adapter);
+    }
 
     log::debug!("Fetching WGL extensions");
 
     unsafe {
-        let dc = wglGetCurrentDC();
         let get_extensions_string_arb: Option<unsafe extern "C" fn(hdc: HDC) -> *const c_char> =
             mem::transmute(wglGetProcAddress(PCSTR(
                 &b"wglGetExtensionsStringARB\0"[0] as *const u8,
             )));
 
-        let extensions = match get_extensions_string_arb {
-            Some(wglGetExtensionsStringARB) => {
-                CStr::from_ptr(wglGetExtensionsStringARB(dc)).to_string_lossy()
+        let extensions = match get_extensions_string_arb.map(|f| f(dc)) {
+            Some(extensions) if !extensions.is_null() => {
+                CStr::from_ptr(extensions).to_string_lossy()
             }
-            None => Cow::Borrowed(""),
+            _ => Cow::Borrowed(""),
         };
 
         log::debug!("Supported extensions: {}", extensions);
@@ -206,145 +690,467 @@ pub(crate) fn create_dxgi_swapchain(
         }
     }
 
-    let dx_interop = unsafe {
-        WGLDXInteropExtensionFunctions {
-            DXCloseDeviceNV: mem::transmute(wglGetProcAddress(PCSTR(
-                &b"wglDXCloseDeviceNV\0"[0] as *const u8,
-            ))),
-            DXLockObjectsNV: mem::transmute(wglGetProcAddress(PCSTR(
-                &b"wglDXLockObjectsNV\0"[0] as *const u8,
-            ))),
-            DXOpenDeviceNV: mem::transmute(wglGetProcAddress(PCSTR(
-                &b"wglDXOpenDeviceNV\0"[0] as *const u8,
-            ))),
-            DXRegisterObjectNV: mem::transmute(wglGetProcAddress(PCSTR(
-                &b"wglDXRegisterObjectNV\0"[0] as *const u8,
-            ))),
-            DXSetResourceShareHandleNV: mem::transmute(wglGetProcAddress(PCSTR(
-                &b"wglDXSetResourceShareHandleNV\0"[0] as *const u8,
-            ))),
-            DXUnlockObjectsNV: mem::transmute(wglGetProcAddress(PCSTR(
-                &b"wglDXUnlockObjectsNV\0"[0] as *const u8,
-            ))),
-            DXUnregisterObjectNV: mem::transmute(wglGetProcAddress(PCSTR(
-                &b"wglDXUnregisterObjectNV\0"[0] as *const u8,
-            ))),
-        }
-    };
+    let dx_interop = unsafe { load_interop_functions()? };
     log::debug!("Fetched interop extension functions");
 
     unsafe {
         // Fetch the swapchain buffer
-        let color_buffer: ID3D11Texture2D = swap_chain.GetBuffer(0).unwrap();
+        let color_buffer: ID3D11Texture2D = swap_chain
+            .GetBuffer(0)
+            .map_err(|err| format!("Failed to fetch the back buffer: {}", err))?;
         let mut color_buffer_view: Option<ID3D11RenderTargetView> = None;
 
         // Create view
         device
             .CreateRenderTargetView(&color_buffer, None, Some(&mut color_buffer_view))
-            .unwrap();
+            .map_err(|err| format!("Failed to create the render target view: {}", err))?;
+        let color_buffer_view = color_buffer_view
+            .ok_or("The driver created an empty render target view for the back buffer")?;
 
         // Attach the back buffer to the render target for the device
-        context.OMSetRenderTargets(Some(&[color_buffer_view.clone()]), None);
+        context.OMSetRenderTargets(Some(&[Some(color_buffer_view.clone())]), None);
 
         // Clear the back buffer
-        let clear_color: [f32; 4] = [0.0, 0.0, 0.0, 0.0];
-        context.ClearRenderTargetView(color_buffer_view.as_ref().unwrap(), &clear_color);
+        context.ClearRenderTargetView(&color_buffer_view, &clear_color);
         log::debug!("Cleared render target view");
 
-        // Register the D3D11 device with GL
-        let gl_handle_d3d = (dx_interop.DXOpenDeviceNV)(device.as_raw());
-        if gl_handle_d3d.is_invalid() {
-            let msg = std::io::Error::last_os_error();
-            return Err(format!(
-                "Failed to open the GL DX interop device. OS Error: {:?}",
-                msg
-            )
-            .into());
+        // Present the cleared buffer, so that the window shows the background color until the
+        // first frame is ready.
+        if let Err(err) = swap_chain.Present(0, 0).ok() {
+            log::warn!("Failed to present the initial frame: {}", err);
         }
 
-        log::debug!("Opened GL DX interop device");
+        let fbo = gl
+            .create_framebuffer()
+            .map_err(|err| format!("Failed to create a framebuffer: {}", err))?;
+
+        let is_amd = adapter
+            .as_ref()
+            .is_some_and(|adapter| adapter.vendor() == "AMD");
+        let prefer_texture = match interop_attachment {
+            InteropAttachment::Auto => is_amd,
+            InteropAttachment::Renderbuffer => false,
+            InteropAttachment::Texture => true,
+        };
+
+        let shared = share_back_buffer(gl, &device, &color_buffer, fbo, dx_interop, prefer_texture);
+        let mode = match shared {
+            Ok(shared) => InteropMode::Shared(shared),
+            Err(err) => {
+                log::warn!(
+                    "Failed to share the DXGI back buffer with GL: {}. Falling back to copying frames on the CPU.",
+                    err
+                );
+                let copy = CopyBackBuffer::new(gl, &device, color_buffer, fbo)?;
 
-        let fbo = gl.create_framebuffer().unwrap();
-        let rbo = gl.create_renderbuffer().unwrap();
+                gl.bind_framebuffer(GL::FRAMEBUFFER, Some(fbo));
+                let status = check_framebuffer_status(gl);
+                gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+                status?;
 
-        let mut color_handle_gl = (dx_interop.DXRegisterObjectNV)(
-            gl_handle_d3d,
-            color_buffer.as_raw(),
-            rbo.0.into(),
-            GL::RENDERBUFFER,
-            WGL_ACCESS_READ_WRITE_DISCARD_NV,
+                log::debug!("Copying frames to the DXGI swapchain on the CPU");
+                InteropMode::Copy(copy)
+            }
+        };
+
+        Ok(DXGIInterop {
+            device,
+            context,
+            swap_chain,
+            fbo,
+            adapter,
+            mode,
+            occluded: false,
+            sync_interval: 1,
+        })
+    }
+}
+
+fn swap_effect_name(swap_effect: DXGI_SWAP_EFFECT) -> String {
+    match swap_effect {
+        DXGI_SWAP_EFFECT_DISCARD => "DXGI_SWAP_EFFECT_DISCARD".to_string(),
+        DXGI_SWAP_EFFECT_SEQUENTIAL => "DXGI_SWAP_EFFECT_SEQUENTIAL".to_string(),
+        DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL => "DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL".to_string(),
+        DXGI_SWAP_EFFECT_FLIP_DISCARD => "DXGI_SWAP_EFFECT_FLIP_DISCARD".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+fn format_name(format: DXGI_FORMAT) -> String {
+    match format {
+        DXGI_FORMAT_R8G8B8A8_UNORM => "DXGI_FORMAT_R8G8B8A8_UNORM".to_string(),
+        DXGI_FORMAT_B8G8R8A8_UNORM => "DXGI_FORMAT_B8G8R8A8_UNORM".to_string(),
+        DXGI_FORMAT_R10G10B10A2_UNORM => "DXGI_FORMAT_R10G10B10A2_UNORM".to_string(),
+        DXGI_FORMAT_R16G16B16A16_FLOAT => "DXGI_FORMAT_R16G16B16A16_FLOAT".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+// Log the swapchain as the driver actually created it. Drivers sometimes quietly change what was
+// asked for, which can explain odd behavior in bug reports.
+fn log_swap_chain_desc(swap_chain: &IDXGISwapChain, requested: &DXGI_SWAP_CHAIN_DESC) {
+    let desc = match unsafe { swap_chain.GetDesc() } {
+        Ok(desc) => desc,
+        Err(err) => {
+            log::debug!("Failed to query the swapchain description: {}", err);
+            return;
+        }
+    };
+
+    log::debug!("Swapchain description: {:?}", desc);
+
+    let mut changes = Vec::new();
+    if desc.BufferDesc.Format != requested.BufferDesc.Format {
+        changes.push(format!(
+            "format {:?} instead of {:?}",
+            desc.BufferDesc.Format, requested.BufferDesc.Format
+        ));
+    }
+    if desc.BufferCount != requested.BufferCount {
+        changes.push(format!(
+            "{} buffers instead of {}",
+            desc.BufferCount, requested.BufferCount
+        ));
+    }
+    if desc.SwapEffect != requested.SwapEffect {
+        changes.push(format!(
+            "swap effect {:?} instead of {:?}",
+            desc.SwapEffect, requested.SwapEffect
+        ));
+    }
+    if desc.Windowed != requested.Windowed {
+        changes.push(format!("windowed set to {}", desc.Windowed.as_bool()));
+    }
+    if desc.SampleDesc.Count != requested.SampleDesc.Count {
+        changes.push(format!(
+            "{} samples instead of {}",
+            desc.SampleDesc.Count, requested.SampleDesc.Count
+        ));
+    }
+
+    if !changes.is_empty() {
+        log::info!(
+            "The driver changed the requested swapchain: {}",
+            changes.join(", ")
         );
+    }
+}
 
-        if color_handle_gl.is_invalid() {
-            log::warn!("Failed to register a renderbuffer with DXGI. Falling back to a texture.");
+// Register the back buffer with GL and attach it to the framebuffer.
+//
+// Any resources created here are cleaned up on failure, so that the caller can try the copy
+// fallback instead.
+unsafe fn share_back_buffer(
+    gl: &glow::Context,
+    device: &ID3D11Device,
+    color_buffer: &ID3D11Texture2D,
+    fbo: GL::NativeFramebuffer,
+    dx_interop: WGLDXInteropExtensionFunctions,
+    // Skip the renderbuffer attempt, like on AMD cards that can’t share them
+    prefer_texture: bool,
+) -> Result<SharedBackBuffer, Problem> {
+    // Register the D3D11 device with GL
+    let gl_handle_d3d = check_interop_handle(
+        (dx_interop.DXOpenDeviceNV)(device.as_raw()),
+        "DXOpenDeviceNV",
+    )
+    .map_err(|err| format!("Failed to open the GL DX interop device: {}", err))?;
 
-            gl.delete_renderbuffer(rbo);
-            let texture = gl.create_texture().unwrap();
+    log::debug!("Opened GL DX interop device");
 
-            // According to my testing, AMD graphics cards don't support sharing renderbuffers.
-            color_handle_gl = (dx_interop.DXRegisterObjectNV)(
-                gl_handle_d3d,
-                color_buffer.as_raw(),
-                texture.0.into(),
-                GL::TEXTURE_2D,
-                WGL_ACCESS_READ_WRITE_DISCARD_NV,
-            );
+    let attach = |kind| attach_back_buffer(gl, &dx_interop, gl_handle_d3d, color_buffer, fbo, kind);
 
-            if color_handle_gl.is_invalid() {
-                let msg = std::io::Error::last_os_error();
-                return Err(
-                    format!("Failed to register texture with DXGI. OS Error: {:?}", msg).into(),
+    // Prefer a renderbuffer. According to my testing, AMD graphics cards don't support sharing
+    // renderbuffers, and NVIDIA reports them as unsupported framebuffer attachments, so try a
+    // texture in both cases.
+    //
+    // The texture goes first if we already know the renderbuffer won’t work. The renderbuffer is
+    // still tried as a fallback, in case the hardware was misidentified.
+    let result = if prefer_texture {
+        attach(AttachmentKind::Texture).or_else(|err| {
+            log::warn!("{}. Falling back to a renderbuffer.", err);
+            attach(AttachmentKind::Renderbuffer)
+        })
+    } else {
+        match attach(AttachmentKind::Renderbuffer) {
+            Ok(attached) if attached.status == FramebufferStatus::Complete => Ok(attached),
+            Ok(attached) => {
+                log::debug!(
+                    "The shared renderbuffer is an unsupported attachment. Trying a texture."
                 );
+                detach_back_buffer(gl, &dx_interop, gl_handle_d3d, attached);
+                attach(AttachmentKind::Texture).or_else(|err| {
+                    log::debug!("{}. Going back to the renderbuffer.", err);
+                    attach(AttachmentKind::Renderbuffer)
+                })
+            }
+            Err(err) => {
+                log::warn!("{}. Falling back to a texture.", err);
+                attach(AttachmentKind::Texture)
             }
+        }
+    };
 
-            log::debug!("Registered DXGI swapchain as GL texture");
+    let attached = match result {
+        Ok(attached) => attached,
+        Err(err) => {
+            (dx_interop.DXCloseDeviceNV)(gl_handle_d3d);
+            return Err(err);
+        }
+    };
 
-            // Bind the texture to the framebuffer
-            gl.bind_framebuffer(GL::FRAMEBUFFER, Some(fbo));
-            gl.framebuffer_texture_2d(
-                GL::FRAMEBUFFER,
-                GL::COLOR_ATTACHMENT0,
-                GL::TEXTURE_2D,
-                Some(texture),
-                0,
-            );
-        } else {
-            log::debug!("Registered DXGI swapchain as GL renderbuffer");
-
-            gl.bind_framebuffer(GL::FRAMEBUFFER, Some(fbo));
-            gl.framebuffer_renderbuffer(
-                GL::FRAMEBUFFER,
-                GL::COLOR_ATTACHMENT0,
-                GL::RENDERBUFFER,
-                Some(rbo),
+    log::info!("Sharing the DXGI back buffer as a {}", attached.attachment);
+
+    // Some drivers report the framebuffer as unsupported, but render to it just fine. Check the
+    // first frame to make sure.
+    let verified = attached.status == FramebufferStatus::Complete;
+    if !verified {
+        log::debug!(
+            "Using the shared {} as an unsupported attachment",
+            attached.attachment
+        );
+    }
+
+    Ok(SharedBackBuffer {
+        dx_interop,
+        gl_handle_d3d,
+        color_handle_gl: attached.color_handle_gl,
+        attachment: attached.attachment,
+        verified,
+    })
+}
+
+#[derive(Clone, Copy)]
+enum AttachmentKind {
+    Renderbuffer,
+    Texture,
+}
+
+struct AttachedBackBuffer {
+    attachment: Attachment,
+    color_handle_gl: HANDLE,
+    status: FramebufferStatus,
+}
+
+// Register the back buffer as a GL object and attach it to the framebuffer.
+unsafe fn attach_back_buffer(
+    gl: &glow::Context,
+    dx_interop: &WGLDXInteropExtensionFunctions,
+    gl_handle_d3d: HANDLE,
+    color_buffer: &ID3D11Texture2D,
+    fbo: GL::NativeFramebuffer,
+    kind: AttachmentKind,
+) -> Result<AttachedBackBuffer, Problem> {
+    let attachment = match kind {
+        AttachmentKind::Renderbuffer => Attachment::Renderbuffer(gl.create_renderbuffer()?),
+        AttachmentKind::Texture => Attachment::Texture(gl.create_texture()?),
+    };
+    let delete_object = || match attachment {
+        Attachment::Renderbuffer(rbo) => gl.delete_renderbuffer(rbo),
+        Attachment::Texture(texture) => gl.delete_texture(texture),
+    };
+
+    let color_handle_gl = (dx_interop.DXRegisterObjectNV)(
+        gl_handle_d3d,
+        color_buffer.as_raw(),
+        attachment.name(),
+        attachment.target(),
+        WGL_ACCESS_READ_WRITE_DISCARD_NV,
+    );
+    let mut color_handle_gl = match check_interop_handle(color_handle_gl, "DXRegisterObjectNV") {
+        Ok(handle) => handle,
+        Err(err) => {
+            delete_object();
+            return Err(format!("Failed to register a {} with DXGI: {}", attachment, err).into());
+        }
+    };
+
+    log::debug!("Registered DXGI swapchain as GL {}", attachment);
+
+    gl.bind_framebuffer(GL::FRAMEBUFFER, Some(fbo));
+    match attachment {
+        Attachment::Renderbuffer(rbo) => gl.framebuffer_renderbuffer(
+            GL::FRAMEBUFFER,
+            GL::COLOR_ATTACHMENT0,
+            GL::RENDERBUFFER,
+            Some(rbo),
+        ),
+        Attachment::Texture(texture) => gl.framebuffer_texture_2d(
+            GL::FRAMEBUFFER,
+            GL::COLOR_ATTACHMENT0,
+            GL::TEXTURE_2D,
+            Some(texture),
+            0,
+        ),
+    }
+
+    // The shared storage is only available to GL while it's locked. Checking the framebuffer
+    // without the lock is one reason drivers report it as unsupported.
+    (dx_interop.DXLockObjectsNV)(gl_handle_d3d, 1, &mut color_handle_gl as *mut _);
+    let status = check_framebuffer_status(gl);
+    (dx_interop.DXUnlockObjectsNV)(gl_handle_d3d, 1, &mut color_handle_gl as *mut _);
+    gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+    match status {
+        Ok(status) => Ok(AttachedBackBuffer {
+            attachment,
+            color_handle_gl,
+            status,
+        }),
+        Err(err) => {
+            (dx_interop.DXUnregisterObjectNV)(gl_handle_d3d, color_handle_gl);
+            delete_object();
+            Err(err)
+        }
+    }
+}
+
+// Check a handle returned by the interop extension. Drivers disagree on how they report a failure:
+// some return null, others INVALID_HANDLE_VALUE, so check for both explicitly instead of trusting
+// `is_invalid`.
+//
+// Call this straight after the extension function, before anything else changes the last error.
+fn check_interop_handle(handle: HANDLE, function: &str) -> Result<HANDLE, String> {
+    let last_error = std::io::Error::last_os_error();
+    log::debug!("{} returned handle {:#x}", function, handle.0);
+
+    if handle.0 == 0 || handle.0 == -1 || handle.is_invalid() {
+        return Err(format!(
+            "{} returned an invalid handle ({:#x}). OS Error: {:?}",
+            function, handle.0, last_error
+        ));
+    }
+
+    Ok(handle)
+}
+
+unsafe fn detach_back_buffer(
+    gl: &glow::Context,
+    dx_interop: &WGLDXInteropExtensionFunctions,
+    gl_handle_d3d: HANDLE,
+    attached: AttachedBackBuffer,
+) {
+    (dx_interop.DXUnregisterObjectNV)(gl_handle_d3d, attached.color_handle_gl);
+    match attached.attachment {
+        Attachment::Renderbuffer(rbo) => gl.delete_renderbuffer(rbo),
+        Attachment::Texture(texture) => gl.delete_texture(texture),
+    }
+}
+
+// Check that the first frame rendered to an unsupported framebuffer actually made it into the
+// back buffer, by looking for any variation across a handful of pixels. Flux never renders a
+// completely flat frame.
+unsafe fn verify_first_frame(
+    gl: &glow::Context,
+    fbo: GL::NativeFramebuffer,
+    width: u32,
+    height: u32,
+) {
+    const SAMPLES: u32 = 4;
+
+    gl.bind_framebuffer(GL::READ_FRAMEBUFFER, Some(fbo));
+    let mut pixels = Vec::with_capacity((SAMPLES * SAMPLES) as usize);
+    for row in 1..=SAMPLES {
+        for column in 1..=SAMPLES {
+            let mut pixel = [0u8; 4];
+            gl.read_pixels(
+                (width * column / (SAMPLES + 1)) as i32,
+                (height * row / (SAMPLES + 1)) as i32,
+                1,
+                1,
+                GL::RGBA,
+                GL::UNSIGNED_BYTE,
+                GL::PixelPackData::Slice(&mut pixel),
             );
+            pixels.push(pixel);
         }
+    }
+    gl.bind_framebuffer(GL::READ_FRAMEBUFFER, None);
 
-        match gl.check_framebuffer_status(GL::FRAMEBUFFER) {
-            GL::FRAMEBUFFER_COMPLETE => {
-                log::debug!("GL Framebuffer complete");
-            }
-            // Nvidia complains that the buffer is unsupported, but it still works. *shrug*
-            GL::FRAMEBUFFER_UNSUPPORTED => log::debug!("GL Framebuffer unsupported"),
-            GL::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => {
-                return Err("GL Framebuffer incomplete attachment".into())
-            }
-            GL::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => {
-                return Err("GL Framebuffer missing attachment".into())
-            }
-            other => return Err(format!("DXGI Framebuffer: {:#x}", other).into()),
+    if pixels.windows(2).all(|pair| pair[0] == pair[1]) {
+        log::warn!(
+            "The first frame rendered to the shared back buffer is blank ({:?}). The unsupported framebuffer may not work on this GPU.",
+            pixels[0]
+        );
+    } else {
+        log::debug!("Verified the first frame rendered to the shared back buffer");
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FramebufferStatus {
+    Complete,
+    // NVIDIA reports shared renderbuffers as unsupported, but still renders to them.
+    Unsupported,
+}
+
+// Check the status of the currently bound framebuffer.
+unsafe fn check_framebuffer_status(gl: &glow::Context) -> Result<FramebufferStatus, Problem> {
+    match gl.check_framebuffer_status(GL::FRAMEBUFFER) {
+        GL::FRAMEBUFFER_COMPLETE => {
+            log::debug!("GL Framebuffer complete");
+            Ok(FramebufferStatus::Complete)
+        }
+        GL::FRAMEBUFFER_UNSUPPORTED => {
+            log::debug!("GL Framebuffer unsupported");
+            Ok(FramebufferStatus::Unsupported)
         }
+        GL::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => Err("GL Framebuffer incomplete attachment".into()),
+        GL::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => {
+            Err("GL Framebuffer missing attachment".into())
+        }
+        other => Err(format!("DXGI Framebuffer: {:#x}", other).into()),
+    }
+}
 
-        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+// These tests need a real GPU and a desktop session, so they’re hidden behind the `gpu-tests`
+// feature. Run them locally with:
+//
+//     cargo test --features gpu-tests -- --test-threads=1
+#[cfg(all(test, feature = "gpu-tests"))]
+mod tests {
+    use super::*;
+    use crate::gl_context;
+    use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
 
-        Ok(DXGIInterop {
-            device,
-            context,
-            swap_chain,
-            gl_handle_d3d,
-            dx_interop,
-            color_handle_gl,
-            fbo,
-        })
+    #[test]
+    fn it_creates_a_swapchain_or_reports_unsupported() {
+        let sdl_context = sdl2::init().unwrap();
+        let video_subsystem = sdl_context.video().unwrap();
+        let window = video_subsystem
+            .window("Flux test", 320, 240)
+            .hidden()
+            .build()
+            .unwrap();
+
+        let gl_context = gl_context::new_gl_context(
+            window.raw_display_handle(),
+            window.size().into(),
+            window.raw_window_handle(),
+            None,
+            None,
+        );
+
+        match create_dxgi_swapchain(
+            &window.raw_window_handle(),
+            &gl_context.gl,
+            [0.0; 4],
+            None,
+            InteropAttachment::Auto,
+        ) {
+            Ok(mut dxgi_interop) => {
+                unsafe {
+                    dxgi_interop.resize(&gl_context.gl, 640, 480).unwrap();
+                    // The window is hidden, so presenting can report it as occluded.
+                    match with_dxgi_swapchain(&mut dxgi_interop, &gl_context.gl, |_fbo| Ok(())) {
+                        Ok(()) | Err(Problem::Occluded) => (),
+                        Err(err) => panic!("Failed to present a frame: {}", err),
+                    }
+                }
+                drop(dxgi_interop);
+            }
+            Err(Problem::Unsupported | Problem::MissingExtension(_)) => (),
+            Err(err) => panic!("Failed to create the DXGI swapchain: {}", err),
+        }
     }
 }
@@ -0,0 +1,20 @@
+use windows::Win32::UI::Shell::{
+    SHQueryUserNotificationState, QUNS_BUSY, QUNS_PRESENTATION_MODE, QUNS_RUNNING_D3D_FULL_SCREEN,
+};
+
+// Whether a fullscreen app, like a game or a video player, has the foreground. Windows uses the
+// same check to hold back notifications.
+//
+// Assumes there’s no fullscreen app if the state can’t be read.
+pub fn is_fullscreen_app_running() -> bool {
+    match unsafe { SHQueryUserNotificationState() } {
+        Ok(state) => matches!(
+            state,
+            QUNS_BUSY | QUNS_RUNNING_D3D_FULL_SCREEN | QUNS_PRESENTATION_MODE
+        ),
+        Err(err) => {
+            log::debug!("Failed to check for fullscreen apps: {}", err);
+            false
+        }
+    }
+}
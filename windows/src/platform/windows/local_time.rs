@@ -0,0 +1,13 @@
+use windows::Win32::System::SystemInformation::GetLocalTime;
+
+// The number of minutes since midnight, in the local time zone.
+pub fn minute_of_day() -> u32 {
+    let time = unsafe { GetLocalTime() };
+    time.wHour as u32 * 60 + time.wMinute as u32
+}
+
+// Like `minute_of_day`, but with the seconds as a fraction, for changes that should be smooth.
+pub fn time_of_day() -> f32 {
+    let time = unsafe { GetLocalTime() };
+    time.wHour as f32 * 60.0 + time.wMinute as f32 + time.wSecond as f32 / 60.0
+}
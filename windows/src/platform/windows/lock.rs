@@ -0,0 +1,39 @@
+use std::ffi::c_void;
+
+use windows::Win32::Foundation::BOOL;
+use windows::Win32::System::Shutdown::LockWorkStation;
+use windows::Win32::UI::WindowsAndMessaging::{
+    SystemParametersInfoW, SPI_GETSCREENSAVESECURE, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+};
+
+// Whether "On resume, display logon screen" is enabled in the screensaver settings.
+pub fn is_screensaver_secure() -> bool {
+    let mut secure = BOOL::default();
+    let result = unsafe {
+        SystemParametersInfoW(
+            SPI_GETSCREENSAVESECURE,
+            0,
+            Some(&mut secure as *mut BOOL as *mut c_void),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    };
+
+    match result {
+        Ok(()) => secure.as_bool(),
+        Err(err) => {
+            log::warn!("Failed to read the screensaver security setting: {}", err);
+            false
+        }
+    }
+}
+
+// Return to the lock screen.
+//
+// When Windows starts the screensaver itself, it already locks the session on exit and this is a
+// no-op. It matters when the screensaver is started some other way, like from the command line or
+// a scheduled task, which Windows wouldn’t otherwise lock behind.
+pub fn lock_workstation() {
+    if let Err(err) = unsafe { LockWorkStation() } {
+        log::error!("Failed to lock the workstation: {}", err);
+    }
+}
@@ -1,3 +1,17 @@
+pub mod accessibility;
+pub mod adapter;
+pub mod desktop;
 pub mod dpi_awareness;
 pub mod dxgi_swapchain;
+pub mod fullscreen;
+pub mod local_time;
+pub mod lock;
+pub mod monitors;
+pub mod notifications;
+pub mod orientation;
+pub mod session;
+pub mod shell;
+pub mod single_instance;
+pub mod theme;
+pub mod tray;
 pub mod window;
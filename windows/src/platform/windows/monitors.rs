@@ -0,0 +1,59 @@
+use std::mem;
+
+use windows::Win32::Foundation::{BOOL, LPARAM, RECT};
+use windows::Win32::Graphics::Gdi::{
+    EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW,
+    MONITORINFOF_PRIMARY,
+};
+
+// The device name of the primary display right now, like `\\.\DISPLAY1`. This is looked up each
+// time, since the primary display changes as displays are connected and rearranged.
+pub fn primary_device_name() -> Option<String> {
+    let mut primary: Option<String> = None;
+    let found = unsafe {
+        EnumDisplayMonitors(
+            HDC::default(),
+            None,
+            Some(find_primary),
+            LPARAM(&mut primary as *mut Option<String> as isize),
+        )
+    };
+    if !found.as_bool() && primary.is_none() {
+        log::warn!("Can’t list the displays to find the primary one");
+    }
+    primary
+}
+
+unsafe extern "system" fn find_primary(
+    monitor: HMONITOR,
+    _hdc: HDC,
+    _rect: *mut RECT,
+    primary: LPARAM,
+) -> BOOL {
+    let mut info = MONITORINFOEXW {
+        monitorInfo: MONITORINFO {
+            cbSize: mem::size_of::<MONITORINFOEXW>() as u32,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    if !GetMonitorInfoW(
+        monitor,
+        &mut info as *mut MONITORINFOEXW as *mut MONITORINFO,
+    )
+    .as_bool()
+    {
+        return true.into();
+    }
+    if info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY == 0 {
+        return true.into();
+    }
+
+    let length = info
+        .szDevice
+        .iter()
+        .position(|&c| c == 0)
+        .unwrap_or(info.szDevice.len());
+    *(primary.0 as *mut Option<String>) = Some(String::from_utf16_lossy(&info.szDevice[..length]));
+    false.into()
+}
@@ -0,0 +1,15 @@
+use windows::core::HSTRING;
+use windows::Win32::UI::WindowsAndMessaging::{FindWindowW, IsWindowVisible};
+
+// Toast notifications pop up in a window of this class, owned by the shell
+const TOAST_WINDOW_CLASS: &str = "Windows.UI.Core.CoreWindow";
+
+// Whether a toast notification is on screen. There’s no API to ask, so this looks for the shell’s
+// notification window by its title. The title is translated along with the rest of Windows, which
+// is why the settings let you list the titles to look for.
+pub fn is_notification_showing(window_titles: &[String]) -> bool {
+    window_titles.iter().any(|title| unsafe {
+        let window = FindWindowW(&HSTRING::from(TOAST_WINDOW_CLASS), &HSTRING::from(title));
+        window.0 != 0 && IsWindowVisible(window).as_bool()
+    })
+}
@@ -0,0 +1,81 @@
+use std::fmt;
+use std::mem;
+
+use winit::dpi::PhysicalSize;
+
+use windows::core::HSTRING;
+use windows::Win32::Graphics::Gdi::{
+    EnumDisplaySettingsW, DEVMODEW, DMDO_180, DMDO_270, DMDO_90, ENUM_CURRENT_SETTINGS,
+};
+
+// How far a display is turned from its natural orientation, clockwise
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Rotation {
+    None,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl Rotation {
+    // Whether the display is on its side, with its width and height swapped
+    pub fn is_sideways(&self) -> bool {
+        matches!(self, Rotation::Rotate90 | Rotation::Rotate270)
+    }
+}
+
+impl fmt::Display for Rotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let degrees = match self {
+            Rotation::None => 0,
+            Rotation::Rotate90 => 90,
+            Rotation::Rotate180 => 180,
+            Rotation::Rotate270 => 270,
+        };
+        write!(f, "{}°", degrees)
+    }
+}
+
+// The current mode of a display, with the size as laid out on the desktop, after rotation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplayMode {
+    pub size: PhysicalSize<u32>,
+    pub rotation: Rotation,
+    // In Hz, if the driver says
+    pub refresh_rate: Option<u32>,
+}
+
+// Look up the current mode of a display by its device name, like `\\.\DISPLAY1`.
+pub fn current_mode(device_name: &str) -> Option<DisplayMode> {
+    let mut mode = DEVMODEW {
+        dmSize: mem::size_of::<DEVMODEW>() as u16,
+        ..Default::default()
+    };
+
+    let found = unsafe {
+        EnumDisplaySettingsW(
+            &HSTRING::from(device_name),
+            ENUM_CURRENT_SETTINGS,
+            &mut mode,
+        )
+    };
+    if !found.as_bool() {
+        log::warn!("Can’t read the display settings for {}", device_name);
+        return None;
+    }
+
+    let orientation = unsafe { mode.Anonymous1.Anonymous2.dmDisplayOrientation };
+    let rotation = match orientation {
+        DMDO_90 => Rotation::Rotate90,
+        DMDO_180 => Rotation::Rotate180,
+        DMDO_270 => Rotation::Rotate270,
+        _ => Rotation::None,
+    };
+
+    Some(DisplayMode {
+        size: PhysicalSize::new(mode.dmPelsWidth, mode.dmPelsHeight),
+        rotation,
+        // 0 and 1 both mean the hardware default.
+        refresh_rate: (mode.dmDisplayFrequency > 1).then_some(mode.dmDisplayFrequency),
+    })
+}
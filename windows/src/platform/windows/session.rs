@@ -0,0 +1,181 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use windows::core::w;
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+use windows::Win32::System::Threading::GetCurrentProcessId;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, GetSystemMetrics, RegisterClassW, HMENU,
+    SM_REMOTESESSION, WINDOW_STYLE, WM_DISPLAYCHANGE, WM_ENDSESSION, WM_QUERYENDSESSION, WNDCLASSW,
+    WS_EX_TOOLWINDOW,
+};
+
+static SESSION_ENDING: AtomicBool = AtomicBool::new(false);
+static DISPLAYS_CHANGED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SessionKind {
+    // Someone sitting at the computer
+    Local,
+    // Remote Desktop, where every frame is sent over the network
+    Remote,
+    // Session 0, where services run without a display of their own
+    Service,
+}
+
+impl SessionKind {
+    // Whether there’s probably no GPU to render with, or a slow connection to show the frames.
+    pub fn is_constrained(&self) -> bool {
+        *self != SessionKind::Local
+    }
+}
+
+impl fmt::Display for SessionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionKind::Local => write!(f, "local"),
+            SessionKind::Remote => write!(f, "remote desktop"),
+            SessionKind::Service => write!(f, "service (session 0)"),
+        }
+    }
+}
+
+// What kind of session we’re running in.
+pub fn kind() -> SessionKind {
+    let mut session_id = 0;
+    let is_session_zero = unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &mut session_id) }
+        .is_ok()
+        && session_id == 0;
+    if is_session_zero {
+        return SessionKind::Service;
+    }
+
+    if unsafe { GetSystemMetrics(SM_REMOTESESSION) } != 0 {
+        return SessionKind::Remote;
+    }
+
+    SessionKind::Local
+}
+
+// A hidden window that hears about the system shutting down or the user logging off, and about
+// displays changing.
+//
+// Windows sends these messages straight to the window procedure of every top-level window instead
+// of posting them, so they never pass through SDL’s message hook. This window is never shown, but
+// it can’t be message-only, since those don’t get broadcasts. Its messages are handled whenever
+// SDL pumps events on the same thread. If we keep rendering, Windows eventually kills us
+// mid-frame and may show an “ending program” dialog.
+pub struct SessionWatcher {
+    hwnd: HWND,
+}
+
+impl SessionWatcher {
+    pub fn new() -> Result<Self, String> {
+        unsafe {
+            let instance = GetModuleHandleW(None).map_err(|err| err.to_string())?;
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(window_proc),
+                hInstance: instance.into(),
+                lpszClassName: w!("FluxSessionWatcher"),
+                ..Default::default()
+            };
+            RegisterClassW(&class);
+
+            let hwnd = CreateWindowExW(
+                WS_EX_TOOLWINDOW,
+                w!("FluxSessionWatcher"),
+                w!("Flux"),
+                WINDOW_STYLE::default(),
+                0,
+                0,
+                0,
+                0,
+                HWND::default(),
+                HMENU::default(),
+                instance,
+                None,
+            );
+            if hwnd.0 == 0 {
+                return Err(format!(
+                    "Failed to create the session window: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+
+            Ok(Self { hwnd })
+        }
+    }
+}
+
+impl Drop for SessionWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DestroyWindow(self.hwnd);
+        }
+    }
+}
+
+// Whether Windows has asked us to close because the session is ending.
+pub fn is_ending() -> bool {
+    SESSION_ENDING.load(Ordering::Relaxed)
+}
+
+// Whether a display was connected, disconnected, or changed resolution since the last call.
+pub fn take_display_change() -> bool {
+    DISPLAYS_CHANGED.swap(false, Ordering::Relaxed)
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    let ending = match message {
+        WM_QUERYENDSESSION => true,
+        // A zero wparam means the shutdown was cancelled.
+        WM_ENDSESSION => wparam.0 != 0,
+        // Broadcast to every top-level window, once for each change
+        WM_DISPLAYCHANGE => {
+            DISPLAYS_CHANGED.store(true, Ordering::Relaxed);
+            false
+        }
+        _ => false,
+    };
+
+    if ending && !SESSION_ENDING.swap(true, Ordering::Relaxed) {
+        log::info!("The session is ending. Shutting down.");
+    }
+
+    // Lets the session end, since we stop on our own
+    DefWindowProcW(hwnd, message, wparam, lparam)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use windows::Win32::UI::WindowsAndMessaging::SendMessageW;
+
+    #[test]
+    fn it_notices_the_session_ending() {
+        let watcher = SessionWatcher::new().unwrap();
+
+        unsafe { SendMessageW(watcher.hwnd, WM_ENDSESSION, WPARAM(0), LPARAM(0)) };
+        assert!(!is_ending());
+
+        unsafe { SendMessageW(watcher.hwnd, WM_QUERYENDSESSION, WPARAM(0), LPARAM(0)) };
+        assert!(is_ending());
+    }
+
+    #[test]
+    fn it_notices_displays_changing() {
+        let watcher = SessionWatcher::new().unwrap();
+        assert!(!take_display_change());
+
+        unsafe { SendMessageW(watcher.hwnd, WM_DISPLAYCHANGE, WPARAM(32), LPARAM(0)) };
+        assert!(take_display_change());
+        assert!(!take_display_change());
+    }
+}
@@ -0,0 +1,31 @@
+use std::path::Path;
+
+use windows::core::{w, HSTRING};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::Shell::ShellExecuteW;
+use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+// Open a file or folder with whatever Windows uses for it, like Notepad or Explorer.
+pub fn open(path: &Path) -> Result<(), String> {
+    let result = unsafe {
+        ShellExecuteW(
+            HWND::default(),
+            w!("open"),
+            &HSTRING::from(path.as_os_str()),
+            None,
+            None,
+            SW_SHOWNORMAL,
+        )
+    };
+
+    // Anything above 32 means it worked. Below that is an error code.
+    if result.0 > 32 {
+        Ok(())
+    } else {
+        Err(format!(
+            "Windows couldn’t open {} (error {})",
+            path.display(),
+            result.0
+        ))
+    }
+}
@@ -0,0 +1,45 @@
+use windows::core::w;
+use windows::Win32::Foundation::{CloseHandle, GetLastError, ERROR_ALREADY_EXISTS, HANDLE};
+use windows::Win32::System::Threading::{CreateMutexW, ReleaseMutex};
+
+// A named mutex held for as long as the screensaver is running.
+//
+// Windows sometimes launches the screensaver again while it’s already running. Two full-screen
+// instances would fight over the displays, so the second one should exit instead.
+pub struct SingleInstance(HANDLE);
+
+impl SingleInstance {
+    // Returns `None` if another instance is already running.
+    pub fn acquire() -> Option<Self> {
+        unsafe {
+            let handle = match CreateMutexW(None, true, w!("Local\\FluxScreensaver")) {
+                Ok(handle) => handle,
+                Err(err) => {
+                    // Don’t prevent the screensaver from running if we can’t create the mutex.
+                    log::warn!("Failed to create the single instance mutex: {}", err);
+                    return Some(Self(HANDLE::default()));
+                }
+            };
+
+            if GetLastError() == ERROR_ALREADY_EXISTS {
+                let _ = CloseHandle(handle);
+                return None;
+            }
+
+            Some(Self(handle))
+        }
+    }
+}
+
+impl Drop for SingleInstance {
+    fn drop(&mut self) {
+        if self.0.is_invalid() {
+            return;
+        }
+
+        unsafe {
+            let _ = ReleaseMutex(self.0);
+            let _ = CloseHandle(self.0);
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use std::ffi::c_void;
+use std::mem;
+
+use windows::core::w;
+use windows::Win32::Foundation::ERROR_SUCCESS;
+use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+// Read the "Choose your default app mode" setting.
+//
+// Returns `None` if the setting can’t be read, which is the case on older versions of Windows.
+pub fn apps_use_light_theme() -> Option<bool> {
+    let mut value: u32 = 0;
+    let mut size = mem::size_of::<u32>() as u32;
+
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            w!("AppsUseLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut value as *mut u32 as *mut c_void),
+            Some(&mut size),
+        )
+    };
+
+    (status == ERROR_SUCCESS).then_some(value != 0)
+}
@@ -0,0 +1,190 @@
+use std::cell::RefCell;
+use std::sync::mpsc;
+
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, POINT, WPARAM};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::Shell::{
+    Shell_NotifyIconW, NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NOTIFYICONDATAW,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    AppendMenuW, CreatePopupMenu, CreateWindowExW, DefWindowProcW, DestroyMenu, DestroyWindow,
+    GetCursorPos, LoadIconW, RegisterClassW, SetForegroundWindow, TrackPopupMenu, HMENU,
+    HWND_MESSAGE, IDI_APPLICATION, MF_SEPARATOR, MF_STRING, TPM_BOTTOMALIGN, TPM_RIGHTBUTTON,
+    WINDOW_EX_STYLE, WINDOW_STYLE, WM_APP, WM_COMMAND, WM_CONTEXTMENU, WM_RBUTTONUP, WNDCLASSW,
+};
+
+// Sent by the shell when something happens to the icon
+const WM_TRAY_ICON: u32 = WM_APP + 1;
+
+const MENU_SETTINGS: usize = 1;
+const MENU_PAUSE: usize = 2;
+const MENU_QUIT: usize = 3;
+
+// What the user picked from the tray menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrayCommand {
+    OpenSettings,
+    TogglePause,
+    Quit,
+}
+
+thread_local! {
+    // The window procedure has no other way to reach the tray.
+    static COMMANDS: RefCell<Option<mpsc::Sender<TrayCommand>>> = RefCell::new(None);
+}
+
+// An icon in the notification area, with a menu to control the wallpaper.
+//
+// The icon’s messages arrive through SDL’s message loop, so commands show up after the event pump
+// runs on the same thread.
+pub struct Tray {
+    hwnd: HWND,
+    commands: mpsc::Receiver<TrayCommand>,
+}
+
+impl Tray {
+    pub fn new() -> Result<Self, String> {
+        let (sender, commands) = mpsc::channel();
+        COMMANDS.with(|cell| *cell.borrow_mut() = Some(sender));
+
+        unsafe {
+            let instance = GetModuleHandleW(None).map_err(|err| err.to_string())?;
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(window_proc),
+                hInstance: instance.into(),
+                lpszClassName: w!("FluxTray"),
+                ..Default::default()
+            };
+            RegisterClassW(&class);
+
+            // A message-only window, which is never shown
+            let hwnd = CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                w!("FluxTray"),
+                w!("Flux"),
+                WINDOW_STYLE::default(),
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                HMENU::default(),
+                instance,
+                None,
+            );
+            if hwnd.0 == 0 {
+                return Err(format!(
+                    "Failed to create the tray window: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+
+            // The icon embedded in release builds, or a generic one in development
+            let icon = LoadIconW(instance, PCWSTR(1 as *const u16))
+                .or_else(|_| LoadIconW(HINSTANCE::default(), IDI_APPLICATION))
+                .unwrap_or_default();
+
+            let mut data = notify_icon_data(hwnd);
+            data.uFlags = NIF_ICON | NIF_MESSAGE | NIF_TIP;
+            data.uCallbackMessage = WM_TRAY_ICON;
+            data.hIcon = icon;
+            let tip = "Flux".encode_utf16().collect::<Vec<u16>>();
+            data.szTip[..tip.len()].copy_from_slice(&tip);
+
+            if !Shell_NotifyIconW(NIM_ADD, &data).as_bool() {
+                let _ = DestroyWindow(hwnd);
+                return Err("Failed to add the tray icon".to_string());
+            }
+
+            Ok(Self { hwnd, commands })
+        }
+    }
+
+    // The next command picked from the menu, if any.
+    pub fn try_recv(&self) -> Option<TrayCommand> {
+        self.commands.try_recv().ok()
+    }
+}
+
+impl Drop for Tray {
+    fn drop(&mut self) {
+        unsafe {
+            Shell_NotifyIconW(NIM_DELETE, &notify_icon_data(self.hwnd));
+            let _ = DestroyWindow(self.hwnd);
+        }
+        COMMANDS.with(|cell| *cell.borrow_mut() = None);
+    }
+}
+
+fn notify_icon_data(hwnd: HWND) -> NOTIFYICONDATAW {
+    NOTIFYICONDATAW {
+        cbSize: std::mem::size_of::<NOTIFYICONDATAW>() as u32,
+        hWnd: hwnd,
+        uID: 1,
+        ..Default::default()
+    }
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match message {
+        WM_TRAY_ICON if matches!(lparam.0 as u32, WM_RBUTTONUP | WM_CONTEXTMENU) => {
+            show_menu(hwnd);
+            LRESULT(0)
+        }
+
+        WM_COMMAND => {
+            let command = match wparam.0 & 0xFFFF {
+                MENU_SETTINGS => Some(TrayCommand::OpenSettings),
+                MENU_PAUSE => Some(TrayCommand::TogglePause),
+                MENU_QUIT => Some(TrayCommand::Quit),
+                _ => None,
+            };
+            if let Some(command) = command {
+                COMMANDS.with(|cell| {
+                    if let Some(sender) = cell.borrow().as_ref() {
+                        let _ = sender.send(command);
+                    }
+                });
+            }
+            LRESULT(0)
+        }
+
+        _ => DefWindowProcW(hwnd, message, wparam, lparam),
+    }
+}
+
+unsafe fn show_menu(hwnd: HWND) {
+    let menu = match CreatePopupMenu() {
+        Ok(menu) => menu,
+        Err(err) => {
+            log::warn!("Failed to create the tray menu: {}", err);
+            return;
+        }
+    };
+
+    let _ = AppendMenuW(menu, MF_STRING, MENU_SETTINGS, w!("Settings"));
+    let _ = AppendMenuW(menu, MF_STRING, MENU_PAUSE, w!("Pause/Resume"));
+    let _ = AppendMenuW(menu, MF_SEPARATOR, 0, PCWSTR::null());
+    let _ = AppendMenuW(menu, MF_STRING, MENU_QUIT, w!("Quit"));
+
+    // The menu only closes when clicking elsewhere if our window is in the foreground.
+    let mut cursor = POINT::default();
+    let _ = GetCursorPos(&mut cursor);
+    SetForegroundWindow(hwnd);
+    TrackPopupMenu(
+        menu,
+        TPM_RIGHTBUTTON | TPM_BOTTOMALIGN,
+        cursor.x,
+        cursor.y,
+        0,
+        hwnd,
+        None,
+    );
+    let _ = DestroyMenu(menu);
+}
@@ -1,5 +1,43 @@
 use raw_window_handle::RawWindowHandle;
 use windows::Win32::Foundation::HWND;
+use winit::dpi::PhysicalSize;
+
+// The size of the window’s client area in physical pixels.
+//
+// This assumes the process is DPI aware. Otherwise, Windows reports scaled sizes.
+pub fn client_size(hwnd: HWND) -> PhysicalSize<u32> {
+    use windows::Win32::Foundation::RECT;
+    use windows::Win32::UI::WindowsAndMessaging::GetClientRect;
+
+    let mut rect = RECT::default();
+    unsafe {
+        let _ = GetClientRect(hwnd, &mut rect);
+    }
+
+    PhysicalSize::new(
+        (rect.right - rect.left).max(0) as u32,
+        (rect.bottom - rect.top).max(0) as u32,
+    )
+}
+
+// The scale factor of the monitor the window is on.
+pub fn scale_factor(hwnd: HWND) -> f64 {
+    use windows::Win32::UI::HiDpi::GetDpiForWindow;
+
+    // 96 DPI is 100% scaling
+    match unsafe { GetDpiForWindow(hwnd) } {
+        0 => 1.0,
+        dpi => dpi as f64 / 96.0,
+    }
+}
+
+// Whether the window still exists. The preview’s host window is destroyed when the Screen Saver
+// Settings dialog closes, without telling us.
+pub fn exists(hwnd: HWND) -> bool {
+    use windows::Win32::UI::WindowsAndMessaging::IsWindow;
+
+    unsafe { IsWindow(hwnd) }.as_bool()
+}
 
 pub unsafe fn set_window_parent_win32(handle: HWND, parent_handle: HWND) -> bool {
     use windows::Win32::UI::WindowsAndMessaging::{
@@ -0,0 +1,795 @@
+use std::rc::Rc;
+
+use glow as GL;
+use glow::HasContext;
+use winit::dpi::PhysicalSize;
+
+use crate::config::{AntiAliasing, Config, CropAnchor, FrameClear, UpscaleFilter};
+use crate::watermark::Watermark;
+
+// Adjustments applied to the final frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Effects {
+    // A multiplier for the color of each pixel. Used to fade in and out.
+    pub brightness: f32,
+    // Stretches colors away from mid-grey. 1 leaves them unchanged.
+    pub contrast: f32,
+    pub invert: bool,
+    // Moves the whole picture by this many pixels, to spread the wear on OLED displays
+    pub shift: (i32, i32),
+}
+
+impl Default for Effects {
+    fn default() -> Self {
+        Self {
+            brightness: 1.0,
+            contrast: 1.0,
+            invert: false,
+            shift: (0, 0),
+        }
+    }
+}
+
+impl Effects {
+    // Whether the effects leave the frame untouched, in which case we can skip the extra pass.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // Apply the effects to a single color, the same way the shader does.
+    pub fn apply(&self, [r, g, b]: [f32; 3]) -> [f32; 3] {
+        [r, g, b].map(|channel| {
+            let channel = if self.invert { 1.0 - channel } else { channel };
+            let channel = (channel - 0.5) * self.contrast + 0.5;
+            (channel * self.brightness).clamp(0.0, 1.0)
+        })
+    }
+}
+
+// A rendered frame that can be drawn by other contexts sharing the same objects.
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub texture: GL::NativeTexture,
+    pub size: PhysicalSize<u32>,
+}
+
+// A final pass that runs after Flux has rendered a frame.
+//
+// Flux renders into an offscreen texture, which is then drawn to the target framebuffer with the
+// effects applied.
+pub struct PostProcess {
+    gl: Rc<glow::Context>,
+    program: GL::NativeProgram,
+    vertex_array: GL::NativeVertexArray,
+    framebuffer: GL::NativeFramebuffer,
+    texture: GL::NativeTexture,
+    // The size of the target
+    size: PhysicalSize<u32>,
+    // Flux renders at this fraction of the target size
+    render_scale: f32,
+    upscale_filter: UpscaleFilter,
+    brightness_location: Option<GL::NativeUniformLocation>,
+    contrast_location: Option<GL::NativeUniformLocation>,
+    invert_location: Option<GL::NativeUniformLocation>,
+    uv_scale_location: Option<GL::NativeUniformLocation>,
+    uv_center_location: Option<GL::NativeUniformLocation>,
+    fxaa_location: Option<GL::NativeUniformLocation>,
+    texel_size_location: Option<GL::NativeUniformLocation>,
+
+    // Always render offscreen, so that the frame can be mirrored to other windows.
+    shared: bool,
+
+    // Cleared to before Flux renders each frame
+    clear_color: [f32; 4],
+    trails: Option<Trails>,
+    // Which part of a frame to keep when it’s cropped to cover the target
+    crop_anchor: CropAnchor,
+    // Smooth the edges of the finished frame as it’s presented
+    fxaa: bool,
+    multisample: Option<Multisample>,
+    // Drawn over the finished frame, after the effects
+    watermark: Option<Watermark>,
+}
+
+// The frames accumulated so far for motion trails. Flux’s output is blended into this on every
+// frame, and this is what ends up on screen.
+struct Trails {
+    framebuffer: GL::NativeFramebuffer,
+    texture: GL::NativeTexture,
+    // How much of the accumulated frames to keep each frame
+    persistence: f32,
+}
+
+// A multisampled framebuffer for Flux to render into. It’s resolved into the offscreen texture
+// after every frame.
+struct Multisample {
+    framebuffer: GL::NativeFramebuffer,
+    renderbuffer: GL::NativeRenderbuffer,
+    samples: i32,
+}
+
+const VERTEX_SHADER: &str = r#"
+out vec2 v_uv;
+
+uniform vec2 u_uv_scale;
+uniform vec2 u_uv_center;
+
+void main() {
+    // Draw a single triangle that covers the whole screen.
+    vec2 position = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+    v_uv = (position - 0.5) * u_uv_scale + u_uv_center;
+    gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+in vec2 v_uv;
+out vec4 out_color;
+
+uniform sampler2D u_frame;
+uniform float u_brightness;
+uniform float u_contrast;
+// 1 to invert the colors, 0 to leave them alone
+uniform float u_invert;
+// 1 to smooth out edges with FXAA, 0 to leave them alone
+uniform float u_fxaa;
+// The size of a pixel of the frame, in UV coordinates
+uniform vec2 u_texel_size;
+
+const vec3 LUMA = vec3(0.299, 0.587, 0.114);
+const float FXAA_REDUCE_MIN = 1.0 / 128.0;
+const float FXAA_REDUCE_MUL = 1.0 / 8.0;
+const float FXAA_SPAN_MAX = 8.0;
+
+// The simple version of Timothy Lottes’ FXAA. It finds the direction of the edge from the
+// brightness of the diagonal neighbours, and blurs along it. If that blur brings in colors from
+// across the edge, it falls back to a shorter one.
+vec3 fxaa(vec2 uv, vec3 rgb_m) {
+    float luma_nw = dot(texture(u_frame, uv + vec2(-1.0, -1.0) * u_texel_size).rgb, LUMA);
+    float luma_ne = dot(texture(u_frame, uv + vec2(1.0, -1.0) * u_texel_size).rgb, LUMA);
+    float luma_sw = dot(texture(u_frame, uv + vec2(-1.0, 1.0) * u_texel_size).rgb, LUMA);
+    float luma_se = dot(texture(u_frame, uv + vec2(1.0, 1.0) * u_texel_size).rgb, LUMA);
+    float luma_m = dot(rgb_m, LUMA);
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    vec2 direction = vec2(
+        (luma_sw + luma_se) - (luma_nw + luma_ne),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+    float reduce = max(
+        (luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL,
+        FXAA_REDUCE_MIN
+    );
+    float scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, -FXAA_SPAN_MAX, FXAA_SPAN_MAX) * u_texel_size;
+
+    vec3 rgb_a = 0.5 * (
+        texture(u_frame, uv + direction * (1.0 / 3.0 - 0.5)).rgb +
+        texture(u_frame, uv + direction * (2.0 / 3.0 - 0.5)).rgb
+    );
+    vec3 rgb_b = rgb_a * 0.5 + 0.25 * (
+        texture(u_frame, uv - direction * 0.5).rgb +
+        texture(u_frame, uv + direction * 0.5).rgb
+    );
+    float luma_b = dot(rgb_b, LUMA);
+
+    return (luma_b < luma_min || luma_b > luma_max) ? rgb_a : rgb_b;
+}
+
+void main() {
+    vec4 color = texture(u_frame, v_uv);
+    if (u_fxaa > 0.5) {
+        color.rgb = fxaa(v_uv, color.rgb);
+    }
+    vec3 rgb = mix(color.rgb, 1.0 - color.rgb, u_invert);
+    rgb = (rgb - 0.5) * u_contrast + 0.5;
+    out_color = vec4(clamp(rgb * u_brightness, 0.0, 1.0), color.a);
+}
+"#;
+
+impl PostProcess {
+    pub fn new(
+        gl: &Rc<glow::Context>,
+        size: PhysicalSize<u32>,
+        render_scale: f32,
+        upscale_filter: UpscaleFilter,
+    ) -> Result<Self, String> {
+        unsafe {
+            let program = create_program(gl, VERTEX_SHADER, FRAGMENT_SHADER)?;
+            let vertex_array = gl.create_vertex_array()?;
+            let framebuffer = gl.create_framebuffer()?;
+            let texture = gl.create_texture()?;
+
+            gl.use_program(Some(program));
+            gl.uniform_1_i32(gl.get_uniform_location(program, "u_frame").as_ref(), 0);
+            let brightness_location = gl.get_uniform_location(program, "u_brightness");
+            let contrast_location = gl.get_uniform_location(program, "u_contrast");
+            let invert_location = gl.get_uniform_location(program, "u_invert");
+            let uv_scale_location = gl.get_uniform_location(program, "u_uv_scale");
+            let uv_center_location = gl.get_uniform_location(program, "u_uv_center");
+            let fxaa_location = gl.get_uniform_location(program, "u_fxaa");
+            let texel_size_location = gl.get_uniform_location(program, "u_texel_size");
+            gl.use_program(None);
+
+            let post_process = Self {
+                gl: Rc::clone(gl),
+                program,
+                vertex_array,
+                framebuffer,
+                texture,
+                size,
+                render_scale,
+                upscale_filter,
+                brightness_location,
+                contrast_location,
+                invert_location,
+                uv_scale_location,
+                uv_center_location,
+                fxaa_location,
+                texel_size_location,
+                shared: false,
+                clear_color: [0.0, 0.0, 0.0, 1.0],
+                trails: None,
+                crop_anchor: CropAnchor::Center,
+                fxaa: false,
+                multisample: None,
+                watermark: None,
+            };
+            post_process.allocate()?;
+
+            Ok(post_process)
+        }
+    }
+
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        if self.size == size {
+            return;
+        }
+
+        self.size = size;
+        if let Err(err) = unsafe { self.allocate() } {
+            log::error!("Failed to resize the post-processing framebuffer: {}", err);
+        }
+    }
+
+    // Choose how each frame starts out. Trails need a texture of their own to accumulate frames in,
+    // since the contents of the window’s back buffer aren’t kept between frames.
+    pub fn set_frame_clear(
+        &mut self,
+        frame_clear: FrameClear,
+        clear_color: [f32; 4],
+        trail_persistence: f32,
+    ) -> Result<(), String> {
+        self.clear_color = clear_color;
+
+        unsafe {
+            if let Some(trails) = self.trails.take() {
+                self.gl.delete_framebuffer(trails.framebuffer);
+                self.gl.delete_texture(trails.texture);
+            }
+
+            if frame_clear == FrameClear::Trails {
+                self.trails = Some(Trails {
+                    framebuffer: self.gl.create_framebuffer()?,
+                    texture: self.gl.create_texture()?,
+                    persistence: trail_persistence,
+                });
+                self.allocate()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn set_crop_anchor(&mut self, crop_anchor: CropAnchor) {
+        self.crop_anchor = crop_anchor;
+    }
+
+    // Choose how to smooth out edges. MSAA asks for at most as many samples as the driver supports.
+    pub fn set_anti_aliasing(&mut self, anti_aliasing: AntiAliasing) -> Result<(), String> {
+        self.fxaa = anti_aliasing == AntiAliasing::Fxaa;
+
+        unsafe {
+            if let Some(multisample) = self.multisample.take() {
+                self.gl.delete_framebuffer(multisample.framebuffer);
+                self.gl.delete_renderbuffer(multisample.renderbuffer);
+            }
+
+            let max_samples = self.gl.get_parameter_i32(GL::MAX_SAMPLES);
+            let samples = anti_aliasing.samples().min(max_samples);
+            if samples > 1 {
+                self.multisample = Some(Multisample {
+                    framebuffer: self.gl.create_framebuffer()?,
+                    renderbuffer: self.gl.create_renderbuffer()?,
+                    samples,
+                });
+                self.allocate()?;
+            } else if anti_aliasing.samples() > 1 {
+                log::warn!("MSAA isn’t supported. Rendering without it.");
+            }
+        }
+
+        Ok(())
+    }
+
+    // Load the watermark image from the settings, if there is one. A missing or broken image is
+    // logged and skipped, rather than keeping the screensaver from starting.
+    pub fn set_watermark(&mut self, config: &Config) {
+        self.watermark = config.watermark_path().and_then(|path| {
+            let watermark = &config.screensaver.watermark;
+            Watermark::new(&self.gl, &path, watermark.corner, watermark.opacity)
+                .map_err(|err| log::warn!("{}", err))
+                .ok()
+        });
+    }
+
+    // The size Flux should render at.
+    pub fn render_size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(
+            scale(self.size.width, self.render_scale),
+            scale(self.size.height, self.render_scale),
+        )
+    }
+
+    fn is_scaled(&self) -> bool {
+        self.render_size() != self.size
+    }
+
+    // Always keep the latest frame in the offscreen texture, and return it so that it can be drawn
+    // to other windows.
+    pub fn share_frame(&mut self) -> Frame {
+        self.shared = true;
+        self.output()
+    }
+
+    // The texture holding the finished frame, before effects
+    fn output(&self) -> Frame {
+        let texture = match &self.trails {
+            Some(trails) => trails.texture,
+            None => self.texture,
+        };
+        Frame {
+            texture,
+            size: self.render_size(),
+        }
+    }
+
+    // Render a frame with `render`, and draw it to `target` with the effects applied.
+    //
+    // The effects pass is skipped entirely if there’s nothing to apply and the frame doesn’t need
+    // upscaling.
+    pub fn draw(
+        &self,
+        target: Option<GL::NativeFramebuffer>,
+        effects: &Effects,
+        render: impl FnOnce(),
+    ) {
+        let gl = &self.gl;
+
+        unsafe {
+            if effects.is_identity()
+                && !self.shared
+                && !self.is_scaled()
+                && self.trails.is_none()
+                && !self.fxaa
+                && self.multisample.is_none()
+            {
+                gl.bind_framebuffer(GL::FRAMEBUFFER, target);
+                self.clear(self.size);
+                render();
+                if let Some(watermark) = &self.watermark {
+                    watermark.draw(target, self.size, effects.shift);
+                }
+                return;
+            }
+
+            match &self.multisample {
+                Some(multisample) => {
+                    gl.bind_framebuffer(GL::FRAMEBUFFER, Some(multisample.framebuffer));
+                    self.clear(self.render_size());
+                    render();
+                    self.resolve(multisample);
+                }
+                None => {
+                    gl.bind_framebuffer(GL::FRAMEBUFFER, Some(self.framebuffer));
+                    self.clear(self.render_size());
+                    render();
+                }
+            }
+
+            if let Some(trails) = &self.trails {
+                self.accumulate(trails);
+            }
+        }
+
+        self.present(target, self.output(), effects);
+    }
+
+    // Clear the bound framebuffer to the background color. The back buffer’s contents are
+    // undefined after a DXGI present, so anything Flux doesn’t overwrite would otherwise show
+    // older frames.
+    unsafe fn clear(&self, size: PhysicalSize<u32>) {
+        let gl = &self.gl;
+        let [r, g, b, a] = self.clear_color;
+        gl.viewport(0, 0, size.width as i32, size.height as i32);
+        gl.clear_color(r, g, b, a);
+        gl.clear(GL::COLOR_BUFFER_BIT);
+    }
+
+    // Average the samples of each pixel into the offscreen texture.
+    unsafe fn resolve(&self, multisample: &Multisample) {
+        let gl = &self.gl;
+        let render_size = self.render_size();
+        let (width, height) = (render_size.width as i32, render_size.height as i32);
+
+        gl.bind_framebuffer(GL::READ_FRAMEBUFFER, Some(multisample.framebuffer));
+        gl.bind_framebuffer(GL::DRAW_FRAMEBUFFER, Some(self.framebuffer));
+        gl.blit_framebuffer(
+            0,
+            0,
+            width,
+            height,
+            0,
+            0,
+            width,
+            height,
+            GL::COLOR_BUFFER_BIT,
+            GL::NEAREST,
+        );
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+    }
+
+    // Blend the frame Flux just rendered over the accumulated ones, fading those out a little.
+    unsafe fn accumulate(&self, trails: &Trails) {
+        let gl = &self.gl;
+        let render_size = self.render_size();
+
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(trails.framebuffer));
+        gl.viewport(0, 0, render_size.width as i32, render_size.height as i32);
+
+        let blend_enabled = gl.is_enabled(GL::BLEND);
+        gl.enable(GL::BLEND);
+        gl.blend_color(0.0, 0.0, 0.0, 1.0 - trails.persistence);
+        gl.blend_func(GL::CONSTANT_ALPHA, GL::ONE_MINUS_CONSTANT_ALPHA);
+
+        self.draw_texture(self.texture, Crop::NONE, &Effects::default(), None);
+
+        gl.blend_func(GL::ONE, GL::ZERO);
+        if !blend_enabled {
+            gl.disable(GL::BLEND);
+        }
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+    }
+
+    // Draw a frame to `target` with the effects applied. Frames that don’t match the aspect ratio
+    // of the target are scaled to cover it and cropped.
+    pub fn present(&self, target: Option<GL::NativeFramebuffer>, frame: Frame, effects: &Effects) {
+        let gl = &self.gl;
+
+        unsafe {
+            gl.bind_framebuffer(GL::FRAMEBUFFER, target);
+            // Fill the strips along the edges that a shifted picture leaves uncovered.
+            let (x, y) = effects.shift;
+            if (x, y) != (0, 0) {
+                self.clear(self.size);
+            }
+            gl.viewport(x, y, self.size.width as i32, self.size.height as i32);
+
+            let blend_enabled = gl.is_enabled(GL::BLEND);
+            gl.disable(GL::BLEND);
+
+            let fxaa = self.fxaa.then_some(frame.size);
+            let crop = cover(frame.size, self.size, self.crop_anchor);
+            self.draw_texture(frame.texture, crop, effects, fxaa);
+
+            if blend_enabled {
+                gl.enable(GL::BLEND);
+            }
+        }
+
+        if let Some(watermark) = &self.watermark {
+            watermark.draw(target, self.size, effects.shift);
+        }
+    }
+
+    // Draw `texture` over the whole viewport of the bound framebuffer. Pass the size of the
+    // texture as `fxaa` to smooth out its edges.
+    unsafe fn draw_texture(
+        &self,
+        texture: GL::NativeTexture,
+        crop: Crop,
+        effects: &Effects,
+        fxaa: Option<PhysicalSize<u32>>,
+    ) {
+        let gl = &self.gl;
+
+        gl.use_program(Some(self.program));
+        gl.uniform_1_f32(self.brightness_location.as_ref(), effects.brightness);
+        gl.uniform_1_f32(self.contrast_location.as_ref(), effects.contrast);
+        gl.uniform_1_f32(
+            self.invert_location.as_ref(),
+            if effects.invert { 1.0 } else { 0.0 },
+        );
+        gl.uniform_2_f32(self.uv_scale_location.as_ref(), crop.scale.0, crop.scale.1);
+        gl.uniform_2_f32(
+            self.uv_center_location.as_ref(),
+            crop.center.0,
+            crop.center.1,
+        );
+        match fxaa {
+            Some(size) => {
+                gl.uniform_1_f32(self.fxaa_location.as_ref(), 1.0);
+                gl.uniform_2_f32(
+                    self.texel_size_location.as_ref(),
+                    1.0 / size.width.max(1) as f32,
+                    1.0 / size.height.max(1) as f32,
+                );
+            }
+            None => gl.uniform_1_f32(self.fxaa_location.as_ref(), 0.0),
+        }
+
+        gl.active_texture(GL::TEXTURE0);
+        gl.bind_texture(GL::TEXTURE_2D, Some(texture));
+        gl.bind_vertex_array(Some(self.vertex_array));
+        gl.draw_arrays(GL::TRIANGLES, 0, 3);
+
+        gl.bind_vertex_array(None);
+        gl.bind_texture(GL::TEXTURE_2D, None);
+        gl.use_program(None);
+    }
+
+    // (Re)allocate the offscreen textures to match the current render size.
+    unsafe fn allocate(&self) -> Result<(), String> {
+        self.allocate_target(self.framebuffer, self.texture)?;
+
+        if let Some(multisample) = &self.multisample {
+            self.allocate_multisample(multisample)?;
+        }
+
+        // Start the trails from a blank frame.
+        if let Some(trails) = &self.trails {
+            self.allocate_target(trails.framebuffer, trails.texture)?;
+            self.gl
+                .bind_framebuffer(GL::FRAMEBUFFER, Some(trails.framebuffer));
+            self.clear(self.render_size());
+            self.gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+        }
+
+        Ok(())
+    }
+
+    unsafe fn allocate_multisample(&self, multisample: &Multisample) -> Result<(), String> {
+        let gl = &self.gl;
+        let render_size = self.render_size();
+
+        gl.bind_renderbuffer(GL::RENDERBUFFER, Some(multisample.renderbuffer));
+        gl.renderbuffer_storage_multisample(
+            GL::RENDERBUFFER,
+            multisample.samples,
+            GL::RGBA8,
+            render_size.width as i32,
+            render_size.height as i32,
+        );
+        gl.bind_renderbuffer(GL::RENDERBUFFER, None);
+
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(multisample.framebuffer));
+        gl.framebuffer_renderbuffer(
+            GL::FRAMEBUFFER,
+            GL::COLOR_ATTACHMENT0,
+            GL::RENDERBUFFER,
+            Some(multisample.renderbuffer),
+        );
+        let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+        if status != GL::FRAMEBUFFER_COMPLETE {
+            return Err(format!(
+                "Incomplete multisampled framebuffer: {:#x}",
+                status
+            ));
+        }
+
+        Ok(())
+    }
+
+    unsafe fn allocate_target(
+        &self,
+        framebuffer: GL::NativeFramebuffer,
+        texture: GL::NativeTexture,
+    ) -> Result<(), String> {
+        let gl = &self.gl;
+        let render_size = self.render_size();
+        let filter = match self.upscale_filter {
+            UpscaleFilter::Nearest => GL::NEAREST,
+            UpscaleFilter::Linear => GL::LINEAR,
+        };
+
+        gl.bind_texture(GL::TEXTURE_2D, Some(texture));
+        gl.tex_image_2d(
+            GL::TEXTURE_2D,
+            0,
+            GL::RGBA8 as i32,
+            render_size.width as i32,
+            render_size.height as i32,
+            0,
+            GL::RGBA,
+            GL::UNSIGNED_BYTE,
+            None,
+        );
+        gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, filter as i32);
+        gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, filter as i32);
+        gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+        gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+        gl.bind_texture(GL::TEXTURE_2D, None);
+
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(framebuffer));
+        gl.framebuffer_texture_2d(
+            GL::FRAMEBUFFER,
+            GL::COLOR_ATTACHMENT0,
+            GL::TEXTURE_2D,
+            Some(texture),
+            0,
+        );
+        let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+        if status != GL::FRAMEBUFFER_COMPLETE {
+            return Err(format!("Incomplete framebuffer: {:#x}", status));
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for PostProcess {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_program(self.program);
+            self.gl.delete_vertex_array(self.vertex_array);
+            self.gl.delete_framebuffer(self.framebuffer);
+            self.gl.delete_texture(self.texture);
+            if let Some(trails) = &self.trails {
+                self.gl.delete_framebuffer(trails.framebuffer);
+                self.gl.delete_texture(trails.texture);
+            }
+            if let Some(multisample) = &self.multisample {
+                self.gl.delete_framebuffer(multisample.framebuffer);
+                self.gl.delete_renderbuffer(multisample.renderbuffer);
+            }
+        }
+    }
+}
+
+fn scale(length: u32, scale: f32) -> u32 {
+    ((length as f32 * scale).round() as u32).clamp(1, length.max(1))
+}
+
+// The part of a texture to sample, in texture coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Crop {
+    // The size of the part, as a fraction of the texture
+    scale: (f32, f32),
+    center: (f32, f32),
+}
+
+impl Crop {
+    const NONE: Crop = Crop {
+        scale: (1.0, 1.0),
+        center: (0.5, 0.5),
+    };
+}
+
+// The portion of the source to sample so that it covers the target without stretching. When the
+// top and bottom are cropped off, `anchor` picks which part to keep.
+fn cover(source: PhysicalSize<u32>, target: PhysicalSize<u32>, anchor: CropAnchor) -> Crop {
+    if source.width == 0 || source.height == 0 || target.width == 0 || target.height == 0 {
+        return Crop::NONE;
+    }
+
+    let source_aspect = source.width as f32 / source.height as f32;
+    let target_aspect = target.width as f32 / target.height as f32;
+    if target_aspect > source_aspect {
+        let scale_y = source_aspect / target_aspect;
+        // Texture coordinates start from the bottom row.
+        let center_y = match anchor {
+            CropAnchor::Top => 1.0 - scale_y / 2.0,
+            CropAnchor::Center => 0.5,
+            CropAnchor::Bottom => scale_y / 2.0,
+        };
+        Crop {
+            scale: (1.0, scale_y),
+            center: (0.5, center_y),
+        }
+    } else {
+        Crop {
+            scale: (target_aspect / source_aspect, 1.0),
+            center: (0.5, 0.5),
+        }
+    }
+}
+
+// Compile and link a shader program, prepending the GLSL version for the current context.
+pub(crate) unsafe fn create_program(
+    gl: &glow::Context,
+    vertex_source: &str,
+    fragment_source: &str,
+) -> Result<GL::NativeProgram, String> {
+    let header = if gl.version().is_embedded {
+        "#version 300 es\nprecision mediump float;\n"
+    } else {
+        "#version 330\n"
+    };
+
+    let program = gl.create_program()?;
+
+    let mut shaders = Vec::with_capacity(2);
+    for (shader_type, source) in [
+        (GL::VERTEX_SHADER, vertex_source),
+        (GL::FRAGMENT_SHADER, fragment_source),
+    ] {
+        let shader = gl.create_shader(shader_type)?;
+        gl.shader_source(shader, &format!("{}{}", header, source));
+        gl.compile_shader(shader);
+        if !gl.get_shader_compile_status(shader) {
+            let log = gl.get_shader_info_log(shader);
+            gl.delete_shader(shader);
+            gl.delete_program(program);
+            return Err(format!("Failed to compile shader: {}", log));
+        }
+        gl.attach_shader(program, shader);
+        shaders.push(shader);
+    }
+
+    gl.link_program(program);
+    for shader in shaders {
+        gl.detach_shader(program, shader);
+        gl.delete_shader(shader);
+    }
+
+    if !gl.get_program_link_status(program) {
+        let log = gl.get_program_info_log(program);
+        gl.delete_program(program);
+        return Err(format!("Failed to link shader program: {}", log));
+    }
+
+    Ok(program)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_chosen_part_when_cropping_the_top_and_bottom() {
+        let source = PhysicalSize::new(1920, 1080);
+        let ultrawide = PhysicalSize::new(3840, 1080);
+
+        assert_eq!(
+            cover(source, ultrawide, CropAnchor::Top),
+            Crop {
+                scale: (1.0, 0.5),
+                center: (0.5, 0.75)
+            }
+        );
+        assert_eq!(
+            cover(source, ultrawide, CropAnchor::Center).center,
+            (0.5, 0.5)
+        );
+        assert_eq!(
+            cover(source, ultrawide, CropAnchor::Bottom).center,
+            (0.5, 0.25)
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_middle_when_cropping_the_sides() {
+        let crop = cover(
+            PhysicalSize::new(1920, 1080),
+            PhysicalSize::new(1080, 1080),
+            CropAnchor::Top,
+        );
+        assert_eq!(crop.center, (0.5, 0.5));
+        assert_eq!(crop.scale.1, 1.0);
+    }
+}
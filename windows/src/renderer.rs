@@ -0,0 +1,191 @@
+use std::{path, rc::Rc};
+
+use flux::Flux;
+use glow as GL;
+use winit::dpi::{LogicalSize, PhysicalSize};
+
+use crate::clock::SimulationTime;
+use crate::config::Config;
+use crate::postprocess::{Effects, Frame, PostProcess};
+
+// The length of the shorter side of the simulation’s coordinate space. This matches a 1080p
+// display at 100% scaling, so the pattern looks the same there as it did in logical pixels.
+const SIMULATION_SHORT_SIDE: f64 = 1080.0;
+
+// Flux and its post-processing pass, independent of the window and how frames are presented.
+//
+// Callers make the GL context current and pass in the framebuffer to draw to. The DXGI
+// swapchain, the plain GL surface, and the mirrored windows all go through this.
+pub struct FluxRenderer {
+    flux: Flux,
+    post_process: PostProcess,
+    // How many simulation updates to split each frame into
+    substeps: u32,
+    // Whether to use the same coordinate space at any resolution, instead of logical pixels
+    resolution_independent: bool,
+    simulation_time: SimulationTime,
+}
+
+impl FluxRenderer {
+    // Run Flux on a `width` by `height` framebuffer, in physical pixels, at 100% scaling. The
+    // desktop wallpaper color mode falls back to the default colors.
+    pub fn new(
+        gl: &Rc<glow::Context>,
+        width: u32,
+        height: u32,
+        config: &Config,
+    ) -> Result<Self, String> {
+        Self::for_window(gl, PhysicalSize::new(width, height), 1.0, config, None)
+    }
+
+    // Run Flux in a window with its own scale factor, sampling colors from `wallpaper` in the
+    // desktop wallpaper color mode.
+    pub fn for_window(
+        gl: &Rc<glow::Context>,
+        physical_size: PhysicalSize<u32>,
+        scale_factor: f64,
+        config: &Config,
+        wallpaper: Option<path::PathBuf>,
+    ) -> Result<Self, String> {
+        let mut post_process = PostProcess::new(
+            gl,
+            physical_size,
+            config.screensaver.render_scale,
+            config.screensaver.upscale_filter,
+        )?;
+        post_process.set_frame_clear(
+            config.screensaver.frame_clear,
+            config.screensaver.clear_color(),
+            config.screensaver.trail_persistence,
+        )?;
+        post_process.set_anti_aliasing(config.screensaver.anti_aliasing)?;
+        post_process.set_watermark(config);
+
+        let resolution_independent = config.flux.resolution_independent;
+        let simulation_size = simulation_size(physical_size, scale_factor, resolution_independent);
+        let render_size = post_process.render_size();
+        let settings = config.to_settings(wallpaper);
+        let flux = Flux::new(
+            gl,
+            simulation_size.width,
+            simulation_size.height,
+            render_size.width,
+            render_size.height,
+            &Rc::new(settings),
+        )
+        .map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            flux,
+            post_process,
+            substeps: config.flux.substeps.max(1),
+            resolution_independent,
+            simulation_time: SimulationTime::default(),
+        })
+    }
+
+    pub fn resize(&mut self, physical_size: PhysicalSize<u32>, scale_factor: f64) {
+        self.post_process.resize(physical_size);
+
+        let simulation_size =
+            simulation_size(physical_size, scale_factor, self.resolution_independent);
+        let render_size = self.post_process.render_size();
+        self.flux.resize(
+            simulation_size.width,
+            simulation_size.height,
+            render_size.width,
+            render_size.height,
+        );
+    }
+
+    // Advance the simulation to `timestamp`, in milliseconds, and draw a frame to `target`. After a
+    // stall, the simulation moves on by a normal frame’s worth rather than jumping to `timestamp`.
+    pub fn render(
+        &mut self,
+        target: Option<GL::NativeFramebuffer>,
+        timestamp: f64,
+        effects: &Effects,
+    ) {
+        let flux = &mut self.flux;
+        let last_time = self.simulation_time.current();
+        let time = self.simulation_time.advance(timestamp);
+        match last_time {
+            // Step through the time since the last frame in equal parts.
+            Some(last_time) if time > last_time && self.substeps > 1 => {
+                let step = (time - last_time) / self.substeps as f64;
+                for substep in 1..self.substeps {
+                    flux.compute(last_time + step * substep as f64);
+                }
+                flux.compute(time);
+            }
+            _ => flux.compute(time),
+        }
+
+        self.post_process.draw(target, effects, || flux.render());
+    }
+
+    // Keep each frame around so that other contexts sharing this one can draw it.
+    pub fn share_frame(&mut self) -> Frame {
+        self.post_process.share_frame()
+    }
+}
+
+// The size Flux lays the simulation out in. Flux spaces its lines and samples its noise in these
+// units, so keeping the shorter side the same length keeps the features the same size relative to
+// the display, whatever its resolution, scaling, or render scale.
+fn simulation_size(
+    physical_size: PhysicalSize<u32>,
+    scale_factor: f64,
+    resolution_independent: bool,
+) -> LogicalSize<u32> {
+    if !resolution_independent {
+        return physical_size.to_logical(scale_factor);
+    }
+
+    let short_side = physical_size.width.min(physical_size.height).max(1) as f64;
+    let scale = SIMULATION_SHORT_SIDE / short_side;
+    LogicalSize::new(
+        (physical_size.width as f64 * scale).round().max(1.0) as u32,
+        (physical_size.height as f64 * scale).round().max(1.0) as u32,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_uses_the_same_simulation_size_at_any_resolution() {
+        let full_hd = simulation_size(PhysicalSize::new(1920, 1080), 1.0, true);
+        let uhd = simulation_size(PhysicalSize::new(3840, 2160), 1.0, true);
+        let scaled_uhd = simulation_size(PhysicalSize::new(3840, 2160), 1.5, true);
+
+        assert_eq!(full_hd, LogicalSize::new(1920, 1080));
+        assert_eq!(uhd, full_hd);
+        assert_eq!(scaled_uhd, full_hd);
+    }
+
+    #[test]
+    fn it_keeps_the_aspect_ratio_of_wide_and_tall_surfaces() {
+        assert_eq!(
+            simulation_size(PhysicalSize::new(7680, 1440), 1.0, true),
+            LogicalSize::new(5760, 1080)
+        );
+        assert_eq!(
+            simulation_size(PhysicalSize::new(1440, 2560), 1.0, true),
+            LogicalSize::new(1080, 1920)
+        );
+    }
+
+    #[test]
+    fn it_uses_logical_pixels_when_asked_to() {
+        assert_eq!(
+            simulation_size(PhysicalSize::new(3840, 2160), 2.0, false),
+            LogicalSize::new(1920, 1080)
+        );
+        assert_eq!(
+            simulation_size(PhysicalSize::new(3840, 2160), 1.0, false),
+            LogicalSize::new(3840, 2160)
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use std::path;
+use std::time::{Duration, Instant, SystemTime};
+
+// How often to check the settings file for changes
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Notices when the settings file is saved, so that a running window can pick up changes made in
+// the settings window.
+pub struct SettingsWatcher {
+    path: path::PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl SettingsWatcher {
+    pub fn new(path: &path::Path) -> Self {
+        Self {
+            path: path.to_owned(),
+            modified: modified_time(path),
+            last_check: Instant::now(),
+        }
+    }
+
+    // Whether the file has changed since the last time it was checked. Checks at most once a
+    // second, since this runs every frame.
+    pub fn has_changed(&mut self, now: Instant) -> bool {
+        if now.saturating_duration_since(self.last_check) < CHECK_INTERVAL {
+            return false;
+        }
+        self.last_check = now;
+
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified_time(path: &path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -1,44 +1,166 @@
-use crate::config::{ColorMode, Config, FillMode};
+use crate::config::{
+    AntiAliasing, ColorMode, Config, CropAnchor, FillMode, FluxSettings, FrameClear,
+    MonitorSelection, NightMode, PowerMode, PresentMode, Problem, ScreensaverSettings,
+    SettingsTheme, SettingsWindowConfig, UpscaleFilter,
+};
+use crate::diagnostics::{Adapter, Diagnostics};
+use crate::palette::Color;
 
-use indoc::indoc;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, Instant};
 
 use iced::alignment::Horizontal;
 use iced::executor;
 use iced::theme;
-use iced::widget::{button, column, container, pick_list, row, text, vertical_space};
+use iced::widget::{
+    button, checkbox, column, container, horizontal_space, pick_list, row, scrollable, slider,
+    text, Row, Space,
+};
 use iced::window;
-use iced::{Application, Command, Element, Length, Padding, Theme};
+use iced::{Alignment, Application, Background, Command, Element, Length, Subscription, Theme};
 
-pub fn run(config: Config) -> iced::Result {
-    Config::run(iced::Settings {
-        flags: config,
+// The content stops growing past this width and stays centered in the window.
+const MAX_CONTENT_WIDTH: f32 = 640.0;
+
+// With auto-save on, wait this long after the last change before saving.
+const AUTO_SAVE_DELAY: Duration = Duration::from_secs(1);
+
+pub fn run(
+    config: Config,
+    last_run: Option<Diagnostics>,
+    log_file: Option<PathBuf>,
+) -> iced::Result {
+    let size = config.settings_window.size;
+    let position = initial_position(&config.settings_window, &available_monitors());
+    SettingsWindow::run(iced::Settings {
+        flags: (config, last_run, log_file),
         window: iced::window::Settings {
-            size: (500, 500),
-            resizable: false,
+            size,
+            position,
+            min_size: Some(SettingsWindowConfig::MIN_SIZE),
+            resizable: true,
             decorations: true,
             ..Default::default()
         },
         default_text_size: 16.0,
+        // Remember where the window was before closing it
+        exit_on_close_request: false,
         ..Default::default()
     })
 }
 
-#[derive(Debug, Clone, Copy)]
+struct SettingsWindow {
+    config: Config,
+    page: Page,
+
+    // Diagnostics recorded by the last run of the screensaver
+    last_run: Option<Diagnostics>,
+    adapters: Vec<Adapter>,
+    // The names of the connected monitors, for picking colors for each one
+    monitor_names: Vec<String>,
+    log_file: Option<PathBuf>,
+    // Why the log or the settings folder couldn’t be opened
+    open_error: Option<String>,
+
+    // When the last unsaved change was made, if auto-save is on
+    unsaved_since: Option<Instant>,
+
+    // Whether a full-screen preview is running
+    previewing: bool,
+    // Whether the advanced section is expanded
+    show_advanced: bool,
+    // Where the window was last moved to, in logical pixels
+    position: Option<(i32, i32)>,
+    // The Windows app theme, read when the window opens and when the theme setting changes
+    system_theme: Theme,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Page {
+    Settings,
+    About,
+}
+
+// Messages are `Clone` but not `Copy`, so that they can carry settings that own their data.
+#[derive(Debug, Clone)]
 pub enum Message {
     SetColorMode(ColorMode),
+    SetMonitorColorMode(String, MonitorColorMode),
     SetFillMode(FillMode),
+    SetCropAnchor(CropAnchor),
+    SetMonitorSelection(MonitorSelection),
+    SetTheme(SettingsTheme),
+    SetRenderScale(f32),
+    SetUpscaleFilter(UpscaleFilter),
+    SetAntiAliasing(AntiAliasing),
+    SetPresentMode(PresentMode),
+    SetSolidColorOnly(bool),
+    SetLineWidthScale(f32),
+    SetSubsteps(u32),
+    SetViscosity(f32),
+    SetVelocityDissipation(f32),
+    SetNoiseScale(f32),
+    SetNoiseOctaves(u32),
+    // Put the advanced settings back to how Flux ships
+    ResetAdvanced,
+    ShowAdvanced(bool),
+    SetBrightness(f32),
+    SetTrailLength(f32),
+    SetContrast(f32),
+    SetInvertColors(bool),
+    SetPixelShift(bool),
+    SetNightMode(bool),
+    SetNightStart(TimeOfDay),
+    SetNightEnd(TimeOfDay),
+    SetNightBrightness(f32),
+    SetAutoSave(bool),
+    // Pick a random look
+    Randomize,
+    SetRandomizeOnLaunch(bool),
+    WindowResized(u32, u32),
+    WindowMoved(i32, i32),
+    Tick(Instant),
+    ShowAbout,
+    ShowSettings,
+    OpenLog,
+    OpenSettingsFolder,
     Save,
+    // Save without closing the window
+    SaveQuietly,
     Cancel,
+    // Run the screensaver with the current settings, saved or not
+    PreviewFullscreen,
+    PreviewFinished,
 }
 
-impl Application for Config {
+impl Application for SettingsWindow {
     type Executor = executor::Default;
     type Message = Message;
     type Theme = Theme;
-    type Flags = Config;
+    type Flags = (Config, Option<Diagnostics>, Option<PathBuf>);
 
-    fn new(config: Config) -> (Self, Command<Message>) {
-        (config, Command::none())
+    fn new((config, last_run, log_file): Self::Flags) -> (Self, Command<Message>) {
+        (
+            SettingsWindow {
+                config,
+                page: Page::Settings,
+                last_run,
+                adapters: list_adapters(),
+                monitor_names: available_monitors()
+                    .into_iter()
+                    .filter_map(|monitor| monitor.name)
+                    .collect(),
+                log_file,
+                open_error: None,
+                unsaved_since: None,
+                previewing: false,
+                show_advanced: false,
+                position: None,
+                system_theme: system_theme(),
+            },
+            Command::none(),
+        )
     }
 
     fn title(&self) -> String {
@@ -48,57 +170,646 @@ impl Application for Config {
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::SetColorMode(new_color) => {
-                self.flux.color_mode = new_color;
+                self.config.flux.color_mode = new_color;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetMonitorColorMode(monitor, MonitorColorMode(color_mode)) => {
+                let monitor_color_modes = &mut self.config.flux.monitor_color_modes;
+                match color_mode {
+                    Some(color_mode) => monitor_color_modes.insert(monitor, color_mode),
+                    None => monitor_color_modes.remove(&monitor),
+                };
+                self.changed();
                 Command::none()
             }
 
             Message::SetFillMode(new_fill_mode) => {
-                self.platform.windows.fill_mode = new_fill_mode;
+                self.config.platform.windows.fill_mode = new_fill_mode;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetCropAnchor(new_crop_anchor) => {
+                self.config.platform.windows.crop_anchor = new_crop_anchor;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetMonitorSelection(new_monitor_selection) => {
+                self.config.platform.windows.monitor_selection = new_monitor_selection;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetTheme(new_theme) => {
+                self.config.settings_window.theme = new_theme;
+                self.system_theme = system_theme();
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetRenderScale(render_scale) => {
+                self.config.screensaver.render_scale = render_scale;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetUpscaleFilter(upscale_filter) => {
+                self.config.screensaver.upscale_filter = upscale_filter;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetAntiAliasing(anti_aliasing) => {
+                self.config.screensaver.anti_aliasing = anti_aliasing;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetPresentMode(present_mode) => {
+                self.config.screensaver.present_mode = present_mode;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetSolidColorOnly(solid_color_only) => {
+                self.config.screensaver.power_mode = if solid_color_only {
+                    PowerMode::SolidColor
+                } else {
+                    PowerMode::Animate
+                };
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetSubsteps(substeps) => {
+                self.config.flux.substeps = substeps;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetViscosity(viscosity) => {
+                self.config.flux.viscosity = Some(viscosity);
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetVelocityDissipation(velocity_dissipation) => {
+                self.config.flux.velocity_dissipation = Some(velocity_dissipation);
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetNoiseScale(noise_scale) => {
+                self.config.flux.noise_scale = noise_scale;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetNoiseOctaves(noise_octaves) => {
+                self.config.flux.noise_octaves = Some(noise_octaves);
+                self.changed();
+                Command::none()
+            }
+
+            Message::ResetAdvanced => {
+                self.config.flux.reset_advanced();
+                self.changed();
+                Command::none()
+            }
+
+            Message::ShowAdvanced(show_advanced) => {
+                self.show_advanced = show_advanced;
+                Command::none()
+            }
+
+            Message::SetLineWidthScale(line_width_scale) => {
+                self.config.flux.line_width_scale = line_width_scale;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetBrightness(brightness) => {
+                self.config.screensaver.brightness = brightness;
+                self.changed();
+                Command::none()
+            }
+
+            // No trails at all is a plain clear, which skips the extra texture for them.
+            Message::SetTrailLength(trail_length) => {
+                let screensaver = &mut self.config.screensaver;
+                if trail_length > 0.0 {
+                    screensaver.frame_clear = FrameClear::Trails;
+                    screensaver.trail_persistence = trail_length;
+                } else {
+                    screensaver.frame_clear = FrameClear::Clear;
+                }
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetContrast(contrast) => {
+                self.config.screensaver.contrast = contrast;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetInvertColors(invert_colors) => {
+                self.config.screensaver.invert_colors = invert_colors;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetPixelShift(pixel_shift) => {
+                self.config.screensaver.pixel_shift = pixel_shift;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetNightMode(enabled) => {
+                self.config.screensaver.night_mode.enabled = enabled;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetNightStart(TimeOfDay(minute)) => {
+                self.config.screensaver.night_mode.start_minute = minute;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetNightEnd(TimeOfDay(minute)) => {
+                self.config.screensaver.night_mode.end_minute = minute;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetNightBrightness(brightness) => {
+                self.config.screensaver.night_mode.brightness = brightness;
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetAutoSave(auto_save) => {
+                self.config.settings_window.auto_save = auto_save;
+                // Save the setting itself, so that it sticks even if the window is closed.
+                self.unsaved_since = None;
+                self.update(Message::SaveQuietly)
+            }
+
+            Message::Randomize => {
+                self.config.randomize(&mut rand::thread_rng());
+                self.changed();
+                Command::none()
+            }
+
+            Message::SetRandomizeOnLaunch(randomize_on_launch) => {
+                self.config.screensaver.randomize_on_launch = randomize_on_launch;
+                self.changed();
+                Command::none()
+            }
+
+            Message::Tick(now) => match self.unsaved_since {
+                Some(since) if now.saturating_duration_since(since) >= AUTO_SAVE_DELAY => {
+                    self.update(Message::SaveQuietly)
+                }
+                _ => Command::none(),
+            },
+
+            Message::WindowResized(width, height) => {
+                self.config.settings_window.size = (width, height);
+                Command::none()
+            }
+
+            Message::WindowMoved(x, y) => {
+                self.position = Some((x, y));
+                Command::none()
+            }
+
+            Message::ShowAbout => {
+                self.page = Page::About;
+                Command::none()
+            }
+
+            Message::ShowSettings => {
+                self.page = Page::Settings;
                 Command::none()
             }
 
             Message::Save => {
-                self.save().unwrap_or_else(|err| log::error!("{}", err));
+                self.remember_placement();
+                self.config
+                    .save()
+                    .unwrap_or_else(|err| log::error!("{}", err));
                 window::close()
             }
 
-            Message::Cancel => window::close(),
+            Message::SaveQuietly => {
+                self.unsaved_since = None;
+                self.remember_placement();
+                self.config
+                    .save()
+                    .unwrap_or_else(|err| log::error!("{}", err));
+                Command::none()
+            }
+
+            // Keep the window where the user put it, but leave the other settings as saved. A
+            // pending auto-save still goes through, since those changes were meant to stick.
+            //
+            // Closing the window from the title bar or the taskbar ends up here too.
+            Message::Cancel => {
+                self.remember_placement();
+                let result = match self.unsaved_since.take() {
+                    Some(_) => self.config.save(),
+                    None => self.save_placement(),
+                };
+                if let Err(err) = result {
+                    log::warn!("Failed to save on close: {}", err);
+                }
+                window::close()
+            }
+
+            Message::OpenLog => {
+                let result = match &self.log_file {
+                    Some(log_file) => open_log(log_file),
+                    None => Err("Flux doesn’t have a folder to keep its log in.".to_string()),
+                };
+                self.open_error = result.err();
+                Command::none()
+            }
+
+            Message::OpenSettingsFolder => {
+                let result = match self.config.location().and_then(Path::parent) {
+                    Some(settings_dir) => open_folder(settings_dir),
+                    None => Err("Flux doesn’t have a folder to keep its settings in.".to_string()),
+                };
+                self.open_error = result.err();
+                Command::none()
+            }
+
+            Message::PreviewFullscreen => match start_preview(&self.config) {
+                Ok(mut preview) => {
+                    self.previewing = true;
+                    Command::perform(
+                        async move {
+                            let _ = preview.wait();
+                        },
+                        |_| Message::PreviewFinished,
+                    )
+                }
+                Err(err) => {
+                    log::error!("Failed to start the preview: {}", err);
+                    Command::none()
+                }
+            },
+
+            Message::PreviewFinished => {
+                self.previewing = false;
+                let _ = std::fs::remove_file(preview_file());
+                window::gain_focus()
+            }
         }
     }
 
     fn view(&self) -> Element<Message> {
+        let content = match self.page {
+            Page::Settings => self.settings_view(),
+            Page::About => self.about_view(),
+        };
+
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .padding(24)
+            .into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let window_events = iced::subscription::events_with(|event, _status| match event {
+            iced::Event::Window(window::Event::Resized { width, height }) => {
+                Some(Message::WindowResized(width, height))
+            }
+            iced::Event::Window(window::Event::Moved { x, y }) => Some(Message::WindowMoved(x, y)),
+            iced::Event::Window(window::Event::CloseRequested) => Some(Message::Cancel),
+            _ => None,
+        });
+
+        // Only tick while there’s something to save, so the window stays idle otherwise.
+        let auto_save = match self.unsaved_since {
+            Some(_) => window::frames().map(Message::Tick),
+            None => Subscription::none(),
+        };
+
+        Subscription::batch([window_events, auto_save])
+    }
+
+    fn theme(&self) -> Theme {
+        match self.config.settings_window.theme {
+            SettingsTheme::Dark => Theme::Dark,
+            SettingsTheme::Light => Theme::Light,
+            SettingsTheme::System => self.system_theme.clone(),
+        }
+    }
+}
+
+impl SettingsWindow {
+    // Schedule an auto-save, restarting the delay if one is already pending.
+    fn changed(&mut self) {
+        if self.config.settings_window.auto_save {
+            self.unsaved_since = Some(Instant::now());
+        }
+    }
+
+    // Record where the window is and which monitor it’s on. Positions off every monitor, like the
+    // one Windows parks minimized windows at, are ignored.
+    fn remember_placement(&mut self) {
+        let position = match self.position {
+            Some(position) => position,
+            None => return,
+        };
+
+        if let Some(monitor) = available_monitors()
+            .into_iter()
+            .find(|monitor| monitor.contains(position))
+        {
+            self.config.settings_window.position = Some(position);
+            self.config.settings_window.monitor = monitor.name;
+        }
+    }
+
+    // Save the window’s size and position on top of the saved settings, without any unsaved
+    // changes.
+    fn save_placement(&self) -> Result<(), Problem> {
+        let mut saved = self.config.reload()?;
+        saved.settings_window.size = self.config.settings_window.size;
+        saved.settings_window.position = self.config.settings_window.position;
+        saved.settings_window.monitor = self.config.settings_window.monitor.clone();
+        saved.save()
+    }
+
+    fn settings_view(&self) -> Element<Message> {
         let color_list = pick_list(
-            &ColorMode::ALL[..],
-            Some(self.flux.color_mode),
+            ColorMode::all(),
+            Some(self.config.flux.color_mode.clone()),
             Message::SetColorMode,
         )
         .padding(8);
 
+        let randomize_button = button(text("Surprise me"))
+            .style(theme::Button::Secondary)
+            .padding(8)
+            .on_press(Message::Randomize);
+        let randomize_checkbox = checkbox(
+            "Pick a random look every time the screensaver starts",
+            self.config.screensaver.randomize_on_launch,
+            Message::SetRandomizeOnLaunch,
+        );
+
         let color_section = column![
             text("Colors").size(20.0),
             "Choose from a selection of presets or use your desktop wallpaper.",
-            color_list
+            row![
+                color_list,
+                swatches(&self.config.flux.color_mode),
+                horizontal_space(Length::Fill),
+                randomize_button,
+            ]
+            .spacing(12)
+            .align_items(Alignment::Center),
+            text(self.config.flux.color_mode.description()),
+            randomize_checkbox,
         ]
         .spacing(12);
 
         let fill_list = pick_list(
             &FillMode::ALL[..],
-            Some(self.platform.windows.fill_mode),
+            Some(self.config.platform.windows.fill_mode),
             Message::SetFillMode,
         )
         .padding(8);
+        let monitor_selection_list = pick_list(
+            &MonitorSelection::ALL[..],
+            Some(self.config.platform.windows.monitor_selection),
+            Message::SetMonitorSelection,
+        )
+        .padding(8);
 
-        let fill_section = column![
+        let fill_mode_descriptions = FillMode::ALL
+            .iter()
+            .map(|fill_mode| format!("{}: {}", fill_mode, fill_mode.description()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut fill_section = column![
             text("Fill mode").size(20.0),
             "Configure how Flux works across multiple monitors.",
-            indoc! {"
-                None: Each monitor is a separate surface.
-                Span: Combines any matching adjacent monitors.
-                Fill: Combines all monitors into a single seamless surface.
-            "},
+            text(fill_mode_descriptions),
             fill_list,
         ]
         .spacing(12);
+        // Only mirrors crop the frame, when a display has a different shape from the one it shows.
+        if self.config.platform.windows.fill_mode == FillMode::Mirror {
+            let crop_anchor_list = pick_list(
+                &CropAnchor::ALL[..],
+                Some(self.config.platform.windows.crop_anchor),
+                Message::SetCropAnchor,
+            )
+            .padding(8);
+            fill_section = fill_section.push(
+                "Choose what to keep when a wider monitor crops the top and bottom off the mirrored \
+                 animation.",
+            );
+            fill_section = fill_section.push(crop_anchor_list);
+        }
+        fill_section = fill_section
+            .push(
+                "Choose which monitors to cover. The primary monitor is whichever one Windows \
+                 treats as the main display when the screensaver starts.",
+            )
+            .push(monitor_selection_list);
+        if self.monitor_names.len() > 1 {
+            fill_section = fill_section.push(
+                "Pick different colors for each monitor. These apply when each monitor has a \
+                 window of its own.",
+            );
+            for name in &self.monitor_names {
+                let selected = self.config.flux.monitor_color_modes.get(name).cloned();
+                let monitor = name.clone();
+                let monitor_color_list = pick_list(
+                    MonitorColorMode::choices(),
+                    Some(MonitorColorMode(selected)),
+                    move |choice| Message::SetMonitorColorMode(monitor.clone(), choice),
+                )
+                .padding(8);
+                fill_section = fill_section.push(
+                    row![
+                        text(monitor_label(name)).width(Length::Fixed(120.0)),
+                        monitor_color_list
+                    ]
+                    .spacing(12)
+                    .align_items(Alignment::Center),
+                );
+            }
+        }
+
+        let theme_list = pick_list(
+            &SettingsTheme::ALL[..],
+            Some(self.config.settings_window.theme),
+            Message::SetTheme,
+        )
+        .padding(8);
+
+        let render_scale = self.config.screensaver.render_scale;
+        let render_scale_slider = slider(
+            ScreensaverSettings::MIN_RENDER_SCALE..=1.0,
+            render_scale,
+            Message::SetRenderScale,
+        )
+        .step(0.05);
+        let upscale_filter_list = pick_list(
+            &UpscaleFilter::ALL[..],
+            Some(self.config.screensaver.upscale_filter),
+            Message::SetUpscaleFilter,
+        )
+        .padding(8);
+        let anti_aliasing_list = pick_list(
+            &AntiAliasing::ALL[..],
+            Some(self.config.screensaver.anti_aliasing),
+            Message::SetAntiAliasing,
+        )
+        .padding(8);
+        let present_mode_list = pick_list(
+            &PresentMode::ALL[..],
+            Some(self.config.screensaver.present_mode),
+            Message::SetPresentMode,
+        )
+        .padding(8);
+        let solid_color_checkbox = checkbox(
+            "Show a slowly changing color instead of the animation, to save the most power",
+            self.config.screensaver.power_mode == PowerMode::SolidColor,
+            Message::SetSolidColorOnly,
+        );
+
+        let performance_section = column![
+            text("Performance").size(20.0),
+            "Render at a lower resolution to help slower graphics cards keep up on large displays.",
+            row![
+                render_scale_slider,
+                text(format!("{:.0}%", render_scale * 100.0)).width(Length::Fixed(48.0)),
+            ]
+            .spacing(12)
+            .align_items(Alignment::Center),
+            upscale_filter_list,
+            "Smooth out jagged lines. FXAA costs little, while MSAA looks sharper but is slower.",
+            anti_aliasing_list,
+            "Skip vsync to get frames on screen sooner. Flux still draws no faster than the \
+             display refreshes, but there may be some tearing.",
+            present_mode_list,
+            solid_color_checkbox,
+        ]
+        .spacing(12);
+
+        let line_width_scale = self.config.flux.line_width_scale;
+        let line_width_slider = slider(
+            FluxSettings::LINE_WIDTH_SCALE_RANGE,
+            line_width_scale,
+            Message::SetLineWidthScale,
+        )
+        .step(0.05);
+        let brightness = self.config.screensaver.brightness;
+        let brightness_slider = slider(
+            ScreensaverSettings::BRIGHTNESS_RANGE,
+            brightness,
+            Message::SetBrightness,
+        )
+        .step(0.05);
+        let contrast = self.config.screensaver.contrast;
+        let contrast_slider = slider(
+            ScreensaverSettings::CONTRAST_RANGE,
+            contrast,
+            Message::SetContrast,
+        )
+        .step(0.05);
+        let trail_length = match self.config.screensaver.frame_clear {
+            FrameClear::Trails => self.config.screensaver.trail_persistence,
+            FrameClear::Clear => 0.0,
+        };
+        let trail_length_slider = slider(
+            ScreensaverSettings::TRAIL_PERSISTENCE_RANGE,
+            trail_length,
+            Message::SetTrailLength,
+        )
+        .step(0.05);
+        let trail_length_label = if trail_length > 0.0 {
+            format!("{:.0}%", trail_length * 100.0)
+        } else {
+            "Off".to_string()
+        };
+        let invert_checkbox = checkbox(
+            "Invert colors",
+            self.config.screensaver.invert_colors,
+            Message::SetInvertColors,
+        );
+        let pixel_shift_checkbox = checkbox(
+            "Shift the picture slowly to protect OLED displays",
+            self.config.screensaver.pixel_shift,
+            Message::SetPixelShift,
+        );
+
+        let picture_section = column![
+            text("Picture").size(20.0),
+            "Adjust the lines and the final picture, for a dimmer or higher-contrast look.",
+            row![
+                text("Line width").width(Length::Fixed(96.0)),
+                line_width_slider,
+                text(format!("{:.0}%", line_width_scale * 100.0)).width(Length::Fixed(48.0)),
+            ]
+            .spacing(12)
+            .align_items(Alignment::Center),
+            row![
+                text("Brightness").width(Length::Fixed(96.0)),
+                brightness_slider,
+                text(format!("{:.0}%", brightness * 100.0)).width(Length::Fixed(48.0)),
+            ]
+            .spacing(12)
+            .align_items(Alignment::Center),
+            row![
+                text("Contrast").width(Length::Fixed(96.0)),
+                contrast_slider,
+                text(format!("{:.0}%", contrast * 100.0)).width(Length::Fixed(48.0)),
+            ]
+            .spacing(12)
+            .align_items(Alignment::Center),
+            row![
+                text("Trails").width(Length::Fixed(96.0)),
+                trail_length_slider,
+                text(trail_length_label).width(Length::Fixed(48.0)),
+            ]
+            .spacing(12)
+            .align_items(Alignment::Center),
+            invert_checkbox,
+            pixel_shift_checkbox,
+        ]
+        .spacing(12);
+
+        let night_section = self.night_section();
+        let advanced_section = self.advanced_section();
+
+        let auto_save_checkbox = checkbox(
+            "Auto-save changes",
+            self.config.settings_window.auto_save,
+            Message::SetAutoSave,
+        );
+
+        let theme_section = column![
+            text("Appearance").size(20.0),
+            "Choose the theme for this settings window.",
+            theme_list,
+            auto_save_checkbox,
+        ]
+        .spacing(12);
 
         let save_button = button(text("Save").horizontal_alignment(Horizontal::Center))
             .padding(8)
@@ -109,27 +820,713 @@ impl Application for Config {
             .padding(8)
             .width(Length::Fixed(96.0))
             .on_press(Message::Cancel);
-        let button_row = container(row![save_button, cancel_button].spacing(12));
+        // Disabled while a preview is already running
+        let mut preview_button = button(text("Preview").horizontal_alignment(Horizontal::Center))
+            .style(theme::Button::Secondary)
+            .padding(8)
+            .width(Length::Fixed(96.0));
+        if !self.previewing {
+            preview_button = preview_button.on_press(Message::PreviewFullscreen);
+        }
+        let about_button = button(text("About").horizontal_alignment(Horizontal::Center))
+            .style(theme::Button::Text)
+            .padding(8)
+            .width(Length::Fixed(96.0))
+            .on_press(Message::ShowAbout);
+        let button_row = container(
+            row![
+                save_button,
+                cancel_button,
+                preview_button,
+                horizontal_space(Length::Fill),
+                about_button
+            ]
+            .width(Length::Fill)
+            .spacing(12),
+        );
+
+        // Let the sections scroll when the window is too short to fit them, but keep the buttons
+        // pinned to the bottom.
+        let sections = scrollable(
+            container(
+                column![
+                    color_section,
+                    picture_section,
+                    night_section,
+                    fill_section,
+                    performance_section,
+                    advanced_section,
+                    theme_section
+                ]
+                .spacing(36),
+            )
+            .width(Length::Fill)
+            .center_x(),
+        )
+        .height(Length::Fill);
+
+        column![sections, button_row]
+            .max_width(MAX_CONTENT_WIDTH)
+            .height(Length::Fill)
+            .spacing(36)
+            .into()
+    }
+
+    // Dimming on a schedule. The times only show once it’s turned on.
+    fn night_section(&self) -> Element<Message> {
+        let night_mode = &self.config.screensaver.night_mode;
 
-        let content = column![
-            color_section,
-            fill_section,
-            vertical_space(Length::Fill),
-            button_row
+        let enabled_checkbox = checkbox("Dim at night", night_mode.enabled, Message::SetNightMode);
+        let mut section = column![
+            text("Night").size(20.0),
+            "Gradually dim the picture during the night, and bring it back in the morning.",
+            enabled_checkbox,
         ]
-        .height(Length::Fill)
-        .spacing(36);
+        .spacing(12);
 
-        container(content)
-            .width(Length::Fill)
+        if night_mode.enabled {
+            let start_list = pick_list(
+                TimeOfDay::choices(),
+                Some(TimeOfDay(night_mode.start_minute)),
+                Message::SetNightStart,
+            )
+            .padding(8);
+            let end_list = pick_list(
+                TimeOfDay::choices(),
+                Some(TimeOfDay(night_mode.end_minute)),
+                Message::SetNightEnd,
+            )
+            .padding(8);
+            let brightness_slider = slider(
+                NightMode::BRIGHTNESS_RANGE,
+                night_mode.brightness,
+                Message::SetNightBrightness,
+            )
+            .step(0.05);
+
+            section = section
+                .push(
+                    row![text("From"), start_list, text("to"), end_list]
+                        .spacing(12)
+                        .align_items(Alignment::Center),
+                )
+                .push(
+                    row![
+                        text("Brightness").width(Length::Fixed(96.0)),
+                        brightness_slider,
+                        text(format!("{:.0}%", night_mode.brightness * 100.0))
+                            .width(Length::Fixed(48.0)),
+                    ]
+                    .spacing(12)
+                    .align_items(Alignment::Center),
+                );
+        }
+
+        section.into()
+    }
+
+    // Fluid parameters that are easy to get wrong, so they’re hidden until asked for.
+    fn advanced_section(&self) -> Element<Message> {
+        let toggle_label = if self.show_advanced { "Hide" } else { "Show" };
+        let header = row![
+            text("Advanced").size(20.0),
+            horizontal_space(Length::Fill),
+            button(text(toggle_label))
+                .style(theme::Button::Text)
+                .on_press(Message::ShowAdvanced(!self.show_advanced)),
+        ]
+        .align_items(Alignment::Center);
+
+        if !self.show_advanced {
+            return column![header].into();
+        }
+
+        let flux = &self.config.flux;
+        let substeps_slider = slider(
+            FluxSettings::SUBSTEPS_RANGE,
+            flux.substeps,
+            Message::SetSubsteps,
+        )
+        .step(1u32);
+        let viscosity = flux.viscosity();
+        let viscosity_slider = slider(
+            FluxSettings::VISCOSITY_RANGE,
+            viscosity,
+            Message::SetViscosity,
+        )
+        .step(0.1);
+        let velocity_dissipation = flux.velocity_dissipation();
+        let velocity_dissipation_slider = slider(
+            FluxSettings::VELOCITY_DISSIPATION_RANGE,
+            velocity_dissipation,
+            Message::SetVelocityDissipation,
+        )
+        .step(0.01);
+        let noise_scale_slider = slider(
+            FluxSettings::NOISE_SCALE_RANGE,
+            flux.noise_scale,
+            Message::SetNoiseScale,
+        )
+        .step(0.05);
+        let noise_octaves = flux.noise_octaves();
+        let noise_octaves_slider = slider(
+            FluxSettings::NOISE_OCTAVES_RANGE,
+            noise_octaves,
+            Message::SetNoiseOctaves,
+        )
+        .step(1u32);
+        let reset_button = button(text("Reset to defaults"))
+            .style(theme::Button::Secondary)
+            .padding(8)
+            .on_press(Message::ResetAdvanced);
+
+        column![
+            header,
+            "Tune the fluid simulation. Substeps help if the motion looks jittery, at a GPU \
+             cost. The noise stirs the fluid: a larger scale makes tighter swirls.",
+            row![
+                text("Substeps").width(Length::Fixed(96.0)),
+                substeps_slider,
+                text(flux.substeps.to_string()).width(Length::Fixed(48.0)),
+            ]
+            .spacing(12)
+            .align_items(Alignment::Center),
+            row![
+                text("Viscosity").width(Length::Fixed(96.0)),
+                viscosity_slider,
+                text(format!("{:.1}", viscosity)).width(Length::Fixed(48.0)),
+            ]
+            .spacing(12)
+            .align_items(Alignment::Center),
+            row![
+                text("Dissipation").width(Length::Fixed(96.0)),
+                velocity_dissipation_slider,
+                text(format!("{:.2}", velocity_dissipation)).width(Length::Fixed(48.0)),
+            ]
+            .spacing(12)
+            .align_items(Alignment::Center),
+            row![
+                text("Noise scale").width(Length::Fixed(96.0)),
+                noise_scale_slider,
+                text(format!("{:.0}%", flux.noise_scale * 100.0)).width(Length::Fixed(48.0)),
+            ]
+            .spacing(12)
+            .align_items(Alignment::Center),
+            row![
+                text("Octaves").width(Length::Fixed(96.0)),
+                noise_octaves_slider,
+                text(noise_octaves.to_string()).width(Length::Fixed(48.0)),
+            ]
+            .spacing(12)
+            .align_items(Alignment::Center),
+            reset_button,
+        ]
+        .spacing(12)
+        .into()
+    }
+
+    fn about_view(&self) -> Element<Message> {
+        let version_section = column![
+            text("Flux").size(20.0),
+            text(format!("Version {}", env!("CARGO_PKG_VERSION"))),
+        ]
+        .spacing(12);
+
+        let adapters = if self.adapters.is_empty() {
+            "No graphics adapters found.".to_string()
+        } else {
+            self.adapters
+                .iter()
+                .map(|adapter| adapter.to_string())
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+        let adapter_section =
+            column![text("Graphics adapters").size(20.0), text(adapters)].spacing(12);
+
+        let last_run = match &self.last_run {
+            Some(diagnostics) => diagnostics.to_string(),
+            None => "The screensaver hasn’t run yet.".to_string(),
+        };
+        let last_run_section = column![text("Last run").size(20.0), text(last_run)].spacing(12);
+
+        let mut support_section = column![
+            text("Support").size(20.0),
+            "Attach the log to a bug report, or check the settings file by hand.",
+            row![
+                button(text("Open log"))
+                    .style(theme::Button::Secondary)
+                    .padding(8)
+                    .on_press(Message::OpenLog),
+                button(text("Open settings folder"))
+                    .style(theme::Button::Secondary)
+                    .padding(8)
+                    .on_press(Message::OpenSettingsFolder),
+            ]
+            .spacing(12),
+        ]
+        .spacing(12);
+        if let Some(open_error) = &self.open_error {
+            support_section = support_section.push(text(open_error));
+        }
+
+        let back_button = button(text("Back").horizontal_alignment(Horizontal::Center))
+            .padding(8)
+            .width(Length::Fixed(96.0))
+            .on_press(Message::ShowSettings);
+
+        let sections = scrollable(
+            container(
+                column![
+                    version_section,
+                    adapter_section,
+                    last_run_section,
+                    support_section
+                ]
+                .spacing(36),
+            )
+            .width(Length::Fill),
+        )
+        .height(Length::Fill);
+
+        column![sections, container(back_button)]
+            .max_width(MAX_CONTENT_WIDTH)
             .height(Length::Fill)
-            .center_x()
-            .center_y()
-            .padding(24)
+            .spacing(36)
             .into()
     }
+}
 
-    fn theme(&self) -> Theme {
-        Theme::Dark
+// A row of color swatches previewing a color mode.
+fn swatches<'a>(color_mode: &ColorMode) -> Element<'a, Message> {
+    let colors = color_mode.palette().unwrap_or_default();
+
+    colors
+        .iter()
+        .fold(Row::new().spacing(4), |row, color| {
+            row.push(
+                container(Space::new(Length::Fixed(20.0), Length::Fixed(20.0)))
+                    .style(theme::Container::Custom(Box::new(Swatch(*color)))),
+            )
+        })
+        .into()
+}
+
+struct Swatch(Color);
+
+impl container::StyleSheet for Swatch {
+    type Style = Theme;
+
+    fn appearance(&self, _style: &Self::Style) -> container::Appearance {
+        let [r, g, b] = self.0;
+        container::Appearance {
+            background: Some(Background::Color(iced::Color::from_rgb(r, g, b))),
+            border_radius: 4.0.into(),
+            ..Default::default()
+        }
+    }
+}
+
+// Where the settings for a preview are kept while it runs
+fn preview_file() -> PathBuf {
+    std::env::temp_dir().join("flux-preview.json")
+}
+
+// Open the log, creating it first if nothing has been logged yet.
+fn open_log(log_file: &Path) -> Result<(), String> {
+    let created = log_file
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| {
+            std::fs::OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(log_file)
+        });
+    if let Err(err) = created {
+        return Err(format!(
+            "Can’t create the log at {}: {}",
+            log_file.display(),
+            err
+        ));
+    }
+
+    open_path(log_file)
+}
+
+// Open the settings folder, which doesn’t exist until the settings are first saved.
+fn open_folder(dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|err| format!("Can’t create the folder {}: {}", dir.display(), err))?;
+
+    open_path(dir)
+}
+
+#[cfg(windows)]
+fn open_path(path: &Path) -> Result<(), String> {
+    crate::platform::windows::shell::open(path)
+}
+
+#[cfg(not(windows))]
+fn open_path(path: &Path) -> Result<(), String> {
+    process::Command::new("xdg-open")
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|err| format!("Can’t open {}: {}", path.display(), err))
+}
+
+// Launch the screensaver in a separate process with a copy of the current settings. It exits
+// on input, like the real thing.
+fn start_preview(config: &Config) -> Result<process::Child, String> {
+    let settings_file = preview_file();
+    config
+        .save_to(&settings_file)
+        .map_err(|err| err.to_string())?;
+
+    let exe = std::env::current_exe().map_err(|err| err.to_string())?;
+    process::Command::new(exe)
+        .arg("/s")
+        .arg("--preview")
+        .arg("--settings-file")
+        .arg(&settings_file)
+        .spawn()
+        .map_err(|err| err.to_string())
+}
+
+// A time of day for the night schedule, in minutes after midnight
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeOfDay(u32);
+
+impl TimeOfDay {
+    // The times to pick from, every half hour. Times set in the settings file show up as-is.
+    fn choices() -> Vec<TimeOfDay> {
+        (0..48).map(|half_hour| TimeOfDay(half_hour * 30)).collect()
+    }
+}
+
+impl std::fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+// The color mode for a single monitor, or `None` to use the main one
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MonitorColorMode(Option<ColorMode>);
+
+impl MonitorColorMode {
+    fn choices() -> Vec<MonitorColorMode> {
+        std::iter::once(MonitorColorMode(None))
+            .chain(
+                ColorMode::all()
+                    .into_iter()
+                    .map(|color_mode| MonitorColorMode(Some(color_mode))),
+            )
+            .collect()
+    }
+}
+
+impl std::fmt::Display for MonitorColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some(color_mode) => write!(f, "{}", color_mode),
+            None => write!(f, "Same as the main colors"),
+        }
+    }
+}
+
+// Windows names monitors like `\\.\DISPLAY1`. Leave out the prefix, which only gets in the way.
+fn monitor_label(name: &str) -> &str {
+    name.strip_prefix(r"\\.\").unwrap_or(name)
+}
+
+// A connected monitor, in logical pixels to match the window position
+#[derive(Clone, Debug, PartialEq)]
+struct Monitor {
+    name: Option<String>,
+    position: (i32, i32),
+    size: (u32, u32),
+}
+
+impl Monitor {
+    fn contains(&self, (x, y): (i32, i32)) -> bool {
+        let (left, top) = self.position;
+        let (width, height) = self.size;
+        x >= left && y >= top && x < left + width as i32 && y < top + height as i32
+    }
+
+    // Move a window of `size` at `position` so that it fits on this monitor, as far as possible.
+    fn clamp(&self, (x, y): (i32, i32), (width, height): (u32, u32)) -> (i32, i32) {
+        let (left, top) = self.position;
+        let right = (left + self.size.0 as i32 - width as i32).max(left);
+        let bottom = (top + self.size.1 as i32 - height as i32).max(top);
+        (x.clamp(left, right), y.clamp(top, bottom))
+    }
+}
+
+// Reopen the window where it was last closed. If its monitor has since been disconnected, fall
+// back to whichever connected monitor the position lands on, or the first one.
+fn initial_position(settings: &SettingsWindowConfig, monitors: &[Monitor]) -> window::Position {
+    let position = match settings.position {
+        Some(position) => position,
+        None => return window::Position::default(),
+    };
+
+    let monitor = monitors
+        .iter()
+        .find(|monitor| monitor.name.is_some() && monitor.name == settings.monitor)
+        .or_else(|| monitors.iter().find(|monitor| monitor.contains(position)))
+        .or_else(|| monitors.first());
+
+    match monitor {
+        Some(monitor) => {
+            let (x, y) = monitor.clamp(position, settings.size);
+            window::Position::Specific(x, y)
+        }
+        None => window::Position::default(),
+    }
+}
+
+#[cfg(windows)]
+fn available_monitors() -> Vec<Monitor> {
+    crate::winit_compat::available_monitors()
+        .map(|monitor| {
+            let scale_factor = monitor.scale_factor();
+            let position = monitor.position().to_logical::<i32>(scale_factor);
+            let size = monitor.size().to_logical::<u32>(scale_factor);
+            Monitor {
+                name: monitor.name(),
+                position: (position.x, position.y),
+                size: (size.width, size.height),
+            }
+        })
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn available_monitors() -> Vec<Monitor> {
+    Vec::new()
+}
+
+#[cfg(windows)]
+fn list_adapters() -> Vec<Adapter> {
+    crate::platform::windows::adapter::enumerate().unwrap_or_else(|err| {
+        log::warn!("Failed to list graphics adapters: {}", err);
+        Vec::new()
+    })
+}
+
+#[cfg(not(windows))]
+fn list_adapters() -> Vec<Adapter> {
+    Vec::new()
+}
+
+#[cfg(windows)]
+fn system_theme() -> Theme {
+    match crate::platform::windows::theme::apps_use_light_theme() {
+        Some(true) => Theme::Light,
+        _ => Theme::Dark,
+    }
+}
+
+#[cfg(not(windows))]
+fn system_theme() -> Theme {
+    Theme::Dark
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::palette::PaletteName;
+
+    fn new_settings_window() -> SettingsWindow {
+        let (settings_window, _) = SettingsWindow::new((Config::default(), None, None));
+        settings_window
+    }
+
+    #[test]
+    fn it_applies_setting_changes() {
+        let mut settings_window = new_settings_window();
+        let _ = settings_window.update(Message::SetColorMode(ColorMode::DesktopImage));
+        let _ = settings_window.update(Message::SetFillMode(FillMode::Mirror));
+
+        assert_eq!(
+            settings_window.config.flux.color_mode,
+            ColorMode::DesktopImage
+        );
+        assert_eq!(
+            settings_window.config.platform.windows.fill_mode,
+            FillMode::Mirror
+        );
+
+        let _ = settings_window.update(Message::SetCropAnchor(CropAnchor::Top));
+        assert_eq!(
+            settings_window.config.platform.windows.crop_anchor,
+            CropAnchor::Top
+        );
+
+        let _ = settings_window.update(Message::SetMonitorSelection(MonitorSelection::PrimaryOnly));
+        assert_eq!(
+            settings_window.config.platform.windows.monitor_selection,
+            MonitorSelection::PrimaryOnly
+        );
+    }
+
+    #[test]
+    fn it_turns_trails_off_at_zero_length() {
+        let mut settings_window = new_settings_window();
+        let _ = settings_window.update(Message::SetTrailLength(0.5));
+        assert_eq!(
+            settings_window.config.screensaver.frame_clear,
+            FrameClear::Trails
+        );
+        assert_eq!(settings_window.config.screensaver.trail_persistence, 0.5);
+
+        let _ = settings_window.update(Message::SetTrailLength(0.0));
+        assert_eq!(
+            settings_window.config.screensaver.frame_clear,
+            FrameClear::Clear
+        );
+    }
+
+    #[test]
+    fn it_resets_only_the_advanced_settings() {
+        let mut settings_window = new_settings_window();
+        let _ = settings_window.update(Message::SetBrightness(0.5));
+        let _ = settings_window.update(Message::SetViscosity(2.0));
+        let _ = settings_window.update(Message::SetSubsteps(4));
+
+        let _ = settings_window.update(Message::ResetAdvanced);
+
+        assert_eq!(settings_window.config.flux.viscosity, None);
+        assert_eq!(settings_window.config.flux.substeps, 1);
+        assert_eq!(settings_window.config.screensaver.brightness, 0.5);
+    }
+
+    fn monitor(name: &str, position: (i32, i32)) -> Monitor {
+        Monitor {
+            name: Some(name.to_string()),
+            position,
+            size: (1920, 1080),
+        }
+    }
+
+    #[test]
+    fn it_reopens_on_the_same_monitor() {
+        let monitors = [monitor("left", (0, 0)), monitor("right", (1920, 0))];
+        let settings = SettingsWindowConfig {
+            position: Some((2000, 100)),
+            monitor: Some("right".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            initial_position(&settings, &monitors),
+            window::Position::Specific(2000, 100)
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_window_on_a_connected_monitor() {
+        let monitors = [monitor("left", (0, 0))];
+        let settings = SettingsWindowConfig {
+            size: (500, 500),
+            position: Some((2000, 100)),
+            monitor: Some("right".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            initial_position(&settings, &monitors),
+            window::Position::Specific(1420, 100)
+        );
+    }
+
+    #[test]
+    fn it_uses_the_default_position_without_a_saved_one() {
+        let monitors = [monitor("left", (0, 0))];
+        assert_eq!(
+            initial_position(&SettingsWindowConfig::default(), &monitors),
+            window::Position::Default
+        );
+    }
+
+    #[test]
+    fn it_randomizes_without_touching_the_other_settings() {
+        let mut settings_window = new_settings_window();
+        let _ = settings_window.update(Message::SetBrightness(0.5));
+        let _ = settings_window.update(Message::Randomize);
+
+        assert_eq!(settings_window.config.screensaver.brightness, 0.5);
+        assert!(settings_window.config.sanitize().is_empty());
+    }
+
+    #[test]
+    fn it_finishes_a_pending_auto_save_on_close() {
+        let mut settings_window = new_settings_window();
+        let _ = settings_window.update(Message::SetAutoSave(true));
+        let _ = settings_window.update(Message::SetBrightness(0.5));
+        assert!(settings_window.unsaved_since.is_some());
+
+        let _ = settings_window.update(Message::Cancel);
+        assert!(settings_window.unsaved_since.is_none());
+    }
+
+    #[test]
+    fn it_shows_times_on_a_24_hour_clock() {
+        assert_eq!(TimeOfDay(22 * 60 + 30).to_string(), "22:30");
+        assert_eq!(TimeOfDay(7 * 60).to_string(), "07:00");
+    }
+
+    #[test]
+    fn it_falls_back_to_the_main_colors_on_a_monitor() {
+        let mut settings_window = new_settings_window();
+        let monitor = r"\\.\DISPLAY2".to_string();
+        let color_mode = MonitorColorMode(Some(ColorMode::DesktopImage));
+
+        let _ = settings_window.update(Message::SetMonitorColorMode(monitor.clone(), color_mode));
+        assert_eq!(
+            settings_window
+                .config
+                .flux
+                .monitor_color_modes
+                .get(&monitor),
+            Some(&ColorMode::DesktopImage)
+        );
+
+        let _ = settings_window.update(Message::SetMonitorColorMode(
+            monitor,
+            MonitorColorMode(None),
+        ));
+        assert!(settings_window.config.flux.monitor_color_modes.is_empty());
+        assert_eq!(monitor_label(r"\\.\DISPLAY2"), "DISPLAY2");
+    }
+
+    #[test]
+    fn it_handles_messages_that_own_their_data() {
+        let color_mode = ColorMode::Custom(PaletteName::new("Ocean"));
+        let message = Message::SetMonitorColorMode(
+            r"\\.\DISPLAY2".to_string(),
+            MonitorColorMode(Some(color_mode.clone())),
+        );
+
+        let mut original = new_settings_window();
+        let _ = original.update(message.clone());
+        let _ = original.update(Message::SetColorMode(color_mode.clone()));
+        let mut cloned = new_settings_window();
+        let _ = cloned.update(message);
+        let _ = cloned.update(Message::SetColorMode(color_mode.clone()));
+
+        assert_eq!(original.config, cloned.config);
+        assert_eq!(original.config.flux.color_mode, color_mode);
+        assert_eq!(
+            original
+                .config
+                .flux
+                .monitor_color_modes
+                .get(r"\\.\DISPLAY2"),
+            Some(&color_mode)
+        );
     }
 }
@@ -0,0 +1,89 @@
+use std::rc::Rc;
+
+use glow as GL;
+use glow::HasContext;
+use winit::dpi::PhysicalSize;
+
+use crate::config::Config;
+use crate::palette::Color;
+use crate::postprocess::Effects;
+
+// How long to take to blend from one color to the next, in milliseconds
+const BLEND_DURATION_MS: f64 = 60.0 * 1000.0;
+
+// A stand-in for Flux that fills the window with a single color, slowly shifting through the
+// palette of the color mode. There’s no simulation and no shader, just a clear, so the GPU can
+// stay almost idle.
+pub struct SolidColor {
+    gl: Rc<glow::Context>,
+    size: PhysicalSize<u32>,
+    colors: Vec<Color>,
+}
+
+impl SolidColor {
+    pub fn new(gl: &Rc<glow::Context>, size: PhysicalSize<u32>, config: &Config) -> Self {
+        // The desktop wallpaper has no palette to go through, so fall back to the background.
+        let colors = match config.flux.color_mode.palette() {
+            Some(colors) if !colors.is_empty() => colors.into_owned(),
+            _ => vec![config.screensaver.background_color],
+        };
+
+        Self {
+            gl: Rc::clone(gl),
+            size,
+            colors,
+        }
+    }
+
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.size = size;
+    }
+
+    // Fill `target` with the color for `timestamp`, in milliseconds.
+    pub fn render(&self, target: Option<GL::NativeFramebuffer>, timestamp: f64, effects: &Effects) {
+        let [r, g, b] = effects.apply(color_at(&self.colors, timestamp));
+        let gl = &self.gl;
+
+        unsafe {
+            gl.bind_framebuffer(GL::FRAMEBUFFER, target);
+            gl.viewport(0, 0, self.size.width as i32, self.size.height as i32);
+            gl.clear_color(r, g, b, 1.0);
+            gl.clear(GL::COLOR_BUFFER_BIT);
+        }
+    }
+}
+
+// Blend between neighbouring colors, going round the palette and back to the first one.
+fn color_at(colors: &[Color], timestamp: f64) -> Color {
+    let position = (timestamp.max(0.0) / BLEND_DURATION_MS) % colors.len() as f64;
+    let index = position.floor() as usize;
+    let t = position.fract() as f32;
+    let from = colors[index % colors.len()];
+    let to = colors[(index + 1) % colors.len()];
+
+    [
+        from[0] + (to[0] - from[0]) * t,
+        from[1] + (to[1] - from[1]) * t,
+        from[2] + (to[2] - from[2]) * t,
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const COLORS: [Color; 2] = [[0.0, 0.0, 0.0], [1.0, 0.5, 0.0]];
+
+    #[test]
+    fn it_blends_between_neighbouring_colors() {
+        assert_eq!(color_at(&COLORS, 0.0), [0.0, 0.0, 0.0]);
+        assert_eq!(color_at(&COLORS, BLEND_DURATION_MS / 2.0), [0.5, 0.25, 0.0]);
+        assert_eq!(color_at(&COLORS, BLEND_DURATION_MS), [1.0, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn it_goes_back_round_to_the_first_color() {
+        assert_eq!(color_at(&COLORS, BLEND_DURATION_MS * 2.0), [0.0, 0.0, 0.0]);
+        assert_eq!(color_at(&[[0.2, 0.4, 0.6]], 12345.0), [0.2, 0.4, 0.6]);
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// Counters for a single run of the screensaver, logged as a one-line summary on exit. Nothing
+// leaves the machine: it’s meant to be pasted into a bug report.
+pub struct SessionStats {
+    start: Instant,
+    frames: u64,
+    failed_frames: u64,
+    device_losses: u32,
+    // How each window presented its frames, without duplicates
+    presentations: Vec<String>,
+}
+
+impl SessionStats {
+    pub fn new(start: Instant) -> Self {
+        Self {
+            start,
+            frames: 0,
+            failed_frames: 0,
+            device_losses: 0,
+            presentations: Vec::new(),
+        }
+    }
+
+    pub fn frame_drawn(&mut self, failed: bool) {
+        self.frames += 1;
+        if failed {
+            self.failed_frames += 1;
+        }
+    }
+
+    // Count what happened to a window before it’s closed.
+    pub fn record_window(&mut self, presentation: String, device_losses: u32) {
+        self.device_losses += device_losses;
+        if !self.presentations.contains(&presentation) {
+            self.presentations.push(presentation);
+        }
+    }
+
+    pub fn summary(&self, now: Instant) -> Summary<'_> {
+        Summary {
+            stats: self,
+            elapsed: now.saturating_duration_since(self.start),
+        }
+    }
+}
+
+// How many times a window lost its graphics device. Every frame fails while the device is gone, so
+// a run of failed frames counts as a single loss.
+#[derive(Default)]
+pub struct DeviceLosses {
+    count: u32,
+    // Whether the last frame failed because the device was lost
+    lost: bool,
+}
+
+impl DeviceLosses {
+    // Record whether a frame failed because the device was lost. Returns true for the first frame
+    // of a new loss, which is when to recover.
+    pub fn frame_drawn(&mut self, device_lost: bool) -> bool {
+        let new_loss = device_lost && !self.lost;
+        if new_loss {
+            self.count += 1;
+        }
+        self.lost = device_lost;
+        new_loss
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+pub struct Summary<'a> {
+    stats: &'a SessionStats,
+    elapsed: Duration,
+}
+
+impl fmt::Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stats = self.stats;
+        let seconds = self.elapsed.as_secs_f64();
+        let average_fps = if seconds > 0.0 {
+            stats.frames as f64 / seconds
+        } else {
+            0.0
+        };
+        let presentation = if stats.presentations.is_empty() {
+            "none".to_string()
+        } else {
+            stats.presentations.join(", ")
+        };
+
+        write!(
+            f,
+            "{} frames in {:.1} s ({:.1} FPS), {} failed, {} device lost, presented with {}",
+            stats.frames,
+            seconds,
+            average_fps,
+            stats.failed_frames,
+            stats.device_losses,
+            presentation
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_summarizes_the_session_on_one_line() {
+        let start = Instant::now();
+        let mut stats = SessionStats::new(start);
+        for frame in 0..120 {
+            stats.frame_drawn(frame == 0);
+        }
+        stats.record_window("DXGI interop (texture)".to_string(), 1);
+        stats.record_window("DXGI interop (texture)".to_string(), 0);
+
+        assert_eq!(
+            stats.summary(start + Duration::from_secs(2)).to_string(),
+            "120 frames in 2.0 s (60.0 FPS), 1 failed, 1 device lost, presented with DXGI interop (texture)"
+        );
+    }
+
+    #[test]
+    fn it_counts_each_device_loss_once() {
+        let mut device_losses = DeviceLosses::default();
+        assert!(device_losses.frame_drawn(true));
+        for _ in 0..10 {
+            assert!(!device_losses.frame_drawn(true));
+        }
+        assert_eq!(device_losses.count(), 1);
+
+        device_losses.frame_drawn(false);
+        assert!(device_losses.frame_drawn(true));
+        assert_eq!(device_losses.count(), 2);
+    }
+}
@@ -0,0 +1,123 @@
+use std::path;
+use std::rc::Rc;
+
+use glow as GL;
+use glow::HasContext;
+use winit::dpi::PhysicalSize;
+
+use crate::config::Config;
+use crate::postprocess::Effects;
+use crate::renderer::FluxRenderer;
+
+// The simulation runs at this rate, whatever the size of the still.
+const FRAMES_PER_SECOND: f64 = 60.0;
+
+// Run Flux offscreen for `seconds` of simulated time and save the last frame as a PNG.
+//
+// The GL context should be current. Nothing is presented, so the window behind the context can
+// stay hidden.
+pub fn render_to_file(
+    gl: &Rc<glow::Context>,
+    config: &Config,
+    size: PhysicalSize<u32>,
+    seconds: f64,
+    wallpaper: Option<path::PathBuf>,
+    file: &path::Path,
+) -> Result<(), String> {
+    let mut renderer = FluxRenderer::for_window(gl, size, 1.0, config, wallpaper)?;
+
+    unsafe {
+        let texture = gl.create_texture()?;
+        let framebuffer = gl.create_framebuffer()?;
+        let result = attach_texture(gl, framebuffer, texture, size).and_then(|_| {
+            let frames = (seconds * FRAMES_PER_SECOND).ceil().max(1.0) as u64;
+            for frame in 0..=frames {
+                let timestamp = (frame as f64 * 1000.0 / FRAMES_PER_SECOND).min(seconds * 1000.0);
+                renderer.render(Some(framebuffer), timestamp, &Effects::default());
+            }
+
+            read_pixels(gl, framebuffer, size)
+        });
+
+        gl.delete_framebuffer(framebuffer);
+        gl.delete_texture(texture);
+
+        let image = result?;
+        image
+            .save(file)
+            .map_err(|err| format!("Failed to save the frame to {}: {}", file.display(), err))
+    }
+}
+
+// Back `framebuffer` with `texture`, so that Flux can draw into it without a window.
+pub unsafe fn attach_texture(
+    gl: &glow::Context,
+    framebuffer: GL::NativeFramebuffer,
+    texture: GL::NativeTexture,
+    size: PhysicalSize<u32>,
+) -> Result<(), String> {
+    gl.bind_texture(GL::TEXTURE_2D, Some(texture));
+    gl.tex_image_2d(
+        GL::TEXTURE_2D,
+        0,
+        GL::RGBA8 as i32,
+        size.width as i32,
+        size.height as i32,
+        0,
+        GL::RGBA,
+        GL::UNSIGNED_BYTE,
+        None,
+    );
+    gl.bind_texture(GL::TEXTURE_2D, None);
+
+    gl.bind_framebuffer(GL::FRAMEBUFFER, Some(framebuffer));
+    gl.framebuffer_texture_2d(
+        GL::FRAMEBUFFER,
+        GL::COLOR_ATTACHMENT0,
+        GL::TEXTURE_2D,
+        Some(texture),
+        0,
+    );
+    let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
+    gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+    if status != GL::FRAMEBUFFER_COMPLETE {
+        return Err(format!("Incomplete framebuffer: {:#x}", status));
+    }
+
+    Ok(())
+}
+
+// Read the frame back into an image, top row first.
+unsafe fn read_pixels(
+    gl: &glow::Context,
+    framebuffer: GL::NativeFramebuffer,
+    size: PhysicalSize<u32>,
+) -> Result<image::RgbaImage, String> {
+    let mut pixels = vec![0; size.width as usize * size.height as usize * 4];
+
+    gl.bind_framebuffer(GL::READ_FRAMEBUFFER, Some(framebuffer));
+    gl.pixel_store_i32(GL::PACK_ALIGNMENT, 1);
+    gl.read_pixels(
+        0,
+        0,
+        size.width as i32,
+        size.height as i32,
+        GL::RGBA,
+        GL::UNSIGNED_BYTE,
+        GL::PixelPackData::Slice(&mut pixels),
+    );
+    gl.bind_framebuffer(GL::READ_FRAMEBUFFER, None);
+
+    // A wallpaper can’t be see-through, whatever alpha Flux leaves behind.
+    for alpha in pixels.iter_mut().skip(3).step_by(4) {
+        *alpha = u8::MAX;
+    }
+
+    let mut image = image::RgbaImage::from_raw(size.width, size.height, pixels)
+        .ok_or("The frame doesn’t match its size")?;
+    // GL reads from the bottom row up.
+    image::imageops::flip_vertical_in_place(&mut image);
+
+    Ok(image)
+}
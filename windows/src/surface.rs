@@ -12,15 +12,18 @@ pub struct Surface {
     pub size: PhysicalSize<u32>,
     pub scale_factor: f64,
     pub wallpaper: Option<path::PathBuf>,
+    // The device name of the monitor, if the surface covers just the one
+    pub monitor: Option<String>,
 }
 
 impl Surface {
     fn from_monitor(monitor: &MonitorHandle, wallpaper: &Option<path::PathBuf>) -> Self {
         Self {
             position: monitor.position(),
-            size: monitor.size(),
+            size: upright_size(monitor),
             scale_factor: monitor.scale_factor(),
             wallpaper: wallpaper.clone(),
+            monitor: monitor.name(),
         }
     }
 
@@ -42,6 +45,7 @@ impl Surface {
         );
 
         self.position = top_left;
+        self.monitor = None;
         self.size = PhysicalSize::new(
             top_left.x.abs_diff(bottom_right.x),
             top_left.y.abs_diff(bottom_right.y),
@@ -49,6 +53,51 @@ impl Surface {
     }
 }
 
+// Windows composites windowed swapchains onto rotated displays itself, so Flux renders upright as
+// long as the window is laid out like the desktop. Some drivers report a rotated display by its
+// unrotated mode, which leaves the window on its side, so check against the current mode.
+#[cfg(windows)]
+fn upright_size(monitor: &MonitorHandle) -> PhysicalSize<u32> {
+    use crate::platform::windows::orientation;
+
+    let reported_size = monitor.size();
+    let mode = match monitor
+        .name()
+        .and_then(|name| orientation::current_mode(&name))
+    {
+        Some(mode) => mode,
+        None => return reported_size,
+    };
+    if mode.rotation != orientation::Rotation::None {
+        log::debug!(
+            "{} is rotated by {}",
+            monitor.name().unwrap_or_default(),
+            mode.rotation
+        );
+    }
+
+    upright(reported_size, mode.size, mode.rotation.is_sideways())
+}
+
+#[cfg(not(windows))]
+fn upright_size(monitor: &MonitorHandle) -> PhysicalSize<u32> {
+    monitor.size()
+}
+
+// Swap the width and height of a sideways display reported by its unrotated size.
+fn upright(
+    reported_size: PhysicalSize<u32>,
+    mode_size: PhysicalSize<u32>,
+    is_sideways: bool,
+) -> PhysicalSize<u32> {
+    let transposed = PhysicalSize::new(reported_size.height, reported_size.width);
+    if is_sideways && reported_size != mode_size && transposed == mode_size {
+        mode_size
+    } else {
+        reported_size
+    }
+}
+
 fn from_monitors(monitors: &[(MonitorHandle, Option<path::PathBuf>)]) -> Vec<Surface> {
     monitors
         .iter()
@@ -88,7 +137,8 @@ pub fn build(
 
     use config::FillMode;
     match fill_mode {
-        FillMode::None => surfaces,
+        // Each display gets its own window. The frame is shared between them.
+        FillMode::None | FillMode::Mirror => surfaces,
         FillMode::Span => extend(surfaces),
         FillMode::Fill => fill(surfaces),
     }
@@ -105,12 +155,14 @@ mod test {
             size: (3360, 2100).into(),
             scale_factor: 1.0,
             wallpaper: None,
+            monitor: None,
         };
         let display1 = Surface {
             position: (3360, 0).into(),
             size: (2560, 1440).into(),
             scale_factor: 1.0,
             wallpaper: None,
+            monitor: None,
         };
 
         assert_eq!(
@@ -119,6 +171,18 @@ mod test {
         );
     }
 
+    #[test]
+    fn it_turns_sideways_displays_upright() {
+        let portrait = PhysicalSize::new(1080, 1920);
+        let landscape = PhysicalSize::new(1920, 1080);
+
+        assert_eq!(upright(landscape, portrait, true), portrait);
+        assert_eq!(upright(portrait, portrait, true), portrait);
+        assert_eq!(upright(landscape, landscape, false), landscape);
+        // Don’t trust a mode that doesn’t match the display at all.
+        assert_eq!(upright(landscape, (800, 600).into(), true), landscape);
+    }
+
     #[test]
     fn it_fills_all_displays() {
         let display0 = Surface {
@@ -126,12 +190,14 @@ mod test {
             size: (1920, 1080).into(),
             scale_factor: 1.0,
             wallpaper: None,
+            monitor: None,
         };
         let display1 = Surface {
             position: (1420, 0).into(),
             size: (2560, 1440).into(),
             scale_factor: 1.0,
             wallpaper: None,
+            monitor: None,
         };
         assert_eq!(
             fill(vec![display0, display1]),
@@ -140,6 +206,7 @@ mod test {
                 size: (3980, 1440).into(),
                 scale_factor: 1.0,
                 wallpaper: None,
+                monitor: None,
             }]
         );
     }
@@ -0,0 +1,124 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Catches a screensaver that never gets a frame on screen, like when a buggy driver hangs while
+// creating the swapchain or presenting. Without it, the user is left with a black screen that no
+// input can close.
+//
+// The main loop asks `is_overdue` before drawing and falls back to plain OpenGL once the timeout
+// has passed. If the main loop itself is stuck, a background thread calls `on_hang` after twice
+// the timeout instead.
+pub struct FirstFrameWatchdog {
+    deadline: Option<Instant>,
+    // Whether the main loop already fell back after missing the deadline
+    fallback_tried: bool,
+    // Whether the first frame was recorded, shared with the background thread
+    presented: Arc<AtomicBool>,
+}
+
+impl FirstFrameWatchdog {
+    // Expect the first frame by `first_frame + timeout`. A zero timeout disables the watchdog.
+    pub fn start(
+        first_frame: Instant,
+        timeout: Duration,
+        on_hang: impl FnOnce() + Send + 'static,
+    ) -> Self {
+        let presented = Arc::new(AtomicBool::new(false));
+        if timeout.is_zero() {
+            return Self {
+                deadline: None,
+                fallback_tried: false,
+                presented,
+            };
+        }
+
+        let hang_deadline = first_frame + timeout * 2;
+        let thread_presented = Arc::clone(&presented);
+        let spawned = thread::Builder::new()
+            .name("first frame watchdog".to_string())
+            .spawn(move || {
+                thread::sleep(hang_deadline.saturating_duration_since(Instant::now()));
+                if !thread_presented.load(Ordering::Acquire) {
+                    on_hang();
+                }
+            });
+        if let Err(err) = spawned {
+            log::warn!("Failed to start the first frame watchdog: {}", err);
+        }
+
+        Self {
+            deadline: Some(first_frame + timeout),
+            fallback_tried: false,
+            presented,
+        }
+    }
+
+    // Record a presented frame. Frames from the fallback count too, so the background thread
+    // leaves a screensaver alone once the fallback gets something on screen.
+    pub fn frame_presented(&mut self) {
+        if self.deadline.take().is_some() {
+            self.presented.store(true, Ordering::Release);
+        }
+    }
+
+    // Whether the first frame should have been presented by `now`. This is only true once, so
+    // that the fallback is only tried once.
+    pub fn is_overdue(&mut self, now: Instant) -> bool {
+        match self.deadline {
+            Some(deadline) if now >= deadline && !self.fallback_tried => {
+                self.fallback_tried = true;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn it_is_overdue_once_without_a_frame() {
+        let start = Instant::now();
+        let mut watchdog = FirstFrameWatchdog::start(start, TIMEOUT, || ());
+
+        assert!(!watchdog.is_overdue(start + TIMEOUT / 2));
+        assert!(watchdog.is_overdue(start + TIMEOUT));
+        assert!(!watchdog.is_overdue(start + TIMEOUT * 2));
+    }
+
+    #[test]
+    fn it_stays_quiet_once_a_frame_is_presented() {
+        let start = Instant::now();
+        let mut watchdog = FirstFrameWatchdog::start(start, TIMEOUT, || ());
+        watchdog.frame_presented();
+
+        assert!(!watchdog.is_overdue(start + TIMEOUT));
+        assert!(watchdog.presented.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn it_records_a_frame_presented_after_the_fallback() {
+        let start = Instant::now();
+        let mut watchdog = FirstFrameWatchdog::start(start, TIMEOUT, || ());
+
+        assert!(watchdog.is_overdue(start + TIMEOUT));
+        watchdog.frame_presented();
+
+        assert!(watchdog.presented.load(Ordering::Acquire));
+        assert!(!watchdog.is_overdue(start + TIMEOUT * 2));
+    }
+
+    #[test]
+    fn it_does_nothing_without_a_timeout() {
+        let start = Instant::now();
+        let mut watchdog = FirstFrameWatchdog::start(start, Duration::ZERO, || ());
+
+        assert!(!watchdog.is_overdue(start + TIMEOUT));
+    }
+}
@@ -0,0 +1,247 @@
+use std::path::Path;
+use std::rc::Rc;
+
+use glow as GL;
+use glow::HasContext;
+use winit::dpi::PhysicalSize;
+
+use crate::config::Corner;
+use crate::postprocess::create_program;
+
+// The gap between the image and the edges of the frame, in screen pixels
+const MARGIN: u32 = 16;
+
+// Images larger than this fraction of the frame are scaled down to fit
+const MAX_FRACTION: f32 = 0.25;
+
+// An image, like a logo, drawn in a corner on top of every frame.
+pub struct Watermark {
+    gl: Rc<glow::Context>,
+    program: GL::NativeProgram,
+    vertex_array: GL::NativeVertexArray,
+    texture: GL::NativeTexture,
+    rect_location: Option<GL::NativeUniformLocation>,
+    opacity_location: Option<GL::NativeUniformLocation>,
+    image_size: PhysicalSize<u32>,
+    corner: Corner,
+    opacity: f32,
+}
+
+const VERTEX_SHADER: &str = r#"
+out vec2 v_uv;
+
+// The bottom-left corner and size of the image in clip space
+uniform vec4 u_rect;
+
+void main() {
+    // Draw a quad as a triangle strip.
+    vec2 position = vec2(float(gl_VertexID & 1), float((gl_VertexID >> 1) & 1));
+    v_uv = vec2(position.x, 1.0 - position.y);
+    gl_Position = vec4(u_rect.xy + position * u_rect.zw, 0.0, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+in vec2 v_uv;
+out vec4 out_color;
+
+uniform sampler2D u_image;
+uniform float u_opacity;
+
+void main() {
+    vec4 color = texture(u_image, v_uv);
+    out_color = vec4(color.rgb, color.a * u_opacity);
+}
+"#;
+
+impl Watermark {
+    pub fn new(
+        gl: &Rc<glow::Context>,
+        path: &Path,
+        corner: Corner,
+        opacity: f32,
+    ) -> Result<Self, String> {
+        // Decode first, so that a bad file doesn’t leave any GL objects behind.
+        let image = image::open(path)
+            .map_err(|err| format!("Can't load the watermark {}: {}", path.display(), err))?
+            .into_rgba8();
+        let image_size = PhysicalSize::new(image.width(), image.height());
+
+        unsafe {
+            let program = create_program(gl, VERTEX_SHADER, FRAGMENT_SHADER)?;
+            let vertex_array = gl.create_vertex_array()?;
+            let texture = gl.create_texture()?;
+
+            gl.use_program(Some(program));
+            gl.uniform_1_i32(gl.get_uniform_location(program, "u_image").as_ref(), 0);
+            let rect_location = gl.get_uniform_location(program, "u_rect");
+            let opacity_location = gl.get_uniform_location(program, "u_opacity");
+            gl.use_program(None);
+
+            gl.bind_texture(GL::TEXTURE_2D, Some(texture));
+            gl.tex_image_2d(
+                GL::TEXTURE_2D,
+                0,
+                GL::RGBA8 as i32,
+                image_size.width as i32,
+                image_size.height as i32,
+                0,
+                GL::RGBA,
+                GL::UNSIGNED_BYTE,
+                Some(image.as_raw()),
+            );
+            gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
+            gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::LINEAR as i32);
+            gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+            gl.tex_parameter_i32(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+            gl.bind_texture(GL::TEXTURE_2D, None);
+
+            Ok(Self {
+                gl: Rc::clone(gl),
+                program,
+                vertex_array,
+                texture,
+                rect_location,
+                opacity_location,
+                image_size,
+                corner,
+                opacity,
+            })
+        }
+    }
+
+    // Draw the image over whatever is already in `target`, moved by `shift` pixels along with the
+    // rest of the picture.
+    pub fn draw(
+        &self,
+        target: Option<GL::NativeFramebuffer>,
+        size: PhysicalSize<u32>,
+        shift: (i32, i32),
+    ) {
+        let (x, y, width, height) = match placement(self.image_size, size, self.corner) {
+            Some(rect) => rect,
+            None => return,
+        };
+
+        let gl = &self.gl;
+        let to_clip_x = |x: f32| 2.0 * x / size.width as f32;
+        let to_clip_y = |y: f32| 2.0 * y / size.height as f32;
+
+        unsafe {
+            gl.bind_framebuffer(GL::FRAMEBUFFER, target);
+            gl.viewport(shift.0, shift.1, size.width as i32, size.height as i32);
+
+            let blend_enabled = gl.is_enabled(GL::BLEND);
+            gl.enable(GL::BLEND);
+            // Leave the destination alpha alone, like the overlay.
+            gl.blend_func_separate(GL::SRC_ALPHA, GL::ONE_MINUS_SRC_ALPHA, GL::ZERO, GL::ONE);
+
+            gl.use_program(Some(self.program));
+            gl.uniform_4_f32(
+                self.rect_location.as_ref(),
+                to_clip_x(x) - 1.0,
+                to_clip_y(y) - 1.0,
+                to_clip_x(width),
+                to_clip_y(height),
+            );
+            gl.uniform_1_f32(self.opacity_location.as_ref(), self.opacity);
+
+            gl.active_texture(GL::TEXTURE0);
+            gl.bind_texture(GL::TEXTURE_2D, Some(self.texture));
+            gl.bind_vertex_array(Some(self.vertex_array));
+            gl.draw_arrays(GL::TRIANGLE_STRIP, 0, 4);
+
+            gl.bind_vertex_array(None);
+            gl.bind_texture(GL::TEXTURE_2D, None);
+            gl.use_program(None);
+
+            gl.blend_func(GL::ONE, GL::ZERO);
+            if !blend_enabled {
+                gl.disable(GL::BLEND);
+            }
+        }
+    }
+}
+
+impl Drop for Watermark {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.delete_program(self.program);
+            self.gl.delete_vertex_array(self.vertex_array);
+            self.gl.delete_texture(self.texture);
+        }
+    }
+}
+
+// Where to draw an image in a frame, as the bottom-left corner and size in pixels, counting from
+// the bottom left like GL does. Large images are scaled down, keeping their aspect ratio.
+fn placement(
+    image_size: PhysicalSize<u32>,
+    frame_size: PhysicalSize<u32>,
+    corner: Corner,
+) -> Option<(f32, f32, f32, f32)> {
+    if image_size.width == 0 || image_size.height == 0 {
+        return None;
+    }
+    if frame_size.width <= 2 * MARGIN || frame_size.height <= 2 * MARGIN {
+        return None;
+    }
+
+    let (image_width, image_height) = (image_size.width as f32, image_size.height as f32);
+    let (frame_width, frame_height) = (frame_size.width as f32, frame_size.height as f32);
+    let fit = (frame_width * MAX_FRACTION / image_width)
+        .min(frame_height * MAX_FRACTION / image_height)
+        .min(1.0);
+    let (width, height) = (image_width * fit, image_height * fit);
+
+    let margin = MARGIN as f32;
+    let left = margin;
+    let right = frame_width - margin - width;
+    let bottom = margin;
+    let top = frame_height - margin - height;
+
+    Some(match corner {
+        Corner::TopLeft => (left, top, width, height),
+        Corner::TopRight => (right, top, width, height),
+        Corner::BottomLeft => (left, bottom, width, height),
+        Corner::BottomRight => (right, bottom, width, height),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_places_small_images_at_full_size() {
+        let rect = placement(
+            PhysicalSize::new(100, 50),
+            PhysicalSize::new(1920, 1080),
+            Corner::BottomRight,
+        );
+
+        assert_eq!(rect, Some((1804.0, 16.0, 100.0, 50.0)));
+    }
+
+    #[test]
+    fn it_scales_large_images_down_to_fit() {
+        let rect = placement(
+            PhysicalSize::new(1920, 1080),
+            PhysicalSize::new(1920, 1080),
+            Corner::TopLeft,
+        );
+
+        assert_eq!(rect, Some((16.0, 1080.0 - 16.0 - 270.0, 480.0, 270.0)));
+    }
+
+    #[test]
+    fn it_skips_frames_too_small_for_the_margins() {
+        let rect = placement(
+            PhysicalSize::new(10, 10),
+            PhysicalSize::new(20, 20),
+            Corner::BottomLeft,
+        );
+
+        assert_eq!(rect, None);
+    }
+}
@@ -7,6 +7,9 @@ use sdl2::VideoSubsystem;
 
 use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
 
+#[cfg(windows)]
+use windows::Win32::Foundation::HWND;
+
 use winit::dpi::PhysicalSize;
 pub use winit::monitor::MonitorHandle;
 use winit::platform_impl::platform;
@@ -18,11 +21,37 @@ pub trait HasWinitWindow {
 }
 
 impl HasWinitWindow for Window {
+    // Ask Windows directly, so that we always get the size in physical pixels.
+    #[cfg(windows)]
+    fn inner_size(&self) -> PhysicalSize<u32> {
+        match self.raw_window_handle() {
+            RawWindowHandle::Win32(handle) => {
+                crate::platform::windows::window::client_size(HWND(handle.hwnd as _))
+            }
+            _ => {
+                let (w, h) = self.size();
+                PhysicalSize::new(w, h)
+            }
+        }
+    }
+
+    #[cfg(not(windows))]
     fn inner_size(&self) -> PhysicalSize<u32> {
         let (w, h) = self.size();
         PhysicalSize::new(w, h)
     }
 
+    #[cfg(windows)]
+    fn scale_factor(&self) -> f64 {
+        match self.raw_window_handle() {
+            RawWindowHandle::Win32(handle) => {
+                crate::platform::windows::window::scale_factor(HWND(handle.hwnd as _))
+            }
+            _ => 1.0,
+        }
+    }
+
+    #[cfg(not(windows))]
     fn scale_factor(&self) -> f64 {
         let id = self.display_index().unwrap();
         self.subsystem().display_dpi(id).unwrap().0 as f64 / 96.0
@@ -58,6 +87,13 @@ impl HasMonitors for VideoSubsystem {
     }
 }
 
+// The connected monitors, for code that runs without an SDL video subsystem.
+pub fn available_monitors() -> impl Iterator<Item = MonitorHandle> {
+    platform::monitor::available_monitors()
+        .into_iter()
+        .map(|inner| MonitorHandle { inner })
+}
+
 /// [`winit::dpi::PhysicalSize<u32>`] non-zero extensions.
 pub trait NonZeroU32PhysicalSize {
     /// Converts to non-zero `(width, height)`.